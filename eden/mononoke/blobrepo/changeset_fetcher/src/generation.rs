@@ -0,0 +1,72 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::sync::Mutex;
+
+use anyhow::Error;
+use context::CoreContext;
+use lru_cache::LruCache;
+use mononoke_types::ChangesetId;
+use mononoke_types::Generation;
+
+use crate::ChangesetFetcher;
+
+/// A bounded cache of recently looked up generation numbers. Generation numbers never change
+/// once a changeset exists, so entries never need to be invalidated.
+pub struct GenerationCache {
+    cache: Mutex<LruCache<ChangesetId, Generation>>,
+}
+
+impl GenerationCache {
+    /// Create a cache holding at most `capacity` generation numbers.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            cache: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    pub fn get(&self, cs_id: &ChangesetId) -> Option<Generation> {
+        self.cache
+            .lock()
+            .expect("lock poisoned")
+            .get_mut(cs_id)
+            .copied()
+    }
+
+    pub fn insert(&self, cs_id: ChangesetId, gen: Generation) {
+        self.cache.lock().expect("lock poisoned").insert(cs_id, gen);
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.lock().expect("lock poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Look up the generation number of `cs_id`, consulting `cache` first if one is given, and
+/// recording the result in it.
+pub async fn generation_for(
+    ctx: &CoreContext,
+    changeset_fetcher: &dyn ChangesetFetcher,
+    cache: Option<&GenerationCache>,
+    cs_id: ChangesetId,
+) -> Result<Generation, Error> {
+    if let Some(gen) = cache.and_then(|cache| cache.get(&cs_id)) {
+        return Ok(gen);
+    }
+
+    let gen = changeset_fetcher
+        .get_generation_number(ctx.clone(), cs_id)
+        .await?;
+    if let Some(cache) = cache {
+        cache.insert(cs_id, gen);
+    }
+    Ok(gen)
+}
@@ -18,7 +18,10 @@ use mononoke_types::ChangesetId;
 use mononoke_types::Generation;
 use mononoke_types::RepositoryId;
 
+mod generation;
 mod prefetched;
+pub use generation::generation_for;
+pub use generation::GenerationCache;
 pub use prefetched::PrefetchedChangesetsFetcher;
 
 #[cfg(test)]
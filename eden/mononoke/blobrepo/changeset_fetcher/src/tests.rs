@@ -5,9 +5,15 @@
  * GNU General Public License version 2.
  */
 
+use std::collections::HashMap;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
+use anyhow::format_err;
+use anyhow::Error;
 use anyhow::Result;
+use async_trait::async_trait;
 use changesets::ChangesetEntry;
 use changesets::ChangesetInsert;
 use changesets::Changesets;
@@ -15,13 +21,16 @@ use changesets_impl::SqlChangesetsBuilder;
 use context::CoreContext;
 use fbinit::FacebookInit;
 use futures::stream;
+use mononoke_types::ChangesetId;
 use mononoke_types::Generation;
 use mononoke_types_mocks::changesetid::*;
 use mononoke_types_mocks::repo::*;
 use rendezvous::RendezVousOptions;
 use sql_construct::SqlConstruct;
 
+use super::generation_for;
 use super::ChangesetFetcher;
+use super::GenerationCache;
 use super::PrefetchedChangesetsFetcher;
 
 #[fbinit::test]
@@ -185,3 +194,91 @@ async fn test_prefetched_fetcher_overlap(fb: FacebookInit) -> Result<()> {
 
     Ok(())
 }
+
+/// A fetcher that knows only generation numbers, and counts how often it is asked for them.
+struct CountingGenerationFetcher {
+    generations: HashMap<ChangesetId, Generation>,
+    get_gen_number_count: AtomicUsize,
+}
+
+#[async_trait]
+impl ChangesetFetcher for CountingGenerationFetcher {
+    async fn get_generation_number(
+        &self,
+        _ctx: CoreContext,
+        cs_id: ChangesetId,
+    ) -> Result<Generation, Error> {
+        self.get_gen_number_count.fetch_add(1, Ordering::Relaxed);
+        self.generations
+            .get(&cs_id)
+            .copied()
+            .ok_or_else(|| format_err!("{} not found", cs_id))
+    }
+
+    async fn get_parents(
+        &self,
+        _ctx: CoreContext,
+        cs_id: ChangesetId,
+    ) -> Result<Vec<ChangesetId>, Error> {
+        Err(format_err!("no parents for {}", cs_id))
+    }
+}
+
+#[fbinit::test]
+async fn test_generation_for(fb: FacebookInit) -> Result<()> {
+    let ctx = CoreContext::test_mock(fb);
+    let fetcher = CountingGenerationFetcher {
+        generations: HashMap::from([
+            (ONES_CSID, Generation::new(1)),
+            (TWOS_CSID, Generation::new(2)),
+            (THREES_CSID, Generation::new(3)),
+        ]),
+        get_gen_number_count: AtomicUsize::new(0),
+    };
+    let count = || fetcher.get_gen_number_count.load(Ordering::Relaxed);
+
+    // Without a cache, every lookup goes to the fetcher.
+    assert_eq!(
+        generation_for(&ctx, &fetcher, None, TWOS_CSID).await?,
+        Generation::new(2)
+    );
+    assert_eq!(
+        generation_for(&ctx, &fetcher, None, TWOS_CSID).await?,
+        Generation::new(2)
+    );
+    assert_eq!(count(), 2);
+
+    // With a cache, a repeated lookup is answered from the cache.
+    let cache = GenerationCache::new(2);
+    assert_eq!(
+        generation_for(&ctx, &fetcher, Some(&cache), TWOS_CSID).await?,
+        Generation::new(2)
+    );
+    assert_eq!(count(), 3);
+    assert_eq!(
+        generation_for(&ctx, &fetcher, Some(&cache), TWOS_CSID).await?,
+        Generation::new(2)
+    );
+    assert_eq!(count(), 3);
+
+    // The cache is bounded, so the least recently used entry is evicted.
+    generation_for(&ctx, &fetcher, Some(&cache), ONES_CSID).await?;
+    generation_for(&ctx, &fetcher, Some(&cache), THREES_CSID).await?;
+    assert_eq!(cache.len(), 2);
+    assert_eq!(count(), 5);
+    assert_eq!(
+        generation_for(&ctx, &fetcher, Some(&cache), TWOS_CSID).await?,
+        Generation::new(2)
+    );
+    assert_eq!(count(), 6);
+
+    // Failed lookups aren't cached.
+    assert!(
+        generation_for(&ctx, &fetcher, Some(&cache), FOURS_CSID)
+            .await
+            .is_err()
+    );
+    assert_eq!(cache.get(&FOURS_CSID), None);
+
+    Ok(())
+}
@@ -0,0 +1,275 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use blobstore::Blobstore;
+use blobstore::BlobstoreGetData;
+use blobstore::CountedBlobstore;
+use lock_ext::LockExt;
+use lru_cache::LruCache;
+
+use crate::dummy::DummyLease;
+use crate::locking_cache::CacheBlobstore;
+use crate::locking_cache::CacheOps;
+
+/// How large an `LruCacheOps` cache may grow before it starts evicting the least recently used
+/// entries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LruCacheLimit {
+    /// Hold at most this many blobs.
+    Entries(usize),
+    /// Hold at most this many bytes of blob data. Blobs larger than this are never cached.
+    Bytes(u64),
+}
+
+struct LruCacheState {
+    cache: LruCache<String, BlobstoreGetData>,
+    bytes: u64,
+}
+
+/// A caching layer over an existing blobstore, backed by an in-process LRU cache.
+#[derive(Clone)]
+pub struct LruCacheOps {
+    limit: LruCacheLimit,
+    state: Arc<Mutex<LruCacheState>>,
+}
+
+impl std::fmt::Display for LruCacheOps {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "LruCacheOps")
+    }
+}
+
+impl fmt::Debug for LruCacheOps {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (entries, bytes) = self.state.with(|state| (state.cache.len(), state.bytes));
+        f.debug_struct("LruCacheOps")
+            .field("limit", &self.limit)
+            .field("entries", &entries)
+            .field("bytes", &bytes)
+            .finish()
+    }
+}
+
+impl LruCacheOps {
+    pub fn new(limit: LruCacheLimit) -> Self {
+        Self {
+            limit,
+            state: Arc::new(Mutex::new(LruCacheState {
+                // Eviction is done by hand, so that the size in bytes can be kept up to date.
+                cache: LruCache::new(usize::MAX),
+                bytes: 0,
+            })),
+        }
+    }
+
+    fn is_full(&self, state: &LruCacheState, incoming: u64) -> bool {
+        match self.limit {
+            LruCacheLimit::Entries(max) => state.cache.len() >= max,
+            LruCacheLimit::Bytes(max) => state.bytes + incoming > max,
+        }
+    }
+}
+
+pub fn new_lru_blobstore<T>(
+    blobstore: T,
+    limit: LruCacheLimit,
+) -> CountedBlobstore<CacheBlobstore<LruCacheOps, DummyLease, T>>
+where
+    T: Blobstore,
+{
+    CountedBlobstore::new(
+        "lru".to_string(),
+        CacheBlobstore::new(LruCacheOps::new(limit), DummyLease {}, blobstore, false),
+    )
+}
+
+#[async_trait]
+impl CacheOps for LruCacheOps {
+    const CACHE_NAME: &'static str = "lru";
+
+    async fn get(&self, key: &str) -> Option<BlobstoreGetData> {
+        self.state
+            .with(|state| state.cache.get_mut(key).map(|value| value.clone()))
+    }
+
+    async fn put(&self, key: &str, value: BlobstoreGetData) {
+        let size = value.len() as u64;
+        if let LruCacheLimit::Bytes(max) = self.limit {
+            if size > max {
+                return;
+            }
+        }
+        self.state.with(|state| {
+            if let Some(old) = state.cache.remove(key) {
+                state.bytes -= old.len() as u64;
+            }
+            while !state.cache.is_empty() && self.is_full(state, size) {
+                if let Some((_, evicted)) = state.cache.remove_lru() {
+                    state.bytes -= evicted.len() as u64;
+                }
+            }
+            if !self.is_full(state, size) {
+                state.bytes += size;
+                state.cache.insert(key.to_owned(), value);
+            }
+        })
+    }
+
+    async fn check_present(&self, key: &str) -> bool {
+        self.state.with(|state| state.cache.contains_key(key))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use blobstore::BlobstoreBytes;
+    use blobstore::BlobstoreIsPresent;
+    use borrowed::borrowed;
+    use context::CoreContext;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
+
+    use super::*;
+
+    /// A blobstore that counts the calls that reach it.
+    #[derive(Clone, Debug, Default)]
+    struct CountingBlobstore {
+        inner: Memblob,
+        gets: Arc<AtomicUsize>,
+        is_presents: Arc<AtomicUsize>,
+    }
+
+    impl std::fmt::Display for CountingBlobstore {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "CountingBlobstore")
+        }
+    }
+
+    #[async_trait]
+    impl Blobstore for CountingBlobstore {
+        async fn get<'a>(
+            &'a self,
+            ctx: &'a CoreContext,
+            key: &'a str,
+        ) -> Result<Option<BlobstoreGetData>> {
+            self.gets.fetch_add(1, Ordering::Relaxed);
+            self.inner.get(ctx, key).await
+        }
+
+        async fn put<'a>(
+            &'a self,
+            ctx: &'a CoreContext,
+            key: String,
+            value: BlobstoreBytes,
+        ) -> Result<()> {
+            self.inner.put(ctx, key, value).await
+        }
+
+        async fn is_present<'a>(
+            &'a self,
+            ctx: &'a CoreContext,
+            key: &'a str,
+        ) -> Result<BlobstoreIsPresent> {
+            self.is_presents.fetch_add(1, Ordering::Relaxed);
+            self.inner.is_present(ctx, key).await
+        }
+    }
+
+    fn value(data: &'static str) -> BlobstoreGetData {
+        BlobstoreBytes::from_bytes(data).into()
+    }
+
+    #[fbinit::test]
+    async fn test_second_get_is_cached(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let inner = CountingBlobstore::default();
+        inner
+            .inner
+            .put(ctx, "foo".to_owned(), BlobstoreBytes::from_bytes("foo"))
+            .await
+            .unwrap();
+        let cache = LruCacheOps::new(LruCacheLimit::Entries(10));
+        let blobstore = CacheBlobstore::new(cache.clone(), DummyLease {}, inner.clone(), false);
+
+        let first = blobstore.get(ctx, "foo").await.unwrap();
+        assert_eq!(inner.gets.load(Ordering::Relaxed), 1);
+        // The cache is filled in the background after a miss.
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while CacheOps::get(&cache, "foo").await.is_none() {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("cache was not filled after a miss");
+        let second = blobstore.get(ctx, "foo").await.unwrap();
+        assert_eq!(inner.gets.load(Ordering::Relaxed), 1);
+        assert_eq!(first, second);
+
+        // Puts write through, and fill the cache.
+        blobstore
+            .put(ctx, "bar".to_owned(), BlobstoreBytes::from_bytes("bar"))
+            .await
+            .unwrap();
+        assert!(inner.inner.get(ctx, "bar").await.unwrap().is_some());
+        assert!(
+            blobstore
+                .is_present(ctx, "bar")
+                .await
+                .unwrap()
+                .assume_not_found_if_unsure()
+        );
+        assert!(blobstore.get(ctx, "bar").await.unwrap().is_some());
+        assert_eq!(inner.gets.load(Ordering::Relaxed), 1);
+        assert_eq!(inner.is_presents.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_evicts_least_recently_used() {
+        let cache = LruCacheOps::new(LruCacheLimit::Entries(2));
+        cache.put("a", value("a")).await;
+        cache.put("b", value("b")).await;
+        // Using "a" makes "b" the oldest entry.
+        assert!(cache.get("a").await.is_some());
+        cache.put("c", value("c")).await;
+        assert!(cache.check_present("a").await);
+        assert!(!cache.check_present("b").await);
+        assert!(cache.check_present("c").await);
+    }
+
+    #[tokio::test]
+    async fn test_byte_limit() {
+        let cache = LruCacheOps::new(LruCacheLimit::Bytes(10));
+        cache.put("a", value("aaaa")).await;
+        cache.put("b", value("bbbb")).await;
+        cache.put("c", value("cccc")).await;
+        assert!(!cache.check_present("a").await);
+        assert!(cache.check_present("b").await);
+        assert!(cache.check_present("c").await);
+
+        // Replacing an entry accounts for the old value's size.
+        cache.put("c", value("cc")).await;
+        cache.put("d", value("dddd")).await;
+        assert!(cache.check_present("b").await);
+        assert!(cache.check_present("d").await);
+
+        // Blobs bigger than the whole cache are not cached.
+        cache.put("e", value("eeeeeeeeeee")).await;
+        assert!(!cache.check_present("e").await);
+        assert!(cache.check_present("b").await);
+    }
+}
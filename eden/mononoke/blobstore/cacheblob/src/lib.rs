@@ -12,6 +12,11 @@ pub use crate::cachelib_cache::CachelibBlobstoreOptions;
 
 pub mod dummy;
 
+mod in_process_cache;
+pub use crate::in_process_cache::new_lru_blobstore;
+pub use crate::in_process_cache::LruCacheLimit;
+pub use crate::in_process_cache::LruCacheOps;
+
 mod in_process_lease;
 pub use in_process_lease::InProcessLease;
 
@@ -0,0 +1,22 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::io;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ErrorKind {
+    #[error("Blob {key} decompressed to {actual} bytes, expected {expected}")]
+    LengthMismatch {
+        key: String,
+        expected: u64,
+        actual: u64,
+    },
+    #[error("Blob {0} could not be decompressed")]
+    Decompress(String, #[source] io::Error),
+}
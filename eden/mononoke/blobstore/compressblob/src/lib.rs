@@ -0,0 +1,351 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::io::Read;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use blobstore::Blobstore;
+use blobstore::BlobstoreGetData;
+use blobstore::BlobstoreIsPresent;
+use blobstore::BlobstorePutOps;
+use blobstore::OverwriteStatus;
+use blobstore::PutBehaviour;
+use bytes::Bytes;
+use context::CoreContext;
+use mononoke_types::BlobstoreBytes;
+
+mod errors;
+pub use crate::errors::ErrorKind;
+
+/// Marks a value as written by a `CompressBlobstore`.
+const MAGIC: &[u8; 4] = b"\0MCZ";
+/// Magic, then a codec byte, then the uncompressed length as a big-endian u64.
+const HEADER_LEN: usize = MAGIC.len() + 1 + 8;
+
+/// The value is stored as-is, because compressing it didn't make it smaller.
+const CODEC_RAW: u8 = 0;
+const CODEC_ZSTD: u8 = 1;
+
+/// Every zstd frame starts with these bytes.
+const ZSTD_FRAME_MAGIC: &[u8; 4] = b"\x28\xb5\x2f\xfd";
+
+/// A layer over an existing blobstore that compresses values with zstd on `put`, and
+/// decompresses them on `get`. Each stored value is prefixed with a header identifying the
+/// codec and the uncompressed length. Values without a well-formed header (written before this
+/// layer was added) are returned as they are, even if they happen to start with the magic.
+#[derive(Clone, Debug)]
+pub struct CompressBlobstore<T> {
+    blobstore: T,
+    zstd_level: i32,
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for CompressBlobstore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CompressBlobstore<{}>", &self.blobstore)
+    }
+}
+
+impl<T> CompressBlobstore<T> {
+    pub fn new(blobstore: T, zstd_level: i32) -> Self {
+        Self {
+            blobstore,
+            zstd_level,
+        }
+    }
+
+    fn compress(&self, value: BlobstoreBytes) -> Result<BlobstoreBytes> {
+        let value = value.into_bytes();
+        let compressed = zstd::bulk::compress(&value, self.zstd_level)?;
+        let (codec, payload) = if compressed.len() < value.len() {
+            (CODEC_ZSTD, &compressed[..])
+        } else {
+            (CODEC_RAW, &value[..])
+        };
+
+        let mut stored = Vec::with_capacity(HEADER_LEN + payload.len());
+        stored.extend_from_slice(MAGIC);
+        stored.push(codec);
+        stored.extend_from_slice(&(value.len() as u64).to_be_bytes());
+        stored.extend_from_slice(payload);
+        Ok(BlobstoreBytes::from_bytes(stored))
+    }
+}
+
+/// Split a value written by `compress` into its codec, uncompressed length and payload, or
+/// return `None` if it doesn't look like one. Legacy values that merely start with the magic
+/// fail the codec and payload checks, so they aren't mistaken for compressed values.
+fn parse_header(stored: &Bytes) -> Option<(u8, u64, Bytes)> {
+    if stored.len() < HEADER_LEN || !stored.starts_with(MAGIC) {
+        return None;
+    }
+
+    let codec = stored[MAGIC.len()];
+    let expected = u64::from_be_bytes(
+        stored[MAGIC.len() + 1..HEADER_LEN]
+            .try_into()
+            .expect("header slice has the length of a u64"),
+    );
+    let payload = stored.slice(HEADER_LEN..);
+
+    let well_formed = match codec {
+        CODEC_RAW => payload.len() as u64 == expected,
+        CODEC_ZSTD => payload.starts_with(ZSTD_FRAME_MAGIC),
+        _ => false,
+    };
+    well_formed.then_some((codec, expected, payload))
+}
+
+fn decompress(key: &str, stored: Bytes) -> Result<Bytes> {
+    let (codec, expected, payload) = match parse_header(&stored) {
+        Some(header) => header,
+        None => return Ok(stored),
+    };
+
+    let value = if codec == CODEC_ZSTD {
+        // The length in the header can't be trusted, so don't allocate it up front. Reading
+        // one byte past it is enough to tell that the value is too long.
+        let mut value = Vec::new();
+        zstd::stream::read::Decoder::new(&payload[..])
+            .and_then(|decoder| {
+                decoder
+                    .take(expected.saturating_add(1))
+                    .read_to_end(&mut value)
+            })
+            .map_err(|e| ErrorKind::Decompress(key.to_owned(), e))?;
+        Bytes::from(value)
+    } else {
+        payload
+    };
+
+    if value.len() as u64 != expected {
+        return Err(ErrorKind::LengthMismatch {
+            key: key.to_owned(),
+            expected,
+            actual: value.len() as u64,
+        }
+        .into());
+    }
+    Ok(value)
+}
+
+#[async_trait]
+impl<T: Blobstore> Blobstore for CompressBlobstore<T> {
+    async fn get<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<Option<BlobstoreGetData>> {
+        match self.blobstore.get(ctx, key).await? {
+            Some(data) => {
+                let meta = data.as_meta().clone();
+                let value = decompress(key, data.into_raw_bytes())?;
+                Ok(Some(BlobstoreGetData::new(
+                    meta,
+                    BlobstoreBytes::from_bytes(value),
+                )))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn put<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<()> {
+        let value = self.compress(value)?;
+        self.blobstore.put(ctx, key, value).await
+    }
+
+    #[inline]
+    async fn is_present<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<BlobstoreIsPresent> {
+        self.blobstore.is_present(ctx, key).await
+    }
+}
+
+#[async_trait]
+impl<T: BlobstorePutOps> BlobstorePutOps for CompressBlobstore<T> {
+    async fn put_explicit<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+        put_behaviour: PutBehaviour,
+    ) -> Result<OverwriteStatus> {
+        let value = self.compress(value)?;
+        self.blobstore
+            .put_explicit(ctx, key, value, put_behaviour)
+            .await
+    }
+
+    async fn put_with_status<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<OverwriteStatus> {
+        let value = self.compress(value)?;
+        self.blobstore.put_with_status(ctx, key, value).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use borrowed::borrowed;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
+
+    use super::*;
+
+    /// Bytes that zstd can't make any smaller.
+    fn incompressible(len: usize) -> Vec<u8> {
+        let mut state: u32 = 0x12345678;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    #[fbinit::test]
+    async fn test_roundtrip_compressible(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let wrapper = CompressBlobstore::new(base.clone(), 0);
+
+        let value = BlobstoreBytes::from_bytes("manifest entry\n".repeat(1000));
+        wrapper
+            .put(ctx, "key".to_owned(), value.clone())
+            .await
+            .unwrap();
+
+        let stored = base.get(ctx, "key").await.unwrap().unwrap();
+        assert!(stored.len() < value.len());
+        assert_eq!(stored.as_raw_bytes()[MAGIC.len()], CODEC_ZSTD);
+
+        let got = wrapper.get(ctx, "key").await.unwrap().unwrap();
+        assert_eq!(got.into_bytes(), value);
+    }
+
+    #[fbinit::test]
+    async fn test_roundtrip_incompressible(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let wrapper = CompressBlobstore::new(base.clone(), 0);
+
+        let value = BlobstoreBytes::from_bytes(incompressible(4096));
+        wrapper
+            .put_with_status(ctx, "key".to_owned(), value.clone())
+            .await
+            .unwrap();
+
+        let stored = base.get(ctx, "key").await.unwrap().unwrap();
+        assert_eq!(stored.len(), HEADER_LEN + value.len());
+        assert_eq!(stored.as_raw_bytes()[MAGIC.len()], CODEC_RAW);
+
+        let got = wrapper.get(ctx, "key").await.unwrap().unwrap();
+        assert_eq!(got.into_bytes(), value);
+    }
+
+    #[fbinit::test]
+    async fn test_legacy_uncompressed(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let wrapper = CompressBlobstore::new(base.clone(), 0);
+
+        for value in [
+            &b""[..],
+            &b"short"[..],
+            &b"written before compression was enabled"[..],
+        ] {
+            let value = BlobstoreBytes::from_bytes(value);
+            base.put(ctx, "legacy".to_owned(), value.clone())
+                .await
+                .unwrap();
+            let got = wrapper.get(ctx, "legacy").await.unwrap().unwrap();
+            assert_eq!(got.into_bytes(), value);
+        }
+
+        assert!(wrapper.get(ctx, "missing").await.unwrap().is_none());
+        assert!(
+            wrapper
+                .is_present(ctx, "legacy")
+                .await
+                .unwrap()
+                .assume_not_found_if_unsure()
+        );
+    }
+
+    fn with_header(codec: u8, len: u64, payload: &[u8]) -> Vec<u8> {
+        let mut stored = MAGIC.to_vec();
+        stored.push(codec);
+        stored.extend_from_slice(&len.to_be_bytes());
+        stored.extend_from_slice(payload);
+        stored
+    }
+
+    #[fbinit::test]
+    async fn test_legacy_starting_with_magic(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let wrapper = CompressBlobstore::new(base.clone(), 0);
+
+        for value in [
+            // Text after the magic.
+            [&MAGIC[..], &b"some legacy data"[..]].concat(),
+            // An unknown codec.
+            with_header(42, 0, b""),
+            // A raw value of the wrong length.
+            with_header(CODEC_RAW, 100, b"legacy"),
+            // A zstd value that isn't a zstd frame.
+            with_header(CODEC_ZSTD, 6, b"legacy"),
+        ] {
+            let value = BlobstoreBytes::from_bytes(value);
+            base.put(ctx, "legacy".to_owned(), value.clone())
+                .await
+                .unwrap();
+            let got = wrapper.get(ctx, "legacy").await.unwrap().unwrap();
+            assert_eq!(got.into_bytes(), value);
+        }
+    }
+
+    #[fbinit::test]
+    async fn test_wrong_length_in_header(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let wrapper = CompressBlobstore::new(base.clone(), 0);
+
+        let value = "manifest entry\n".repeat(1000);
+        let compressed = zstd::bulk::compress(value.as_bytes(), 0).unwrap();
+        for len in [u64::MAX, value.len() as u64 + 1, value.len() as u64 - 1] {
+            let stored = with_header(CODEC_ZSTD, len, &compressed);
+            base.put(ctx, "key".to_owned(), BlobstoreBytes::from_bytes(stored))
+                .await
+                .unwrap();
+
+            // An oversized length is an error, rather than an attempt to allocate it.
+            let err = wrapper.get(ctx, "key").await.unwrap_err();
+            assert!(matches!(
+                err.downcast_ref::<ErrorKind>(),
+                Some(ErrorKind::LengthMismatch { expected, actual, .. })
+                    if *expected == len && *actual == value.len() as u64
+            ));
+        }
+    }
+}
@@ -22,6 +22,11 @@ use rand_distr::Distribution;
 
 pub type Normal = rand_distr::Normal<f64>;
 
+/// A distribution that always delays by exactly `delay`.
+pub fn fixed_delay(delay: Duration) -> Normal {
+    Normal::new(delay.as_secs_f64(), 0.0).expect("a zero standard deviation is always valid")
+}
+
 #[derive(Clone, Copy, Default, Debug)]
 pub struct DelayOptions {
     pub get_dist: Option<Normal>,
@@ -151,3 +156,51 @@ where
         .await;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::time::Instant;
+
+    use borrowed::borrowed;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
+
+    use super::*;
+
+    #[fbinit::test]
+    async fn test_get_is_delayed(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let delay = Duration::from_millis(50);
+        let blobstore = DelayedBlobstore::from_options(
+            Memblob::default(),
+            DelayOptions {
+                get_dist: Some(fixed_delay(delay)),
+                put_dist: None,
+            },
+        );
+
+        blobstore
+            .put(ctx, "key".to_string(), BlobstoreBytes::from_bytes("value"))
+            .await
+            .unwrap();
+
+        let start = Instant::now();
+        let value = blobstore.get(ctx, "key").await.unwrap();
+        assert!(start.elapsed() >= delay);
+        assert_eq!(
+            value.map(|v| v.into_bytes()),
+            Some(BlobstoreBytes::from_bytes("value"))
+        );
+
+        let start = Instant::now();
+        assert!(
+            blobstore
+                .is_present(ctx, "key")
+                .await
+                .unwrap()
+                .assume_not_found_if_unsure()
+        );
+        assert!(start.elapsed() >= delay);
+    }
+}
@@ -274,7 +274,12 @@ impl BlobstoreKeySource for Fileblob {
                     });
                 Ok(enum_data)
             }
-            _ => Err(format_err!("Fileblob does not support token, only ranges")),
+            BlobstoreKeyParam::StartReversed(_) => {
+                Err(format_err!("Reverse enumeration unsupported for fileblob"))
+            }
+            BlobstoreKeyParam::Continuation(_) => {
+                Err(format_err!("Fileblob does not support token, only ranges"))
+            }
         }
     }
 }
@@ -7,6 +7,7 @@
 
 use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt;
 use std::sync::Arc;
 use std::sync::Mutex;
@@ -15,11 +16,15 @@ use anyhow::format_err;
 use anyhow::Result;
 use async_trait::async_trait;
 use blobstore::Blobstore;
+use blobstore::BlobstoreBatchOps;
 use blobstore::BlobstoreEnumerationData;
 use blobstore::BlobstoreGetData;
 use blobstore::BlobstoreKeyParam;
+use blobstore::BlobstoreKeyRange;
 use blobstore::BlobstoreKeySource;
+use blobstore::BlobstoreKeyToken;
 use blobstore::BlobstorePutOps;
+use blobstore::BlobstoreSizeHint;
 use blobstore::BlobstoreUnlinkOps;
 use blobstore::OverwriteStatus;
 use blobstore::PutBehaviour;
@@ -97,6 +102,7 @@ impl MemState {
 pub struct Memblob {
     state: Arc<Mutex<MemState>>,
     put_behaviour: PutBehaviour,
+    page_size: usize,
 }
 
 impl std::fmt::Display for Memblob {
@@ -110,6 +116,46 @@ impl Memblob {
         Self {
             state: Arc::new(Mutex::new(MemState::default())),
             put_behaviour,
+            page_size: usize::MAX,
+        }
+    }
+
+    /// Return at most `page_size` keys from each call to `enumerate`, with a continuation token
+    /// for the rest, as stores backed by real storage do. By default all keys are returned at
+    /// once.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Enumerate one page of `range`, starting from the last key if `reverse` is set.
+    fn enumerate_page(&self, range: &BlobstoreKeyRange, reverse: bool) -> BlobstoreEnumerationData {
+        let state = self.state.lock().expect("lock poison");
+        let keys = state.links.range(range).map(|(k, _)| k);
+        let mut keys: Box<dyn Iterator<Item = &String>> = if reverse {
+            Box::new(keys.rev())
+        } else {
+            Box::new(keys)
+        };
+        let page: HashSet<String> = keys.by_ref().take(self.page_size).cloned().collect();
+        let next_token = keys.next().map(|next| {
+            // Ranges are inclusive, so the rest of the range starts at the next key.
+            let rest = if reverse {
+                BlobstoreKeyRange {
+                    begin_key: range.begin_key.clone(),
+                    end_key: next.clone(),
+                }
+            } else {
+                BlobstoreKeyRange {
+                    begin_key: next.clone(),
+                    end_key: range.end_key.clone(),
+                }
+            };
+            BlobstoreKeyParam::Continuation(encode_token(&rest, reverse))
+        });
+        BlobstoreEnumerationData {
+            keys: page,
+            next_token,
         }
     }
 
@@ -191,6 +237,34 @@ impl Blobstore for Memblob {
     }
 }
 
+#[async_trait]
+impl BlobstoreSizeHint for Memblob {
+    async fn blob_size<'a>(&'a self, _ctx: &'a CoreContext, key: &'a str) -> Result<Option<u64>> {
+        let inner = self.state.lock().expect("lock poison");
+        Ok(inner.get(key).map(|bytes| bytes.len() as u64))
+    }
+}
+
+#[async_trait]
+impl BlobstoreBatchOps for Memblob {
+    async fn get_many<'a>(
+        &'a self,
+        _ctx: &'a CoreContext,
+        keys: &'a [&'a str],
+    ) -> Result<HashMap<String, Option<BlobstoreGetData>>> {
+        let state = self.state.clone();
+
+        let inner = state.lock().expect("lock poison");
+        Ok(keys
+            .iter()
+            .map(|key| {
+                let value = inner.get(key).map(|bytes| bytes.clone().into());
+                (key.to_string(), value)
+            })
+            .collect())
+    }
+}
+
 #[async_trait]
 impl BlobstoreUnlinkOps for Memblob {
     async fn unlink<'a>(&'a self, _ctx: &'a CoreContext, key: &'a str) -> Result<()> {
@@ -212,20 +286,54 @@ impl BlobstoreKeySource for Memblob {
         range: &'a BlobstoreKeyParam,
     ) -> Result<BlobstoreEnumerationData> {
         match range {
-            BlobstoreKeyParam::Start(range) => {
-                let state = self.state.lock().expect("lock poison");
-                Ok(BlobstoreEnumerationData {
-                    keys: state.links.range(range).map(|(k, _)| k.clone()).collect(),
-                    next_token: None,
-                })
-            }
-            BlobstoreKeyParam::Continuation(_) => {
-                Err(format_err!("Continuation not supported for memblob"))
+            BlobstoreKeyParam::Start(range) => Ok(self.enumerate_page(range, false)),
+            BlobstoreKeyParam::StartReversed(range) => Ok(self.enumerate_page(range, true)),
+            BlobstoreKeyParam::Continuation(token) => {
+                let (range, reverse) = decode_token(token)?;
+                Ok(self.enumerate_page(&range, reverse))
             }
         }
     }
 }
 
+const ASCENDING: &str = "asc";
+const DESCENDING: &str = "desc";
+
+/// Tokens are `order:len:begin_keyend_key`, where `len` is the length of `begin_key` in bytes,
+/// so that keys can contain any characters.
+fn encode_token(range: &BlobstoreKeyRange, reverse: bool) -> BlobstoreKeyToken {
+    let order = if reverse { DESCENDING } else { ASCENDING };
+    BlobstoreKeyToken::StringToken(format!(
+        "{}:{}:{}{}",
+        order,
+        range.begin_key.len(),
+        range.begin_key,
+        range.end_key
+    ))
+}
+
+fn decode_token(token: &BlobstoreKeyToken) -> Result<(BlobstoreKeyRange, bool)> {
+    let BlobstoreKeyToken::StringToken(token) = token;
+    let parse = || {
+        let (order, rest) = token.split_once(':')?;
+        let reverse = match order {
+            ASCENDING => false,
+            DESCENDING => true,
+            _ => return None,
+        };
+        let (begin_len, keys) = rest.split_once(':')?;
+        let begin_len = begin_len.parse::<usize>().ok()?;
+        let begin_key = keys.get(..begin_len)?;
+        let end_key = keys.get(begin_len..)?;
+        let range = BlobstoreKeyRange {
+            begin_key: begin_key.to_string(),
+            end_key: end_key.to_string(),
+        };
+        Some((range, reverse))
+    };
+    parse().ok_or_else(|| format_err!("Malformed memblob continuation token {:?}", token))
+}
+
 impl fmt::Debug for Memblob {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Memblob")
@@ -0,0 +1,47 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use criterion::black_box;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+use criterion::Throughput;
+use memblob::Memblob;
+use prefixblob::PrefixBlobstore;
+
+const NUM_KEYS: usize = 1_000_000;
+
+fn bench_prepend(c: &mut Criterion) {
+    let prefixed = PrefixBlobstore::new(Memblob::default(), "repo0000.");
+    let prefix = prefixed.prefix();
+    let keys = (0..NUM_KEYS)
+        .map(|i| format!("content.blake2.{:x}", i))
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("prepend");
+    group.throughput(Throughput::Elements(NUM_KEYS as u64));
+    group.sample_size(10);
+    group.bench_function("prepend", |b| {
+        b.iter(|| {
+            for key in keys.iter() {
+                black_box(prefixed.prepend(key));
+            }
+        })
+    });
+    // The previous implementation, kept as a baseline for comparison.
+    group.bench_function("concat", |b| {
+        b.iter(|| {
+            for key in keys.iter() {
+                black_box([prefix.as_str(), key.as_str()].concat());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_prepend);
+criterion_main!(benches);
@@ -0,0 +1,20 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ErrorKind {
+    #[error("PrefixBlobstore prefix must not be empty")]
+    EmptyPrefix,
+    #[error("PrefixBlobstore prefix {0:?} contains invalid character {1:?}")]
+    InvalidPrefixChar(String, char),
+    #[error("Key {key:?} does not start with PrefixBlobstore prefix {prefix:?}")]
+    MissingPrefix { key: String, prefix: String },
+    #[error("Invalid PrefixBlobstore enumeration cursor {0:?}")]
+    InvalidCursor(String),
+}
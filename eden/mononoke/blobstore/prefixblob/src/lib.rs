@@ -5,34 +5,101 @@
  * GNU General Public License version 2.
  */
 
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::Context;
+use anyhow::Error;
 use anyhow::Result;
 use async_trait::async_trait;
 use blobstore::Blobstore;
+use blobstore::BlobstoreBatchOps;
 use blobstore::BlobstoreEnumerationData;
 use blobstore::BlobstoreGetData;
+use blobstore::BlobstoreHealth;
 use blobstore::BlobstoreIsPresent;
 use blobstore::BlobstoreKeyParam;
 use blobstore::BlobstoreKeyRange;
 use blobstore::BlobstoreKeySource;
 use blobstore::BlobstorePutOps;
+use blobstore::BlobstoreSizeHint;
 use blobstore::BlobstoreUnlinkOps;
 use blobstore::OverwriteStatus;
 use blobstore::PutBehaviour;
 use context::CoreContext;
+use futures::stream;
+use futures::stream::Stream;
+use futures::stream::TryStreamExt;
 use inlinable_string::InlinableString;
+use memblob::Memblob;
 use mononoke_types::BlobstoreBytes;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+
+mod errors;
+mod multi_prefix;
+pub use crate::errors::ErrorKind;
+pub use crate::multi_prefix::MultiPrefixBlobstore;
 
 /// A layer over an existing blobstore that prepends a fixed string to each get and put.
 #[derive(Clone, Debug)]
 pub struct PrefixBlobstore<T> {
     // Try to inline the prefix to ensure copies remain cheap. Most prefixes are short anyway.
     prefix: InlinableString,
+    // When false, keys are passed through to the inner blobstore unchanged.
+    enabled: bool,
     blobstore: T,
 }
 
 impl<T: std::fmt::Display> std::fmt::Display for PrefixBlobstore<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "PrefixBlobstore<{}>", &self.blobstore)
+        write!(f, "PrefixBlobstore<{}, {}>", &self.prefix, &self.blobstore)
+    }
+}
+
+/// Blobstores that can describe the key prefixes applied by any stacked prefix layers, so that
+/// diagnostics can show the full composed prefix in a single line. Leaf blobstores, and trait
+/// objects whose layers can't be seen, use the default methods.
+pub trait DescribePrefix: std::fmt::Display {
+    /// The prefix that this blobstore and its inner layers add to keys before they reach storage.
+    fn composed_prefix(&self) -> String {
+        String::new()
+    }
+
+    /// The innermost blobstore that doesn't add a prefix.
+    fn describe_innermost(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl DescribePrefix for Memblob {}
+
+impl<T: Blobstore + ?Sized> DescribePrefix for Arc<T> {}
+
+impl<T: Blobstore + ?Sized> DescribePrefix for Box<T> {}
+
+impl<T: DescribePrefix> DescribePrefix for PrefixBlobstore<T> {
+    fn composed_prefix(&self) -> String {
+        // Inner layers prepend their prefixes after ours.
+        [self.blobstore.composed_prefix().as_str(), &*self.prefix].concat()
+    }
+
+    fn describe_innermost(&self) -> String {
+        self.blobstore.describe_innermost()
+    }
+}
+
+impl<T: DescribePrefix> PrefixBlobstore<T> {
+    /// Describe this blobstore with the prefixes of all nested prefix layers collapsed into one.
+    pub fn describe(&self) -> String {
+        format!(
+            "PrefixBlobstore<{}, {}>",
+            self.composed_prefix(),
+            self.describe_innermost()
+        )
     }
 }
 
@@ -50,20 +117,297 @@ impl<T> PrefixBlobstore<T> {
     }
 }
 
+/// Returns true if `c` may appear in a prefix accepted by `PrefixBlobstore::new_checked`.
+fn is_valid_prefix_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':' | '/')
+}
+
 impl<T> PrefixBlobstore<T> {
+    /// Create a prefixed blobstore without validating the prefix. Any string is accepted,
+    /// including the empty string and characters outside those allowed by `new_checked`.
     pub fn new<S: Into<InlinableString>>(blobstore: T, prefix: S) -> Self {
         let prefix = prefix.into();
-        Self { prefix, blobstore }
+        Self {
+            prefix,
+            enabled: true,
+            blobstore,
+        }
+    }
+
+    /// Create a blobstore that doesn't prefix keys at all, for compositions that expect a
+    /// PrefixBlobstore layer but shouldn't change keys. Keys and enumeration ranges are passed
+    /// through to the inner blobstore unchanged.
+    pub fn passthrough(blobstore: T) -> Self {
+        Self {
+            prefix: InlinableString::default(),
+            enabled: false,
+            blobstore,
+        }
+    }
+
+    /// Create a prefixed blobstore, rejecting empty prefixes and prefixes containing anything
+    /// other than ASCII alphanumerics, `-`, `_`, `.`, `:` and `/`.
+    pub fn new_checked<S: Into<InlinableString>>(blobstore: T, prefix: S) -> Result<Self> {
+        let prefix = prefix.into();
+        if prefix.is_empty() {
+            return Err(ErrorKind::EmptyPrefix.into());
+        }
+        if let Some(c) = prefix.chars().find(|c| !is_valid_prefix_char(*c)) {
+            return Err(ErrorKind::InvalidPrefixChar(prefix.to_string(), c).into());
+        }
+        Ok(Self {
+            prefix,
+            enabled: true,
+            blobstore,
+        })
     }
 
     #[inline]
     pub fn prepend(&self, key: impl AsRef<str>) -> String {
-        [&self.prefix, key.as_ref()].concat()
+        // Build the key with exactly one allocation; this is on the path of every operation.
+        let key = key.as_ref();
+        if !self.enabled {
+            return key.to_string();
+        }
+        let mut prefixed = String::with_capacity(self.prefix.len() + key.len());
+        prefixed.push_str(&self.prefix);
+        prefixed.push_str(key);
+        prefixed
     }
 
+    /// Strip the prefix from `key`, panicking if `key` does not carry it. Prefer
+    /// `try_unprepend` for keys that come from the inner blobstore.
     #[inline]
     pub fn unprepend(&self, key: &str) -> String {
-        key[self.prefix.len()..].to_string()
+        self.try_unprepend(key)
+            .expect("key passed to unprepend must start with the prefix")
+    }
+
+    /// Strip the prefix from `key`, failing if `key` does not start with it.
+    #[inline]
+    pub fn try_unprepend(&self, key: &str) -> Result<String> {
+        if !self.enabled {
+            return Ok(key.to_string());
+        }
+        match key.strip_prefix(&*self.prefix) {
+            Some(key) => Ok(key.to_string()),
+            None => Err(ErrorKind::MissingPrefix {
+                key: key.to_string(),
+                prefix: self.prefix.to_string(),
+            }
+            .into()),
+        }
+    }
+
+    /// Returns true if `inner_key`, a key as stored in the inner blobstore, was written through
+    /// this blobstore.
+    pub fn owns_key(&self, inner_key: &str) -> bool {
+        !self.enabled || inner_key.starts_with(&*self.prefix)
+    }
+
+    /// Map a key as stored in the inner blobstore back to the key used with this blobstore, or
+    /// `None` if `inner_key` doesn't belong to this blobstore.
+    pub fn logical_key(&self, inner_key: &str) -> Option<String> {
+        if self.owns_key(inner_key) {
+            self.try_unprepend(inner_key).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Translate a logical key range into the range of inner keys carrying the prefix.
+    fn prepend_range(&self, range: &BlobstoreKeyRange) -> BlobstoreKeyRange {
+        if !self.enabled {
+            return range.clone();
+        }
+        BlobstoreKeyRange {
+            // Regardless of the value of the begin_key (empty or non-empty), we
+            // need to prepend the prefix to begin the search from the first
+            // prefix-included entry in the underlying blobstore.
+            begin_key: self.prepend(&range.begin_key),
+            end_key: if range.end_key.is_empty() {
+                // When the end-key is empty, we need to prepend the prefix to ensure
+                // that the search is limited to prefix-included entries. The \u{10ffff}
+                // (final valid unicode value) is added as a representative end-of-range
+                // character for restricting the search.
+                self.prepend("\u{10ffff}")
+            } else {
+                self.prepend(&range.end_key)
+            },
+        }
+    }
+
+    fn prepend_all(&self, keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|key| self.prepend(key)).collect()
+    }
+
+    fn unprepend_map<V>(&self, values: HashMap<String, V>) -> Result<HashMap<String, V>> {
+        values
+            .into_iter()
+            .map(|(key, value)| Ok((self.try_unprepend(&key)?, value)))
+            .collect()
+    }
+}
+
+impl<T: Blobstore> PrefixBlobstore<T> {
+    /// Fetch all of `keys` using individual `get` calls on the inner blobstore, with at most
+    /// `concurrency` of them in flight. Use this when the inner blobstore has no batch API, as
+    /// `BlobstoreBatchOps` is only implemented when the inner blobstore implements it.
+    pub async fn get_many_buffered<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        keys: &'a [&'a str],
+        concurrency: usize,
+    ) -> Result<HashMap<String, Option<BlobstoreGetData>>> {
+        let prefixed = self.prepend_all(keys);
+        let prefixed = prefixed.iter().map(String::as_str).collect::<Vec<_>>();
+        let values =
+            blobstore::get_many_buffered(&self.blobstore, ctx, &prefixed, concurrency).await?;
+        self.unprepend_map(values)
+    }
+}
+
+/// The smallest string greater than every string starting with `prefix`, or the empty string
+/// (meaning no bound) if there is none.
+fn prefix_upper_bound(prefix: &str) -> String {
+    let mut chars: Vec<char> = prefix.chars().collect();
+    while let Some(last) = chars.pop() {
+        let next = match last {
+            '\u{d7ff}' => Some('\u{e000}'),
+            c => char::from_u32(c as u32 + 1),
+        };
+        if let Some(next) = next {
+            chars.push(next);
+            return chars.into_iter().collect();
+        }
+    }
+    String::new()
+}
+
+/// A point at which to resume enumerating a logical key range of a `PrefixBlobstore`, for jobs
+/// that checkpoint their progress. It holds the range being enumerated along with the inner
+/// blobstore's continuation, so resuming doesn't need the range to be given again. Cursors
+/// round-trip through their `Display` and `FromStr` forms, for storing between runs.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct EnumerateCursor {
+    range: BlobstoreKeyRange,
+    next: Option<BlobstoreKeyParam>,
+}
+
+impl EnumerateCursor {
+    /// A cursor at the start of the logical key range `range`.
+    pub fn new(range: BlobstoreKeyRange) -> Self {
+        Self {
+            next: Some(BlobstoreKeyParam::Start(range.clone())),
+            range,
+        }
+    }
+
+    /// The logical key range being enumerated.
+    pub fn range(&self) -> &BlobstoreKeyRange {
+        &self.range
+    }
+
+    /// Whether every key in the range has been enumerated.
+    pub fn is_done(&self) -> bool {
+        self.next.is_none()
+    }
+}
+
+impl fmt::Display for EnumerateCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string(self).map_err(|_| fmt::Error)?;
+        write!(f, "{}", json)
+    }
+}
+
+impl FromStr for EnumerateCursor {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        serde_json::from_str(s).with_context(|| ErrorKind::InvalidCursor(s.to_string()))
+    }
+}
+
+impl<T: BlobstoreKeySource> PrefixBlobstore<T> {
+    /// Enumerate the next page of logical keys from `cursor`, returning them along with the
+    /// cursor to continue from. Once the cursor is done, no more keys are returned.
+    pub async fn enumerate_from_cursor<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        cursor: EnumerateCursor,
+    ) -> Result<(HashSet<String>, EnumerateCursor)> {
+        let param = match &cursor.next {
+            Some(param) => param,
+            None => return Ok((HashSet::new(), cursor)),
+        };
+        let res = self.enumerate(ctx, param).await?;
+        let next = EnumerateCursor {
+            range: cursor.range,
+            next: res.next_token,
+        };
+        Ok((res.keys, next))
+    }
+
+    /// Enumerate all the logical keys that start with `logical_prefix`, such as
+    /// `content.blake2.`. Every page from the inner blobstore is fetched, so the result never
+    /// has a `next_token`.
+    pub async fn enumerate_logical_prefix(
+        &self,
+        ctx: &CoreContext,
+        logical_prefix: &str,
+    ) -> Result<BlobstoreEnumerationData> {
+        let range = BlobstoreKeyParam::Start(BlobstoreKeyRange {
+            begin_key: logical_prefix.to_string(),
+            end_key: prefix_upper_bound(logical_prefix),
+        });
+        let mut keys = self.enumerate_with_progress(ctx, &range, |_| {}).await?;
+        // Ranges include their end key, which is just past the prefix.
+        keys.retain(|key| key.starts_with(logical_prefix));
+        Ok(BlobstoreEnumerationData {
+            keys,
+            next_token: None,
+        })
+    }
+
+    /// Enumerate the logical keys in `range`, following continuation tokens from the inner
+    /// blobstore until the enumeration is complete. Pages are fetched lazily as the stream is
+    /// polled.
+    pub fn enumerate_stream<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        range: &'a BlobstoreKeyParam,
+    ) -> impl Stream<Item = Result<String>> + 'a {
+        stream::try_unfold(Some(range.clone()), move |param| async move {
+            let param = match param {
+                Some(param) => param,
+                None => return Ok(None),
+            };
+            let res = self.enumerate(ctx, &param).await?;
+            let keys = stream::iter(res.keys.into_iter().map(Ok));
+            Ok::<_, Error>(Some((keys, res.next_token)))
+        })
+        .try_flatten()
+    }
+
+    /// Enumerate all the logical keys in `range`, following continuation tokens until the
+    /// enumeration is complete. After each page, `on_page` is called with the number of keys
+    /// enumerated so far.
+    pub async fn enumerate_with_progress<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        range: &'a BlobstoreKeyParam,
+        mut on_page: impl FnMut(usize),
+    ) -> Result<HashSet<String>> {
+        let mut keys = HashSet::new();
+        let mut param = Some(range.clone());
+        while let Some(current) = param {
+            let res = self.enumerate(ctx, &current).await?;
+            keys.extend(res.keys);
+            on_page(keys.len());
+            param = res.next_token;
+        }
+        Ok(keys)
     }
 }
 
@@ -107,6 +451,11 @@ impl<T: Blobstore> Blobstore for PrefixBlobstore<T> {
             .copy(ctx, &self.prepend(old_key), self.prepend(new_key))
             .await
     }
+
+    #[inline]
+    async fn health_check<'a>(&'a self, ctx: &'a CoreContext) -> Result<BlobstoreHealth> {
+        self.blobstore.health_check(ctx).await
+    }
 }
 
 #[async_trait]
@@ -135,6 +484,30 @@ impl<T: BlobstorePutOps> BlobstorePutOps for PrefixBlobstore<T> {
     }
 }
 
+/// Batch fetches go through the inner blobstore's `get_many`, so this needs `T` to implement
+/// `BlobstoreBatchOps` too. A blanket fallback for any `T: Blobstore` would overlap with this
+/// impl; for those, use `PrefixBlobstore::get_many_buffered` instead.
+#[async_trait]
+impl<T: BlobstoreBatchOps> BlobstoreBatchOps for PrefixBlobstore<T> {
+    async fn get_many<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        keys: &'a [&'a str],
+    ) -> Result<HashMap<String, Option<BlobstoreGetData>>> {
+        let prefixed = self.prepend_all(keys);
+        let prefixed = prefixed.iter().map(String::as_str).collect::<Vec<_>>();
+        let values = self.blobstore.get_many(ctx, &prefixed).await?;
+        self.unprepend_map(values)
+    }
+}
+
+#[async_trait]
+impl<T: BlobstoreSizeHint> BlobstoreSizeHint for PrefixBlobstore<T> {
+    async fn blob_size<'a>(&'a self, ctx: &'a CoreContext, key: &'a str) -> Result<Option<u64>> {
+        self.blobstore.blob_size(ctx, &self.prepend(key)).await
+    }
+}
+
 #[async_trait]
 impl<T: BlobstoreUnlinkOps> BlobstoreUnlinkOps for PrefixBlobstore<T> {
     async fn unlink<'a>(&'a self, ctx: &'a CoreContext, key: &'a str) -> Result<()> {
@@ -150,37 +523,44 @@ impl<T: BlobstoreKeySource> BlobstoreKeySource for PrefixBlobstore<T> {
         range: &'a BlobstoreKeyParam,
     ) -> Result<BlobstoreEnumerationData> {
         let new_param = match range {
-            BlobstoreKeyParam::Start(range) => BlobstoreKeyParam::Start(BlobstoreKeyRange {
-                // Regardless of the value of the begin_key (empty or non-empty), we
-                // need to prepend the prefix to begin the search from the first
-                // prefix-included entry in the underlying blobstore.
-                begin_key: self.prepend(&range.begin_key),
-                end_key: if range.end_key.is_empty() {
-                    // When the end-key is empty, we need to prepend the prefix to ensure
-                    // that the search is limited to prefix-included entries. The \u{10ffff}
-                    // (final valid unicode value) is added as a representative end-of-range
-                    // character for restricting the search.
-                    self.prepend("\u{10ffff}")
-                } else {
-                    self.prepend(&range.end_key)
-                },
-            }),
+            BlobstoreKeyParam::Start(range) => BlobstoreKeyParam::Start(self.prepend_range(range)),
+            BlobstoreKeyParam::StartReversed(range) => {
+                BlobstoreKeyParam::StartReversed(self.prepend_range(range))
+            }
             // No need to prepend Continuation as we don't unprepend it
-            p => p.clone(),
+            p @ BlobstoreKeyParam::Continuation(_) => p.clone(),
         };
         let mut res = self.blobstore.enumerate(ctx, &new_param).await?;
-        res.keys = res.keys.into_iter().map(|k| self.unprepend(&k)).collect();
+        res.keys = res
+            .keys
+            .into_iter()
+            .map(|k| self.try_unprepend(&k))
+            .collect::<Result<_>>()?;
         Ok(res)
     }
+
+    async fn count_keys<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        range: &'a BlobstoreKeyRange,
+    ) -> Result<u64> {
+        // Counting needs no unprepending, so let the inner store do the paging.
+        self.blobstore
+            .count_keys(ctx, &self.prepend_range(range))
+            .await
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use std::sync::Mutex;
+
+    use anyhow::anyhow;
     use borrowed::borrowed;
     use bytes::Bytes;
     use fbinit::FacebookInit;
+    use maplit::hashmap;
     use maplit::hashset;
-    use memblob::Memblob;
 
     use super::*;
 
@@ -261,4 +641,932 @@ mod test {
                 .is_empty()
         );
     }
+
+    #[test]
+    fn test_describe() {
+        let inner = PrefixBlobstore::new(Memblob::default(), "repo0001.");
+        let outer = PrefixBlobstore::new(inner, "bubble42.");
+        assert_eq!(
+            outer.to_string(),
+            "PrefixBlobstore<bubble42., PrefixBlobstore<repo0001., Memblob>>"
+        );
+        // Keys reach Memblob with the inner prefix first.
+        assert_eq!(
+            outer.describe(),
+            "PrefixBlobstore<repo0001.bubble42., Memblob>"
+        );
+
+        let arc: Arc<dyn Blobstore> = Arc::new(Memblob::default());
+        let prefixed = PrefixBlobstore::new(arc, "repo0001.");
+        assert_eq!(prefixed.describe(), "PrefixBlobstore<repo0001., Memblob>");
+
+        // Layers behind a trait object are described as they display.
+        let arc: Arc<dyn Blobstore> = Arc::new(outer);
+        let prefixed = PrefixBlobstore::new(arc, "x.");
+        assert_eq!(
+            prefixed.describe(),
+            "PrefixBlobstore<x., PrefixBlobstore<bubble42., PrefixBlobstore<repo0001., Memblob>>>"
+        );
+    }
+
+    #[test]
+    fn test_new_checked() {
+        assert!(PrefixBlobstore::new_checked(Memblob::default(), "repo0001.").is_ok());
+        assert!(PrefixBlobstore::new_checked(Memblob::default(), "a-b_c.d:e").is_ok());
+        assert_eq!(
+            PrefixBlobstore::new_checked(Memblob::default(), "repo/")
+                .expect("prefix with a slash should be accepted")
+                .prefix(),
+            "repo/"
+        );
+
+        let err = PrefixBlobstore::new_checked(Memblob::default(), "")
+            .expect_err("empty prefix should be rejected");
+        assert!(matches!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::EmptyPrefix)
+        ));
+
+        let err = PrefixBlobstore::new_checked(Memblob::default(), "repo*")
+            .expect_err("prefix with an asterisk should be rejected");
+        assert!(matches!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::InvalidPrefixChar(_, '*'))
+        ));
+
+        // The unchecked constructor keeps accepting anything.
+        assert_eq!(
+            PrefixBlobstore::new(Memblob::default(), "repo*").prefix(),
+            "repo*"
+        );
+        assert_eq!(PrefixBlobstore::new(Memblob::default(), "").prefix(), "");
+    }
+
+    #[test]
+    fn test_prepend_allocates_exactly() {
+        let prefixed = PrefixBlobstore::new(Memblob::default(), "repo0001.");
+        for key in ["", "a", "content.blake2.0123456789abcdef", "\u{10ffff}"] {
+            let prepended = prefixed.prepend(key);
+            assert_eq!(prepended, format!("repo0001.{}", key));
+            // A single allocation of exactly the right size, never grown on the way.
+            assert_eq!(prepended.capacity(), prepended.len());
+        }
+    }
+
+    #[test]
+    fn test_try_unprepend() {
+        let prefixed = PrefixBlobstore::new(Memblob::default(), "prefix123-");
+        assert_eq!(
+            prefixed.try_unprepend("prefix123-foobar").unwrap(),
+            "foobar"
+        );
+        assert_eq!(prefixed.try_unprepend("prefix123-").unwrap(), "");
+        assert_eq!(prefixed.unprepend("prefix123-foobar"), "foobar");
+
+        for key in ["prefix", "other123-foobar", ""] {
+            let err = prefixed
+                .try_unprepend(key)
+                .expect_err("key without prefix should be rejected");
+            assert!(matches!(
+                err.downcast_ref::<ErrorKind>(),
+                Some(ErrorKind::MissingPrefix { key: k, .. }) if k == key
+            ));
+        }
+    }
+
+    #[test]
+    fn test_logical_key() {
+        let inner = PrefixBlobstore::new(Memblob::default(), "repo0001.");
+        let outer = PrefixBlobstore::new(inner, "bubble42.");
+
+        assert!(outer.as_inner().owns_key("repo0001.bubble42.foo"));
+        assert_eq!(
+            outer
+                .as_inner()
+                .logical_key("repo0001.bubble42.foo")
+                .and_then(|key| outer.logical_key(&key)),
+            Some("foo".to_string())
+        );
+
+        // Unowned keys, including ones that are a strict prefix of the prefix.
+        assert!(!outer.owns_key("repo0001.foo"));
+        assert_eq!(outer.logical_key("repo0001.foo"), None);
+        assert!(!outer.owns_key("bubble42"));
+        assert_eq!(outer.logical_key("bubble42"), None);
+
+        // A key exactly as long as the prefix maps to the empty logical key.
+        assert!(outer.owns_key("bubble42."));
+        assert_eq!(outer.logical_key("bubble42."), Some(String::new()));
+
+        let passthrough = PrefixBlobstore::passthrough(Memblob::default());
+        assert!(passthrough.owns_key("anything"));
+        assert_eq!(
+            passthrough.logical_key("anything"),
+            Some("anything".to_string())
+        );
+    }
+
+    /// A key source that returns a fixed set of keys regardless of the requested range, and
+    /// can't enumerate in reverse.
+    #[derive(Debug)]
+    struct FixedKeys(Vec<&'static str>);
+
+    impl std::fmt::Display for FixedKeys {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "FixedKeys")
+        }
+    }
+
+    #[async_trait]
+    impl Blobstore for FixedKeys {
+        async fn get<'a>(
+            &'a self,
+            _ctx: &'a CoreContext,
+            _key: &'a str,
+        ) -> Result<Option<BlobstoreGetData>> {
+            Ok(None)
+        }
+
+        async fn put<'a>(
+            &'a self,
+            _ctx: &'a CoreContext,
+            _key: String,
+            _value: BlobstoreBytes,
+        ) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl BlobstoreKeySource for FixedKeys {
+        async fn enumerate<'a>(
+            &'a self,
+            _ctx: &'a CoreContext,
+            range: &'a BlobstoreKeyParam,
+        ) -> Result<BlobstoreEnumerationData> {
+            if let BlobstoreKeyParam::StartReversed(_) = range {
+                return Err(anyhow!("Reverse enumeration unsupported for FixedKeys"));
+            }
+            Ok(BlobstoreEnumerationData {
+                keys: self.0.iter().map(|k| k.to_string()).collect(),
+                next_token: None,
+            })
+        }
+    }
+
+    /// A Memblob wrapper that records the enumeration parameters it is called with, and
+    /// supports reverse enumeration (which makes no difference to an unpaged result).
+    #[derive(Debug, Default)]
+    struct RecordingMemblob {
+        inner: Memblob,
+        params: Mutex<Vec<BlobstoreKeyParam>>,
+    }
+
+    impl std::fmt::Display for RecordingMemblob {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "RecordingMemblob")
+        }
+    }
+
+    #[async_trait]
+    impl Blobstore for RecordingMemblob {
+        async fn get<'a>(
+            &'a self,
+            ctx: &'a CoreContext,
+            key: &'a str,
+        ) -> Result<Option<BlobstoreGetData>> {
+            self.inner.get(ctx, key).await
+        }
+
+        async fn put<'a>(
+            &'a self,
+            ctx: &'a CoreContext,
+            key: String,
+            value: BlobstoreBytes,
+        ) -> Result<()> {
+            self.inner.put(ctx, key, value).await
+        }
+    }
+
+    #[async_trait]
+    impl BlobstoreKeySource for RecordingMemblob {
+        async fn enumerate<'a>(
+            &'a self,
+            ctx: &'a CoreContext,
+            range: &'a BlobstoreKeyParam,
+        ) -> Result<BlobstoreEnumerationData> {
+            self.params.lock().unwrap().push(range.clone());
+            let range = match range {
+                BlobstoreKeyParam::Start(range) | BlobstoreKeyParam::StartReversed(range) => {
+                    range.clone()
+                }
+                BlobstoreKeyParam::Continuation(_) => {
+                    return Err(anyhow!("Continuation not supported"));
+                }
+            };
+            self.inner
+                .enumerate(ctx, &BlobstoreKeyParam::Start(range))
+                .await
+        }
+    }
+
+    /// A store that knows the size of every blob, and records the keys it is asked about.
+    #[derive(Debug, Default)]
+    struct SizeOnlyBlobstore {
+        size_queries: Mutex<Vec<String>>,
+    }
+
+    impl std::fmt::Display for SizeOnlyBlobstore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "SizeOnlyBlobstore")
+        }
+    }
+
+    #[async_trait]
+    impl Blobstore for SizeOnlyBlobstore {
+        async fn get<'a>(
+            &'a self,
+            _ctx: &'a CoreContext,
+            _key: &'a str,
+        ) -> Result<Option<BlobstoreGetData>> {
+            Err(anyhow!("get not supported"))
+        }
+
+        async fn put<'a>(
+            &'a self,
+            _ctx: &'a CoreContext,
+            _key: String,
+            _value: BlobstoreBytes,
+        ) -> Result<()> {
+            Err(anyhow!("put not supported"))
+        }
+    }
+
+    #[async_trait]
+    impl BlobstoreSizeHint for SizeOnlyBlobstore {
+        async fn blob_size<'a>(
+            &'a self,
+            _ctx: &'a CoreContext,
+            key: &'a str,
+        ) -> Result<Option<u64>> {
+            self.size_queries.lock().unwrap().push(key.to_string());
+            Ok(Some(1234))
+        }
+    }
+
+    #[fbinit::test]
+    async fn test_blob_size(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let prefixed = PrefixBlobstore::new(SizeOnlyBlobstore::default(), "prefix123-");
+
+        assert_eq!(prefixed.blob_size(ctx, "foobar").await.unwrap(), Some(1234));
+        assert_eq!(
+            *prefixed.as_inner().size_queries.lock().unwrap(),
+            vec![prefixed.prepend("foobar")]
+        );
+        assert_eq!(prefixed.prepend("foobar"), "prefix123-foobar");
+
+        // A store that can answer reports the size of the prefixed blob.
+        let base = Memblob::default();
+        let prefixed = PrefixBlobstore::new(base.clone(), "prefix123-");
+        prefixed
+            .put(
+                ctx,
+                "foobar".to_string(),
+                BlobstoreBytes::from_bytes("test"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(prefixed.blob_size(ctx, "foobar").await.unwrap(), Some(4));
+        assert_eq!(base.blob_size(ctx, "foobar").await.unwrap(), None);
+        assert_eq!(
+            base.blob_size(ctx, "prefix123-foobar").await.unwrap(),
+            Some(4)
+        );
+    }
+
+    #[fbinit::test]
+    async fn test_enumerate_reversed(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let prefixed = PrefixBlobstore::new(RecordingMemblob::default(), "prefix123-");
+
+        for key in ["a", "b", "c"] {
+            prefixed
+                .put(ctx, key.to_string(), BlobstoreBytes::from_bytes(key))
+                .await
+                .unwrap();
+        }
+
+        let range = BlobstoreKeyRange {
+            begin_key: "b".to_string(),
+            end_key: String::new(),
+        };
+        let forward = prefixed
+            .enumerate(ctx, &BlobstoreKeyParam::Start(range.clone()))
+            .await
+            .unwrap();
+        let reversed = prefixed
+            .enumerate(ctx, &BlobstoreKeyParam::StartReversed(range.clone()))
+            .await
+            .unwrap();
+        assert_eq!(forward.keys, hashset! {"b".to_string(), "c".to_string()});
+        assert_eq!(reversed.keys, forward.keys);
+
+        // Both directions prepend the range in the same way, and the direction is forwarded.
+        let prefixed_range = prefixed.prepend_range(&range);
+        assert_eq!(
+            *prefixed.as_inner().params.lock().unwrap(),
+            vec![
+                BlobstoreKeyParam::Start(prefixed_range.clone()),
+                BlobstoreKeyParam::StartReversed(prefixed_range),
+            ]
+        );
+
+        // Stores that can't enumerate in reverse say so.
+        let err = PrefixBlobstore::new(FixedKeys(vec![]), "prefix123-")
+            .enumerate(ctx, &BlobstoreKeyParam::StartReversed(range))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("unsupported"));
+    }
+
+    #[fbinit::test]
+    async fn test_enumerate_stream(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let paged = base.clone().with_page_size(30);
+        let prefixed = PrefixBlobstore::new(paged, "prefix123-");
+
+        let mut expected = Vec::new();
+        for i in 0..200 {
+            let key = format!("key{:03}", i);
+            prefixed
+                .put(ctx, key.clone(), BlobstoreBytes::from_bytes("test value"))
+                .await
+                .expect("put should succeed");
+            expected.push(key);
+        }
+        base.put(
+            ctx,
+            "other-key".to_string(),
+            BlobstoreBytes::from_bytes("test value"),
+        )
+        .await
+        .expect("put should succeed");
+
+        // A single enumerate only returns the first page.
+        let first_page = prefixed
+            .enumerate(ctx, &BlobstoreKeyParam::from(..))
+            .await
+            .expect("enumerate should succeed");
+        assert_eq!(first_page.keys.len(), 30);
+        assert!(first_page.next_token.is_some());
+
+        let mut keys = prefixed
+            .enumerate_stream(ctx, &BlobstoreKeyParam::from(..))
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("enumerate_stream should succeed");
+        keys.sort();
+        assert_eq!(keys, expected);
+
+        let mut keys = prefixed
+            .enumerate_stream(ctx, &BlobstoreKeyParam::from("key150".to_string()..))
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("enumerate_stream should succeed");
+        keys.sort();
+        assert_eq!(keys, expected[150..]);
+    }
+
+    #[fbinit::test]
+    async fn test_enumerate_with_progress(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let paged = base.clone().with_page_size(30);
+        let prefixed = PrefixBlobstore::new(paged, "prefix123-");
+
+        let mut expected = HashSet::new();
+        for i in 0..100 {
+            let key = format!("key{:03}", i);
+            prefixed
+                .put(ctx, key.clone(), BlobstoreBytes::from_bytes("test value"))
+                .await
+                .expect("put should succeed");
+            expected.insert(key);
+        }
+        // Keys outside the prefix aren't counted.
+        base.put(
+            ctx,
+            "other-key".to_string(),
+            BlobstoreBytes::from_bytes("test value"),
+        )
+        .await
+        .expect("put should succeed");
+
+        let mut progress = Vec::new();
+        let keys = prefixed
+            .enumerate_with_progress(ctx, &BlobstoreKeyParam::from(..), |count| {
+                progress.push(count)
+            })
+            .await
+            .expect("enumerate_with_progress should succeed");
+        assert_eq!(keys, expected);
+        assert_eq!(progress, vec![30, 60, 90, 100]);
+    }
+
+    #[fbinit::test]
+    async fn test_enumerate_from_cursor(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let paged = Memblob::default().with_page_size(30);
+        let prefixed = PrefixBlobstore::new(paged, "prefix123-");
+
+        let mut expected = HashSet::new();
+        for i in 0..50 {
+            let key = format!("key{:03}", i);
+            prefixed
+                .put(ctx, key.clone(), BlobstoreBytes::from_bytes("test value"))
+                .await
+                .expect("put should succeed");
+            expected.insert(key);
+        }
+
+        let range = BlobstoreKeyRange {
+            begin_key: String::new(),
+            end_key: String::new(),
+        };
+        let (first, cursor) = prefixed
+            .enumerate_from_cursor(ctx, EnumerateCursor::new(range.clone()))
+            .await
+            .expect("first page should succeed");
+        assert_eq!(first.len(), 30);
+        assert!(!cursor.is_done());
+
+        // Checkpoint the cursor, and pick it up again as a later run would.
+        let saved = cursor.to_string();
+        let cursor: EnumerateCursor = saved.parse().expect("cursor should parse");
+        assert_eq!(cursor.range(), &range);
+
+        let (rest, cursor) = prefixed
+            .enumerate_from_cursor(ctx, cursor)
+            .await
+            .expect("second page should succeed");
+        assert_eq!(rest.len(), 20);
+        assert!(first.is_disjoint(&rest));
+        assert!(cursor.is_done());
+        assert_eq!(
+            first.union(&rest).cloned().collect::<HashSet<_>>(),
+            expected
+        );
+
+        // A finished cursor yields nothing more.
+        let (nothing, cursor) = prefixed
+            .enumerate_from_cursor(ctx, cursor)
+            .await
+            .expect("enumerating a done cursor should succeed");
+        assert!(nothing.is_empty());
+        assert!(cursor.is_done());
+
+        assert!("not a cursor".parse::<EnumerateCursor>().is_err());
+    }
+
+    #[fbinit::test]
+    async fn test_enumerate_malformed_key(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let prefixed = PrefixBlobstore::new(FixedKeys(vec!["prefix123-foo", "bar"]), "prefix123-");
+
+        let err = prefixed
+            .enumerate(ctx, &BlobstoreKeyParam::from(..))
+            .await
+            .expect_err("enumerate should fail on a key without the prefix");
+        assert!(matches!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::MissingPrefix { key, .. }) if key == "bar"
+        ));
+    }
+
+    #[fbinit::test]
+    async fn test_passthrough(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let passthrough = PrefixBlobstore::passthrough(base.clone());
+        let key = "foobar".to_string();
+
+        assert_eq!(passthrough.prepend(&key), key);
+        assert_eq!(passthrough.unprepend(&key), key);
+
+        passthrough
+            .put(ctx, key.clone(), BlobstoreBytes::from_bytes("test foobar"))
+            .await
+            .expect("put should succeed");
+        assert_eq!(
+            base.get(ctx, &key)
+                .await
+                .expect("get should succeed")
+                .expect("value should be present")
+                .into_raw_bytes(),
+            Bytes::from("test foobar"),
+        );
+        base.put(
+            ctx,
+            "\u{10ffff}-raw".to_string(),
+            BlobstoreBytes::from_bytes("test raw"),
+        )
+        .await
+        .expect("put should succeed");
+
+        // No range rewriting happens, so keys beyond the usual end-of-prefix marker are visible.
+        let enumerated = passthrough
+            .enumerate(ctx, &BlobstoreKeyParam::from(..))
+            .await
+            .expect("enumerate should succeed");
+        assert_eq!(
+            enumerated.keys,
+            hashset! { key.clone(), "\u{10ffff}-raw".to_string() }
+        );
+        let enumerated = passthrough
+            .enumerate(ctx, &BlobstoreKeyParam::from("g".to_string()..))
+            .await
+            .expect("enumerate should succeed");
+        assert_eq!(enumerated.keys, hashset! { "\u{10ffff}-raw".to_string() });
+    }
+
+    #[fbinit::test]
+    async fn test_count_keys(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let prefixed = PrefixBlobstore::new(base.clone(), "prefix123-");
+
+        for i in 0..50 {
+            prefixed
+                .put(
+                    ctx,
+                    format!("key{:02}", i),
+                    BlobstoreBytes::from_bytes("test value"),
+                )
+                .await
+                .expect("put should succeed");
+        }
+        // Keys outside the prefix must not be counted.
+        base.put(
+            ctx,
+            "other-key00".to_string(),
+            BlobstoreBytes::from_bytes("test value"),
+        )
+        .await
+        .expect("put should succeed");
+
+        let all = BlobstoreKeyRange {
+            begin_key: String::new(),
+            end_key: String::new(),
+        };
+        assert_eq!(prefixed.count_keys(ctx, &all).await.unwrap(), 50);
+
+        let ranged = BlobstoreKeyRange {
+            begin_key: "key10".to_string(),
+            end_key: "key19".to_string(),
+        };
+        assert_eq!(prefixed.count_keys(ctx, &ranged).await.unwrap(), 10);
+
+        let open_ended = BlobstoreKeyRange {
+            begin_key: "key45".to_string(),
+            end_key: String::new(),
+        };
+        assert_eq!(prefixed.count_keys(ctx, &open_ended).await.unwrap(), 5);
+    }
+
+    #[fbinit::test]
+    async fn test_key_type_histogram(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let prefixed = PrefixBlobstore::new(base.clone(), "repo0001.");
+
+        let keys = [
+            "changeset.blake2.01",
+            "changeset.blake2.02",
+            "content.blake2.01",
+            "content.blake2.02",
+            "content.blake2.03",
+            "hgchangeset.sha1.01",
+        ];
+        for key in keys {
+            prefixed
+                .put(
+                    ctx,
+                    key.to_string(),
+                    BlobstoreBytes::from_bytes("test value"),
+                )
+                .await
+                .expect("put should succeed");
+        }
+        // Keys in another repo must not be counted.
+        base.put(
+            ctx,
+            "repo0002.changeset.blake2.03".to_string(),
+            BlobstoreBytes::from_bytes("test value"),
+        )
+        .await
+        .expect("put should succeed");
+
+        let all = BlobstoreKeyRange {
+            begin_key: String::new(),
+            end_key: String::new(),
+        };
+        assert_eq!(
+            prefixed.key_type_histogram(ctx, &all).await.unwrap(),
+            hashmap! {
+                "changeset".to_string() => 2,
+                "content".to_string() => 3,
+                "hgchangeset".to_string() => 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_prefix_upper_bound() {
+        assert_eq!(prefix_upper_bound("content.blake2."), "content.blake2/");
+        assert_eq!(prefix_upper_bound("ab\u{10ffff}"), "ac");
+        assert_eq!(prefix_upper_bound("a\u{d7ff}"), "a\u{e000}");
+        assert_eq!(prefix_upper_bound("\u{10ffff}"), "");
+        assert_eq!(prefix_upper_bound(""), "");
+    }
+
+    #[fbinit::test]
+    async fn test_enumerate_logical_prefix(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let prefixed = PrefixBlobstore::new(base.clone(), "repo0001.");
+
+        let keys = [
+            "changeset.blake2.01",
+            "content.blake2.01",
+            "content.blake2.02",
+            "content.blake2/",
+            "content.sha1.01",
+            "contentx",
+        ];
+        for key in keys {
+            prefixed
+                .put(
+                    ctx,
+                    key.to_string(),
+                    BlobstoreBytes::from_bytes("test value"),
+                )
+                .await
+                .expect("put should succeed");
+        }
+        // Keys in another repo must not be returned.
+        base.put(
+            ctx,
+            "repo0002.content.blake2.03".to_string(),
+            BlobstoreBytes::from_bytes("test value"),
+        )
+        .await
+        .expect("put should succeed");
+
+        let res = prefixed
+            .enumerate_logical_prefix(ctx, "content.blake2.")
+            .await
+            .unwrap();
+        assert_eq!(
+            res.keys,
+            hashset! {
+                "content.blake2.01".to_string(),
+                "content.blake2.02".to_string(),
+            }
+        );
+
+        let res = prefixed
+            .enumerate_logical_prefix(ctx, "content.")
+            .await
+            .unwrap();
+        assert_eq!(res.keys.len(), 4);
+
+        let res = prefixed.enumerate_logical_prefix(ctx, "").await.unwrap();
+        assert_eq!(res.keys.len(), keys.len());
+
+        let res = prefixed
+            .enumerate_logical_prefix(ctx, "missing.")
+            .await
+            .unwrap();
+        assert!(res.keys.is_empty());
+    }
+
+    #[fbinit::test]
+    async fn test_enumerate_logical_prefix_pages(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let prefixed = PrefixBlobstore::new(Memblob::default().with_page_size(2), "repo0001.");
+
+        // The last key is the end of the enumerated range, and lands on the last page.
+        let keys = [
+            "content.blake2.01",
+            "content.blake2.02",
+            "content.blake2.03",
+            "content.blake2.04",
+            "content.blake2.05",
+            "content.blake2/",
+        ];
+        for key in keys {
+            prefixed
+                .put(
+                    ctx,
+                    key.to_string(),
+                    BlobstoreBytes::from_bytes("test value"),
+                )
+                .await
+                .expect("put should succeed");
+        }
+
+        // All three pages are followed, and the end key on the last one is dropped.
+        let res = prefixed
+            .enumerate_logical_prefix(ctx, "content.blake2.")
+            .await
+            .unwrap();
+        assert!(res.next_token.is_none());
+        assert_eq!(
+            res.keys,
+            keys[..5]
+                .iter()
+                .map(|key| key.to_string())
+                .collect::<HashSet<_>>()
+        );
+    }
+
+    #[fbinit::test]
+    async fn test_unlink(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let prefixed = PrefixBlobstore::new(base.clone(), "prefix123-");
+        let unprefixed_key = "foobar".to_string();
+        let prefixed_key = "prefix123-foobar".to_string();
+
+        prefixed
+            .put(
+                ctx,
+                unprefixed_key.clone(),
+                BlobstoreBytes::from_bytes("test foobar"),
+            )
+            .await
+            .expect("put should succeed");
+
+        // Unlinking the raw key in the inner store removes it from the prefixed store.
+        BlobstoreUnlinkOps::unlink(&base, ctx, &prefixed_key)
+            .await
+            .expect("unlink should succeed");
+        assert!(
+            !prefixed
+                .is_present(ctx, &unprefixed_key)
+                .await
+                .expect("is_present should succeed")
+                .assume_not_found_if_unsure()
+        );
+
+        // Unlinking through the prefixed store targets the prefixed key in the inner store.
+        prefixed
+            .put(
+                ctx,
+                unprefixed_key.clone(),
+                BlobstoreBytes::from_bytes("test foobar"),
+            )
+            .await
+            .expect("put should succeed");
+        prefixed
+            .unlink(ctx, &unprefixed_key)
+            .await
+            .expect("unlink should succeed");
+        assert!(
+            !base
+                .is_present(ctx, &prefixed_key)
+                .await
+                .expect("is_present should succeed")
+                .assume_not_found_if_unsure()
+        );
+        assert!(prefixed.unlink(ctx, &unprefixed_key).await.is_err());
+    }
+
+    #[fbinit::test]
+    async fn test_get_many(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let prefixed = PrefixBlobstore::new(base.clone(), "prefix123-");
+
+        prefixed
+            .put(
+                ctx,
+                "foo".to_string(),
+                BlobstoreBytes::from_bytes("test foo"),
+            )
+            .await
+            .expect("put should succeed");
+        prefixed
+            .put(
+                ctx,
+                "bar".to_string(),
+                BlobstoreBytes::from_bytes("test bar"),
+            )
+            .await
+            .expect("put should succeed");
+        // Only visible to the inner store, not through the prefix.
+        base.put(
+            ctx,
+            "baz".to_string(),
+            BlobstoreBytes::from_bytes("test baz"),
+        )
+        .await
+        .expect("put should succeed");
+
+        let keys = ["foo", "bar", "baz"];
+        let batched = prefixed
+            .get_many(ctx, &keys)
+            .await
+            .expect("get_many should succeed");
+        let buffered = prefixed
+            .get_many_buffered(ctx, &keys, 2)
+            .await
+            .expect("get_many_buffered should succeed");
+
+        for values in [batched, buffered] {
+            let values = values
+                .into_iter()
+                .map(|(key, value)| (key, value.map(BlobstoreGetData::into_raw_bytes)))
+                .collect::<HashMap<_, _>>();
+            assert_eq!(
+                values,
+                hashmap! {
+                    "foo".to_string() => Some(Bytes::from("test foo")),
+                    "bar".to_string() => Some(Bytes::from("test bar")),
+                    "baz".to_string() => None,
+                }
+            );
+        }
+    }
+
+    /// A store that reports a fixed health, and supports nothing else.
+    #[derive(Debug)]
+    struct FixedHealthBlobstore(BlobstoreHealth);
+
+    impl std::fmt::Display for FixedHealthBlobstore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "FixedHealthBlobstore")
+        }
+    }
+
+    #[async_trait]
+    impl Blobstore for FixedHealthBlobstore {
+        async fn get<'a>(
+            &'a self,
+            _ctx: &'a CoreContext,
+            _key: &'a str,
+        ) -> Result<Option<BlobstoreGetData>> {
+            Err(anyhow!("get not supported"))
+        }
+
+        async fn put<'a>(
+            &'a self,
+            _ctx: &'a CoreContext,
+            _key: String,
+            _value: BlobstoreBytes,
+        ) -> Result<()> {
+            Err(anyhow!("put not supported"))
+        }
+
+        async fn health_check<'a>(&'a self, _ctx: &'a CoreContext) -> Result<BlobstoreHealth> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[fbinit::test]
+    async fn test_health_check(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+
+        for health in [
+            BlobstoreHealth::Healthy,
+            BlobstoreHealth::Degraded("1 of 3 backends down".to_string()),
+            BlobstoreHealth::Unhealthy("all backends down".to_string()),
+        ] {
+            let prefixed = PrefixBlobstore::new(FixedHealthBlobstore(health.clone()), "prefix123-");
+            assert_eq!(prefixed.health_check(ctx).await.unwrap(), health);
+        }
+
+        // Stores that can't check their health say so, and the prefix doesn't hide that.
+        let prefixed = PrefixBlobstore::new(Memblob::default(), "prefix123-");
+        assert_eq!(
+            prefixed.health_check(ctx).await.unwrap(),
+            BlobstoreHealth::Unknown
+        );
+    }
 }
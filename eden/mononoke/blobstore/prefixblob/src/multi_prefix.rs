@@ -0,0 +1,215 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use anyhow::Result;
+use async_trait::async_trait;
+use blobstore::Blobstore;
+use blobstore::BlobstoreGetData;
+use blobstore::BlobstoreIsPresent;
+use context::CoreContext;
+use inlinable_string::InlinableString;
+use mononoke_types::BlobstoreBytes;
+
+/// A layer over an existing blobstore that writes keys under a primary prefix, but reads them
+/// from the primary prefix followed by an ordered list of fallback prefixes. Used while migrating
+/// keys from one prefix to another.
+#[derive(Clone, Debug)]
+pub struct MultiPrefixBlobstore<T> {
+    primary: InlinableString,
+    fallbacks: Vec<String>,
+    blobstore: T,
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for MultiPrefixBlobstore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MultiPrefixBlobstore<{}>", &self.blobstore)
+    }
+}
+
+impl<T> MultiPrefixBlobstore<T> {
+    pub fn new<S: Into<InlinableString>>(blobstore: T, primary: S, fallbacks: Vec<String>) -> Self {
+        Self {
+            primary: primary.into(),
+            fallbacks,
+            blobstore,
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.blobstore
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.blobstore
+    }
+
+    /// The key written by `put`.
+    #[inline]
+    pub fn prepend(&self, key: impl AsRef<str>) -> String {
+        [&self.primary, key.as_ref()].concat()
+    }
+
+    /// All keys that `get` tries, in order.
+    fn read_keys<'a>(&'a self, key: &'a str) -> impl Iterator<Item = String> + 'a {
+        std::iter::once(&*self.primary)
+            .chain(self.fallbacks.iter().map(String::as_str))
+            .map(move |prefix| [prefix, key].concat())
+    }
+}
+
+#[async_trait]
+impl<T: Blobstore> Blobstore for MultiPrefixBlobstore<T> {
+    async fn get<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<Option<BlobstoreGetData>> {
+        for prefixed_key in self.read_keys(key) {
+            if let Some(value) = self.blobstore.get(ctx, &prefixed_key).await? {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    #[inline]
+    async fn put<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<()> {
+        self.blobstore.put(ctx, self.prepend(key), value).await
+    }
+
+    async fn is_present<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<BlobstoreIsPresent> {
+        let mut unsure = None;
+        for prefixed_key in self.read_keys(key) {
+            match self.blobstore.is_present(ctx, &prefixed_key).await? {
+                BlobstoreIsPresent::Present => return Ok(BlobstoreIsPresent::Present),
+                BlobstoreIsPresent::Absent => {}
+                // Keep looking, a later prefix might still have the key.
+                BlobstoreIsPresent::ProbablyNotPresent(err) => {
+                    unsure.get_or_insert(err);
+                }
+            }
+        }
+        Ok(match unsure {
+            Some(err) => BlobstoreIsPresent::ProbablyNotPresent(err),
+            None => BlobstoreIsPresent::Absent,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use borrowed::borrowed;
+    use bytes::Bytes;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
+
+    use super::*;
+
+    #[fbinit::test]
+    async fn test_fallback(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let multi = MultiPrefixBlobstore::new(
+            base.clone(),
+            "v3-",
+            vec!["v2-".to_string(), "v1-".to_string()],
+        );
+
+        base.put(
+            ctx,
+            "v1-foo".to_string(),
+            BlobstoreBytes::from_bytes("legacy foo"),
+        )
+        .await
+        .expect("put should succeed");
+
+        assert_eq!(
+            multi
+                .get(ctx, "foo")
+                .await
+                .expect("get should succeed")
+                .expect("value should be present")
+                .into_raw_bytes(),
+            Bytes::from("legacy foo"),
+        );
+        assert!(
+            multi
+                .is_present(ctx, "foo")
+                .await
+                .expect("is_present should succeed")
+                .assume_not_found_if_unsure()
+        );
+        assert!(
+            multi
+                .get(ctx, "bar")
+                .await
+                .expect("get should succeed")
+                .is_none()
+        );
+        assert!(
+            !multi
+                .is_present(ctx, "bar")
+                .await
+                .expect("is_present should succeed")
+                .assume_not_found_if_unsure()
+        );
+    }
+
+    #[fbinit::test]
+    async fn test_primary_first(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let multi = MultiPrefixBlobstore::new(base.clone(), "v2-", vec!["v1-".to_string()]);
+
+        base.put(
+            ctx,
+            "v1-foo".to_string(),
+            BlobstoreBytes::from_bytes("legacy foo"),
+        )
+        .await
+        .expect("put should succeed");
+        multi
+            .put(
+                ctx,
+                "foo".to_string(),
+                BlobstoreBytes::from_bytes("new foo"),
+            )
+            .await
+            .expect("put should succeed");
+
+        // Writes only go to the primary prefix.
+        assert_eq!(
+            base.get(ctx, "v2-foo")
+                .await
+                .expect("get should succeed")
+                .expect("value should be present")
+                .into_raw_bytes(),
+            Bytes::from("new foo"),
+        );
+        // Reads prefer the primary prefix over the fallbacks.
+        assert_eq!(
+            multi
+                .get(ctx, "foo")
+                .await
+                .expect("get should succeed")
+                .expect("value should be present")
+                .into_raw_bytes(),
+            Bytes::from("new foo"),
+        );
+    }
+}
@@ -0,0 +1,26 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use anyhow::Error;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ErrorKind {
+    #[error("No backend returned {key}, and {} backends failed: {errors:?}", .errors.len())]
+    GetFailed { key: String, errors: Vec<Error> },
+    #[error(
+        "Put of {key} succeeded on {successes} backends, but {quorum} are required: {errors:?}"
+    )]
+    WriteQuorumNotMet {
+        key: String,
+        successes: usize,
+        quorum: usize,
+        errors: Vec<Error>,
+    },
+    #[error("Write quorum must be between 1 and {blobstores}, got {quorum}")]
+    InvalidWriteQuorum { quorum: usize, blobstores: usize },
+}
@@ -0,0 +1,240 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::fmt;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use blobstore::Blobstore;
+use blobstore::BlobstoreGetData;
+use context::CoreContext;
+use futures::stream::FuturesUnordered;
+use futures::stream::StreamExt;
+use mononoke_types::BlobstoreBytes;
+
+mod errors;
+pub use crate::errors::ErrorKind;
+
+/// A blobstore over several replicated backends. Reads go to every backend concurrently and
+/// return the first value found, so they succeed as long as one backend has the value. Writes go
+/// to every backend, and succeed once `write_quorum` of them have succeeded.
+#[derive(Clone, Debug)]
+pub struct QuorumReadBlobstore {
+    blobstores: Vec<Arc<dyn Blobstore>>,
+    write_quorum: usize,
+}
+
+impl fmt::Display for QuorumReadBlobstore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let blobstores: Vec<_> = self.blobstores.iter().map(|b| b.to_string()).collect();
+        write!(f, "QuorumReadBlobstore<{}>", blobstores.join(", "))
+    }
+}
+
+impl QuorumReadBlobstore {
+    /// Fails if `write_quorum` is 0, or more than the number of backends, as such a quorum
+    /// could never be met.
+    pub fn new(blobstores: Vec<Arc<dyn Blobstore>>, write_quorum: usize) -> Result<Self> {
+        if write_quorum == 0 || write_quorum > blobstores.len() {
+            return Err(ErrorKind::InvalidWriteQuorum {
+                quorum: write_quorum,
+                blobstores: blobstores.len(),
+            }
+            .into());
+        }
+
+        Ok(Self {
+            blobstores,
+            write_quorum,
+        })
+    }
+}
+
+#[async_trait]
+impl Blobstore for QuorumReadBlobstore {
+    /// Returns the first value any backend finds. Missing values are only an error if some
+    /// backend failed, since that backend might have had the value.
+    async fn get<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<Option<BlobstoreGetData>> {
+        let mut gets: FuturesUnordered<_> = self
+            .blobstores
+            .iter()
+            .map(|blobstore| blobstore.get(ctx, key))
+            .collect();
+
+        let mut errors = Vec::new();
+        while let Some(result) = gets.next().await {
+            match result {
+                Ok(Some(value)) => return Ok(Some(value)),
+                Ok(None) => {}
+                Err(e) => errors.push(e),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(None)
+        } else {
+            Err(ErrorKind::GetFailed {
+                key: key.to_owned(),
+                errors,
+            }
+            .into())
+        }
+    }
+
+    async fn put<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<()> {
+        let mut puts: FuturesUnordered<_> = self
+            .blobstores
+            .iter()
+            .map(|blobstore| blobstore.put(ctx, key.clone(), value.clone()))
+            .collect();
+
+        let mut successes = 0;
+        let mut errors = Vec::new();
+        while let Some(result) = puts.next().await {
+            match result {
+                Ok(()) => successes += 1,
+                Err(e) => errors.push(e),
+            }
+        }
+
+        if successes >= self.write_quorum {
+            Ok(())
+        } else {
+            Err(ErrorKind::WriteQuorumNotMet {
+                key,
+                successes,
+                quorum: self.write_quorum,
+                errors,
+            }
+            .into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use blobstore_test_utils::FailingBlobstore;
+    use blobstore_test_utils::FailurePolicy;
+    use borrowed::borrowed;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
+
+    use super::*;
+
+    fn failing() -> Arc<dyn Blobstore> {
+        Arc::new(FailingBlobstore::new(
+            Memblob::default(),
+            FailurePolicy::Always,
+        ))
+    }
+
+    #[fbinit::test]
+    async fn test_get_tolerates_failures(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let good = Memblob::default();
+        let value = BlobstoreBytes::from_bytes("value");
+        good.put(ctx, "key".to_owned(), value.clone())
+            .await
+            .unwrap();
+
+        let blobstore = QuorumReadBlobstore::new(vec![failing(), Arc::new(good)], 1).unwrap();
+        let got = blobstore.get(ctx, "key").await.unwrap().unwrap();
+        assert_eq!(got.into_bytes(), value);
+
+        // A missing value is an error if a backend that might have had it failed...
+        let err = blobstore.get(ctx, "missing").await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::GetFailed { errors, .. }) if errors.len() == 1
+        ));
+
+        // ...but not if every backend answered.
+        let healthy = QuorumReadBlobstore::new(
+            vec![Arc::new(Memblob::default()), Arc::new(Memblob::default())],
+            1,
+        )
+        .unwrap();
+        assert!(healthy.get(ctx, "missing").await.unwrap().is_none());
+
+        let all_failing = QuorumReadBlobstore::new(vec![failing(), failing()], 1).unwrap();
+        assert!(all_failing.get(ctx, "key").await.is_err());
+    }
+
+    #[fbinit::test]
+    async fn test_put_quorum(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let value = BlobstoreBytes::from_bytes("value");
+        let good1 = Memblob::default();
+        let good2 = Memblob::default();
+        let backends: Vec<Arc<dyn Blobstore>> =
+            vec![Arc::new(good1.clone()), failing(), Arc::new(good2.clone())];
+
+        // Two of the three backends succeed, which meets a quorum of two.
+        let blobstore = QuorumReadBlobstore::new(backends.clone(), 2).unwrap();
+        blobstore
+            .put(ctx, "key".to_owned(), value.clone())
+            .await
+            .unwrap();
+        for good in [&good1, &good2] {
+            let got = good.get(ctx, "key").await.unwrap().unwrap();
+            assert_eq!(got.into_bytes(), value);
+        }
+
+        // But not a quorum of three.
+        let blobstore = QuorumReadBlobstore::new(backends, 3).unwrap();
+        let err = blobstore
+            .put(ctx, "key2".to_owned(), value)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::WriteQuorumNotMet {
+                successes: 2,
+                quorum: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_write_quorum() {
+        let backends: Vec<Arc<dyn Blobstore>> =
+            vec![Arc::new(Memblob::default()), Arc::new(Memblob::default())];
+
+        let err = QuorumReadBlobstore::new(backends.clone(), 0).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::InvalidWriteQuorum {
+                quorum: 0,
+                blobstores: 2
+            })
+        ));
+
+        let err = QuorumReadBlobstore::new(backends.clone(), 3).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::InvalidWriteQuorum {
+                quorum: 3,
+                blobstores: 2
+            })
+        ));
+
+        assert!(QuorumReadBlobstore::new(backends, 2).is_ok());
+    }
+}
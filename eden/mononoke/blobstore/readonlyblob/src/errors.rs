@@ -9,6 +9,6 @@ use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum ErrorKind {
-    #[error("Attempt to put to ReadOnlyBlobstore for key {0}")]
-    ReadOnlyPut(String),
+    #[error("Attempt to modify ReadOnlyBlobstore for key {key}")]
+    ReadOnlyViolation { key: String },
 }
@@ -11,6 +11,7 @@ use blobstore::Blobstore;
 use blobstore::BlobstoreGetData;
 use blobstore::BlobstoreIsPresent;
 use blobstore::BlobstorePutOps;
+use blobstore::BlobstoreUnlinkOps;
 use blobstore::OverwriteStatus;
 use blobstore::PutBehaviour;
 use context::CoreContext;
@@ -54,7 +55,7 @@ impl<T: Blobstore> Blobstore for ReadOnlyBlobstore<T> {
         key: String,
         _value: BlobstoreBytes,
     ) -> Result<()> {
-        Err(ErrorKind::ReadOnlyPut(key).into())
+        Err(ErrorKind::ReadOnlyViolation { key }.into())
     }
 
     #[inline]
@@ -68,7 +69,7 @@ impl<T: Blobstore> Blobstore for ReadOnlyBlobstore<T> {
 }
 
 #[async_trait]
-impl<T: BlobstorePutOps> BlobstorePutOps for ReadOnlyBlobstore<T> {
+impl<T: Blobstore> BlobstorePutOps for ReadOnlyBlobstore<T> {
     async fn put_explicit<'a>(
         &'a self,
         _ctx: &'a CoreContext,
@@ -76,7 +77,7 @@ impl<T: BlobstorePutOps> BlobstorePutOps for ReadOnlyBlobstore<T> {
         _value: BlobstoreBytes,
         _put_behaviour: PutBehaviour,
     ) -> Result<OverwriteStatus> {
-        Err(ErrorKind::ReadOnlyPut(key).into())
+        Err(ErrorKind::ReadOnlyViolation { key }.into())
     }
 
     async fn put_with_status<'a>(
@@ -85,7 +86,17 @@ impl<T: BlobstorePutOps> BlobstorePutOps for ReadOnlyBlobstore<T> {
         key: String,
         _value: BlobstoreBytes,
     ) -> Result<OverwriteStatus> {
-        Err(ErrorKind::ReadOnlyPut(key).into())
+        Err(ErrorKind::ReadOnlyViolation { key }.into())
+    }
+}
+
+#[async_trait]
+impl<T: Blobstore> BlobstoreUnlinkOps for ReadOnlyBlobstore<T> {
+    async fn unlink<'a>(&'a self, _ctx: &'a CoreContext, key: &'a str) -> Result<()> {
+        Err(ErrorKind::ReadOnlyViolation {
+            key: key.to_owned(),
+        }
+        .into())
     }
 }
 
@@ -136,7 +147,10 @@ mod test {
                 BlobstoreBytes::from_bytes("test foobar"),
             )
             .await;
-        assert!(r.is_err());
+        assert!(matches!(
+            r.unwrap_err().downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::ReadOnlyViolation { key }) if key == "foobar"
+        ));
         let base_present = base
             .is_present(ctx, key)
             .await
@@ -144,4 +158,44 @@ mod test {
             .assume_not_found_if_unsure();
         assert!(!base_present);
     }
+
+    #[fbinit::test]
+    async fn test_reads_pass_writes_fail(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let wrapper = ReadOnlyBlobstore::new(base.clone());
+        let value = BlobstoreBytes::from_bytes("test foobar");
+        base.put(ctx, "present".to_owned(), value.clone())
+            .await
+            .unwrap();
+
+        let got = wrapper.get(ctx, "present").await.unwrap();
+        assert_eq!(got.map(|data| data.into_bytes()), Some(value.clone()));
+        assert!(
+            wrapper
+                .is_present(ctx, "present")
+                .await
+                .unwrap()
+                .assume_not_found_if_unsure()
+        );
+
+        let err = wrapper
+            .put(ctx, "foobar".to_owned(), value)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("foobar"));
+        match err.downcast_ref::<ErrorKind>() {
+            Some(ErrorKind::ReadOnlyViolation { key }) => assert_eq!(key, "foobar"),
+            other => panic!("Unexpected error: {:?}", other),
+        }
+
+        let err = wrapper.unlink(ctx, "present").await.unwrap_err();
+        assert!(err.to_string().contains("present"));
+        match err.downcast_ref::<ErrorKind>() {
+            Some(ErrorKind::ReadOnlyViolation { key }) => assert_eq!(key, "present"),
+            other => panic!("Unexpected error: {:?}", other),
+        }
+        assert!(base.get(ctx, "present").await.unwrap().is_some());
+    }
 }
@@ -11,4 +11,6 @@ use thiserror::Error;
 pub enum ErrorKind {
     #[error("The blob {0} is censored.\n Task/Sev: {1}")]
     Censored(String, String),
+    #[error("The blob {key} is redacted")]
+    RedactedBlob { key: String },
 }
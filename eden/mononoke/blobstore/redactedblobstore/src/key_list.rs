@@ -0,0 +1,161 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use anyhow::Result;
+use async_trait::async_trait;
+use blobstore::Blobstore;
+use blobstore::BlobstoreGetData;
+use blobstore::BlobstoreIsPresent;
+use blobstore::BlobstorePutOps;
+use blobstore::OverwriteStatus;
+use blobstore::PutBehaviour;
+use context::CoreContext;
+use mononoke_types::BlobstoreBytes;
+use mononoke_types::RedactionKeyList;
+
+use crate::ErrorKind;
+
+/// A layer over an existing blobstore that refuses to return the contents of keys in a
+/// `RedactionKeyList`, failing with `ErrorKind::RedactedBlob` instead. Unlike
+/// `RedactedBlobstore`, this needs no redaction config, and it doesn't log accesses or block
+/// writes, so it suits tools that are handed a single key list.
+#[derive(Debug, Clone)]
+pub struct KeyListRedactedBlobstore<T> {
+    blobstore: T,
+    redacted: RedactionKeyList,
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for KeyListRedactedBlobstore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "KeyListRedactedBlobstore<{}>", &self.blobstore)
+    }
+}
+
+impl<T> KeyListRedactedBlobstore<T> {
+    pub fn new(blobstore: T, redacted: RedactionKeyList) -> Self {
+        Self {
+            blobstore,
+            redacted,
+        }
+    }
+}
+
+#[async_trait]
+impl<T: Blobstore> Blobstore for KeyListRedactedBlobstore<T> {
+    async fn get<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<Option<BlobstoreGetData>> {
+        if self.redacted.contains(key) {
+            return Err(ErrorKind::RedactedBlob {
+                key: key.to_string(),
+            }
+            .into());
+        }
+        self.blobstore.get(ctx, key).await
+    }
+
+    #[inline]
+    async fn put<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<()> {
+        self.blobstore.put(ctx, key, value).await
+    }
+
+    #[inline]
+    async fn is_present<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<BlobstoreIsPresent> {
+        self.blobstore.is_present(ctx, key).await
+    }
+}
+
+#[async_trait]
+impl<T: BlobstorePutOps> BlobstorePutOps for KeyListRedactedBlobstore<T> {
+    async fn put_explicit<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+        put_behaviour: PutBehaviour,
+    ) -> Result<OverwriteStatus> {
+        self.blobstore
+            .put_explicit(ctx, key, value, put_behaviour)
+            .await
+    }
+
+    async fn put_with_status<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<OverwriteStatus> {
+        self.blobstore.put_with_status(ctx, key, value).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use borrowed::borrowed;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
+
+    use super::*;
+    use crate::has_redaction_root_cause;
+
+    #[fbinit::test]
+    async fn test_key_list_redaction(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+
+        let base = Memblob::default();
+        let blob = KeyListRedactedBlobstore::new(
+            base.clone(),
+            RedactionKeyList::new(vec!["bar".to_owned()]),
+        );
+
+        // Puts are unaffected, even for redacted keys.
+        blob.put(
+            ctx,
+            "foo".to_owned(),
+            BlobstoreBytes::from_bytes("test foo"),
+        )
+        .await?;
+        blob.put(
+            ctx,
+            "bar".to_owned(),
+            BlobstoreBytes::from_bytes("test bar"),
+        )
+        .await?;
+        assert!(base.get(ctx, "bar").await?.is_some());
+
+        let err = blob
+            .get(ctx, "bar")
+            .await
+            .expect_err("the key should be redacted");
+        assert!(has_redaction_root_cause(&err));
+        assert_matches!(
+            err.downcast::<ErrorKind>(),
+            Ok(ErrorKind::RedactedBlob { ref key }) if key == "bar"
+        );
+
+        let val = blob.get(ctx, "foo").await?;
+        assert_eq!(
+            val.map(|val| val.into_bytes()),
+            Some(BlobstoreBytes::from_bytes("test foo"))
+        );
+
+        Ok(())
+    }
+}
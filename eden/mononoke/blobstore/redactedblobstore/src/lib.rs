@@ -6,6 +6,7 @@
  */
 
 mod errors;
+mod key_list;
 mod redaction_config_blobstore;
 pub mod store;
 
@@ -26,6 +27,7 @@ use slog::debug;
 use tunables::tunables;
 
 pub use crate::errors::ErrorKind;
+pub use crate::key_list::KeyListRedactedBlobstore;
 pub use crate::redaction_config_blobstore::ArcRedactionConfigBlobstore;
 pub use crate::redaction_config_blobstore::RedactionConfigBlobstore;
 pub use crate::store::RedactedBlobs;
@@ -261,7 +263,7 @@ impl<B: Blobstore> Blobstore for RedactedBlobstore<B> {
 
 pub fn has_redaction_root_cause(e: &Error) -> bool {
     match e.root_cause().downcast_ref::<ErrorKind>() {
-        Some(ErrorKind::Censored(_, _)) => true,
+        Some(ErrorKind::Censored(_, _)) | Some(ErrorKind::RedactedBlob { .. }) => true,
         None => false,
     }
 }
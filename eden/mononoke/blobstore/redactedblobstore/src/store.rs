@@ -204,7 +204,7 @@ impl InnerConfig {
                         .await
                         .with_context(|| format!("Keylist with id {} not found", redaction.id))?;
                     let keys_with_metadata = keylist
-                        .keys
+                        .into_keys()
                         .into_iter()
                         .map(|key| {
                             (
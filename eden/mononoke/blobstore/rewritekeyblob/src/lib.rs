@@ -0,0 +1,332 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use blobstore::Blobstore;
+use blobstore::BlobstoreEnumerationData;
+use blobstore::BlobstoreGetData;
+use blobstore::BlobstoreIsPresent;
+use blobstore::BlobstoreKeyParam;
+use blobstore::BlobstoreKeyRange;
+use blobstore::BlobstoreKeySource;
+use blobstore::BlobstorePutOps;
+use blobstore::OverwriteStatus;
+use blobstore::PutBehaviour;
+use context::CoreContext;
+use mononoke_types::BlobstoreBytes;
+
+mod errors;
+pub use crate::errors::ErrorKind;
+
+/// A function mapping one key to another.
+pub type KeyRewrite = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// A layer over an existing blobstore that rewrites each key before passing it on, for example
+/// to read and write keys in a new scheme while callers still use the old one. This is a
+/// generalization of `PrefixBlobstore` to arbitrary key transforms.
+///
+/// Enumerating keys needs the inverse of the rewrite, to turn the inner blobstore's keys back
+/// into the keys callers use. Enumerated ranges are rewritten too, so the rewrite must preserve
+/// the ordering of keys for enumeration to return the right keys.
+#[derive(Clone)]
+pub struct RewriteKeyBlobstore<T> {
+    blobstore: T,
+    rewrite: KeyRewrite,
+    inverse: Option<KeyRewrite>,
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for RewriteKeyBlobstore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RewriteKeyBlobstore<{}>", &self.blobstore)
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for RewriteKeyBlobstore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RewriteKeyBlobstore")
+            .field("blobstore", &self.blobstore)
+            .field("has_inverse", &self.inverse.is_some())
+            .finish()
+    }
+}
+
+impl<T> RewriteKeyBlobstore<T> {
+    /// Create a blobstore that rewrites keys with `rewrite`. It can't enumerate keys.
+    pub fn new(blobstore: T, rewrite: KeyRewrite) -> Self {
+        Self {
+            blobstore,
+            rewrite,
+            inverse: None,
+        }
+    }
+
+    /// Create a blobstore that rewrites keys with `rewrite`, and maps enumerated keys back with
+    /// `inverse`. Enumerated keys that `rewrite` doesn't produce from their inverse weren't
+    /// written through this blobstore, and are left out.
+    pub fn new_with_inverse(blobstore: T, rewrite: KeyRewrite, inverse: KeyRewrite) -> Self {
+        Self {
+            blobstore,
+            rewrite,
+            inverse: Some(inverse),
+        }
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.blobstore
+    }
+
+    #[inline]
+    fn rewrite(&self, key: &str) -> String {
+        (self.rewrite)(key)
+    }
+
+    /// Rewrite the bounds of `range`. An empty bound means the range is unbounded on that
+    /// side, so it is left empty rather than rewritten into a concrete key.
+    fn rewrite_range(&self, range: &BlobstoreKeyRange) -> BlobstoreKeyRange {
+        let rewrite_bound = |bound: &str| {
+            if bound.is_empty() {
+                String::new()
+            } else {
+                self.rewrite(bound)
+            }
+        };
+        BlobstoreKeyRange {
+            begin_key: rewrite_bound(&range.begin_key),
+            end_key: rewrite_bound(&range.end_key),
+        }
+    }
+}
+
+#[async_trait]
+impl<T: Blobstore> Blobstore for RewriteKeyBlobstore<T> {
+    #[inline]
+    async fn get<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<Option<BlobstoreGetData>> {
+        self.blobstore.get(ctx, &self.rewrite(key)).await
+    }
+
+    #[inline]
+    async fn put<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<()> {
+        self.blobstore.put(ctx, self.rewrite(&key), value).await
+    }
+
+    #[inline]
+    async fn is_present<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<BlobstoreIsPresent> {
+        self.blobstore.is_present(ctx, &self.rewrite(key)).await
+    }
+}
+
+#[async_trait]
+impl<T: BlobstorePutOps> BlobstorePutOps for RewriteKeyBlobstore<T> {
+    async fn put_explicit<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+        put_behaviour: PutBehaviour,
+    ) -> Result<OverwriteStatus> {
+        self.blobstore
+            .put_explicit(ctx, self.rewrite(&key), value, put_behaviour)
+            .await
+    }
+
+    async fn put_with_status<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<OverwriteStatus> {
+        self.blobstore
+            .put_with_status(ctx, self.rewrite(&key), value)
+            .await
+    }
+}
+
+#[async_trait]
+impl<T: BlobstoreKeySource> BlobstoreKeySource for RewriteKeyBlobstore<T> {
+    async fn enumerate<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        range: &'a BlobstoreKeyParam,
+    ) -> Result<BlobstoreEnumerationData> {
+        let inverse = self.inverse.as_ref().ok_or(ErrorKind::NoInverse)?;
+        let new_param = match range {
+            BlobstoreKeyParam::Start(range) => BlobstoreKeyParam::Start(self.rewrite_range(range)),
+            BlobstoreKeyParam::StartReversed(range) => {
+                BlobstoreKeyParam::StartReversed(self.rewrite_range(range))
+            }
+            // Continuations come from the inner blobstore, so are already rewritten.
+            p @ BlobstoreKeyParam::Continuation(_) => p.clone(),
+        };
+        let mut res = self.blobstore.enumerate(ctx, &new_param).await?;
+        // Unbounded ranges can reach inner keys that no key rewrites to, so only keep the keys
+        // that map back to themselves.
+        res.keys = res
+            .keys
+            .iter()
+            .filter_map(|k| {
+                let key = inverse(k);
+                (self.rewrite(&key) == *k).then_some(key)
+            })
+            .collect();
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use borrowed::borrowed;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
+
+    use super::*;
+
+    fn uppercase() -> KeyRewrite {
+        Arc::new(|key: &str| key.to_uppercase())
+    }
+
+    fn lowercase() -> KeyRewrite {
+        Arc::new(|key: &str| key.to_lowercase())
+    }
+
+    fn add_scheme() -> KeyRewrite {
+        Arc::new(|key: &str| format!("v2.{}", key))
+    }
+
+    fn strip_scheme() -> KeyRewrite {
+        Arc::new(|key: &str| key.strip_prefix("v2.").unwrap_or(key).to_owned())
+    }
+
+    #[fbinit::test]
+    async fn test_get_put(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let wrapper = RewriteKeyBlobstore::new(base.clone(), uppercase());
+
+        let value = BlobstoreBytes::from_bytes("value");
+        wrapper
+            .put(ctx, "content.blake2.abc".to_owned(), value.clone())
+            .await
+            .unwrap();
+
+        // The value is stored under the rewritten key.
+        assert!(base.get(ctx, "content.blake2.abc").await.unwrap().is_none());
+        let stored = base.get(ctx, "CONTENT.BLAKE2.ABC").await.unwrap().unwrap();
+        assert_eq!(stored.into_bytes(), value);
+
+        // And read back through the rewrite.
+        let got = wrapper
+            .get(ctx, "content.blake2.abc")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(got.into_bytes(), value);
+        assert!(
+            wrapper
+                .is_present(ctx, "content.blake2.abc")
+                .await
+                .unwrap()
+                .assume_not_found_if_unsure()
+        );
+        assert!(
+            wrapper
+                .get(ctx, "content.blake2.def")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[fbinit::test]
+    async fn test_enumerate(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let wrapper = RewriteKeyBlobstore::new_with_inverse(base.clone(), uppercase(), lowercase());
+
+        for key in ["content.a", "content.b", "filenode.a"] {
+            wrapper
+                .put(ctx, key.to_owned(), BlobstoreBytes::from_bytes("value"))
+                .await
+                .unwrap();
+        }
+
+        let range = BlobstoreKeyParam::Start(BlobstoreKeyRange {
+            begin_key: "content.".to_owned(),
+            end_key: "content.z".to_owned(),
+        });
+        let res = wrapper.enumerate(ctx, &range).await.unwrap();
+        assert_eq!(
+            res.keys,
+            HashSet::from(["content.a".to_owned(), "content.b".to_owned()])
+        );
+
+        // Without an inverse, enumerated keys can't be mapped back.
+        let no_inverse = RewriteKeyBlobstore::new(base, uppercase());
+        let err = no_inverse.enumerate(ctx, &range).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::NoInverse)
+        ));
+    }
+
+    #[fbinit::test]
+    async fn test_enumerate_open_bounds(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let wrapper =
+            RewriteKeyBlobstore::new_with_inverse(base.clone(), add_scheme(), strip_scheme());
+
+        // Keys in the old scheme, which the rewrite never produces.
+        for key in ["a", "legacy", "zzz"] {
+            base.put(ctx, key.to_owned(), BlobstoreBytes::from_bytes("value"))
+                .await
+                .unwrap();
+        }
+
+        for key in ["a", "b", "c"] {
+            wrapper
+                .put(ctx, key.to_owned(), BlobstoreBytes::from_bytes("value"))
+                .await
+                .unwrap();
+        }
+
+        let enumerate = |begin_key: &str, end_key: &str| {
+            let range = BlobstoreKeyParam::Start(BlobstoreKeyRange {
+                begin_key: begin_key.to_owned(),
+                end_key: end_key.to_owned(),
+            });
+            async move { wrapper.enumerate(ctx, &range).await.unwrap().keys }
+        };
+        let keys = |keys: &[&str]| keys.iter().map(|k| k.to_string()).collect::<HashSet<_>>();
+
+        // Empty bounds are unbounded, not the rewrite of the empty key, and foreign keys in
+        // the inner blobstore are skipped.
+        assert_eq!(enumerate("", "").await, keys(&["a", "b", "c"]));
+        assert_eq!(enumerate("b", "").await, keys(&["b", "c"]));
+        assert_eq!(enumerate("", "b").await, keys(&["a", "b"]));
+    }
+}
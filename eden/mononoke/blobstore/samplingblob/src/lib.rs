@@ -5,7 +5,9 @@
  * GNU General Public License version 2.
  */
 
+use std::sync::mpsc::Sender;
 use std::sync::Arc;
+use std::sync::Mutex;
 
 use anyhow::Result;
 use async_trait::async_trait;
@@ -18,6 +20,9 @@ use blobstore::PutBehaviour;
 use context::CoreContext;
 use metaconfig_types::BlobstoreId;
 use mononoke_types::BlobstoreBytes;
+use rand::rngs::SmallRng;
+use rand::Rng;
+use rand::SeedableRng;
 
 pub trait SamplingHandler: std::fmt::Debug + Send + Sync {
     fn sample_get(
@@ -99,6 +104,94 @@ impl<T: Blobstore> Blobstore for SamplingBlobstore<T> {
     }
 }
 
+/// The kind of operation recorded by a `TracingSamplingHandler`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlobstoreAccessKind {
+    GetHit,
+    GetMiss,
+    Put,
+    IsPresent,
+}
+
+/// A single sampled blobstore operation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlobstoreAccess {
+    pub key: String,
+    pub kind: BlobstoreAccessKind,
+}
+
+/// A sampling handler for debugging access patterns: it sends a record of a
+/// fraction of the operations it sees to a channel.  Each operation is
+/// sampled independently, using an RNG that can be seeded to make the
+/// sampling deterministic.
+#[derive(Debug)]
+pub struct TracingSamplingHandler {
+    sample_rate: f64,
+    state: Mutex<(SmallRng, Sender<BlobstoreAccess>)>,
+}
+
+impl TracingSamplingHandler {
+    /// Sample operations with probability `sample_rate`, between 0.0 and 1.0.
+    pub fn new(sample_rate: f64, sender: Sender<BlobstoreAccess>) -> Self {
+        Self::with_rng(sample_rate, sender, SmallRng::from_entropy())
+    }
+
+    pub fn with_seed(sample_rate: f64, sender: Sender<BlobstoreAccess>, seed: u64) -> Self {
+        Self::with_rng(sample_rate, sender, SmallRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(sample_rate: f64, sender: Sender<BlobstoreAccess>, rng: SmallRng) -> Self {
+        Self {
+            sample_rate,
+            state: Mutex::new((rng, sender)),
+        }
+    }
+
+    fn record(&self, key: &str, kind: BlobstoreAccessKind) {
+        let (rng, sender) = &mut *self.state.lock().expect("lock poisoned");
+        if rng.gen::<f64>() < self.sample_rate {
+            // Tracing is best effort: if nobody is listening any more, the
+            // operation should still succeed.
+            let _ = sender.send(BlobstoreAccess {
+                key: key.to_owned(),
+                kind,
+            });
+        }
+    }
+}
+
+impl SamplingHandler for TracingSamplingHandler {
+    fn sample_get(
+        &self,
+        _ctx: &CoreContext,
+        key: &str,
+        value: Option<&BlobstoreGetData>,
+    ) -> Result<()> {
+        let kind = if value.is_some() {
+            BlobstoreAccessKind::GetHit
+        } else {
+            BlobstoreAccessKind::GetMiss
+        };
+        self.record(key, kind);
+        Ok(())
+    }
+
+    fn sample_put(&self, _ctx: &CoreContext, key: &str, _value: &BlobstoreBytes) -> Result<()> {
+        self.record(key, BlobstoreAccessKind::Put);
+        Ok(())
+    }
+
+    fn sample_is_present(
+        &self,
+        _ctx: &CoreContext,
+        key: &str,
+        _value: &BlobstoreIsPresent,
+    ) -> Result<()> {
+        self.record(key, BlobstoreAccessKind::IsPresent);
+        Ok(())
+    }
+}
+
 /// Used when you need the BlobstoreId (where there is one) in the sample
 pub trait ComponentSamplingHandler: std::fmt::Debug + Send + Sync {
     fn sample_get(
@@ -232,6 +325,7 @@ impl<T: BlobstorePutOps> BlobstorePutOps for SamplingBlobstorePutOps<T> {
 mod test {
     use std::sync::atomic::AtomicBool;
     use std::sync::atomic::Ordering;
+    use std::sync::mpsc::channel;
 
     use borrowed::borrowed;
     use context::SamplingKey;
@@ -398,4 +492,64 @@ mod test {
         let was_sampled = handler.sampled.load(Ordering::Relaxed);
         assert!(was_sampled);
     }
+
+    #[fbinit::test]
+    async fn test_tracing_records_every_operation(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let (sender, receiver) = channel();
+        let handler = Arc::new(TracingSamplingHandler::with_seed(1.0, sender, 0));
+        let wrapper = SamplingBlobstore::new(Memblob::default(), handler);
+
+        wrapper
+            .put(ctx, "foo".to_owned(), BlobstoreBytes::from_bytes("foo"))
+            .await
+            .unwrap();
+        assert!(wrapper.get(ctx, "foo").await.unwrap().is_some());
+        assert!(wrapper.get(ctx, "bar").await.unwrap().is_none());
+        wrapper.is_present(ctx, "foo").await.unwrap();
+
+        let access = |key: &str, kind| BlobstoreAccess {
+            key: key.to_owned(),
+            kind,
+        };
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<_>>(),
+            vec![
+                access("foo", BlobstoreAccessKind::Put),
+                access("foo", BlobstoreAccessKind::GetHit),
+                access("bar", BlobstoreAccessKind::GetMiss),
+                access("foo", BlobstoreAccessKind::IsPresent),
+            ]
+        );
+    }
+
+    #[fbinit::test]
+    async fn test_tracing_sample_rate(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let (sender, receiver) = channel();
+        let handler = Arc::new(TracingSamplingHandler::with_seed(0.0, sender, 0));
+        let wrapper = SamplingBlobstore::new(Memblob::default(), handler);
+
+        wrapper
+            .put(ctx, "foo".to_owned(), BlobstoreBytes::from_bytes("foo"))
+            .await
+            .unwrap();
+        assert!(wrapper.get(ctx, "foo").await.unwrap().is_some());
+        assert_eq!(receiver.try_iter().count(), 0);
+
+        // The same seed samples the same operations.
+        let sampled = |seed| {
+            let (sender, receiver) = channel();
+            let handler = TracingSamplingHandler::with_seed(0.5, sender, seed);
+            for i in 0..100 {
+                handler.record(&format!("key{}", i), BlobstoreAccessKind::Put);
+            }
+            receiver.try_iter().map(|a| a.key).collect::<Vec<_>>()
+        };
+        let first = sampled(42);
+        assert!(!first.is_empty() && first.len() < 100);
+        assert_eq!(first, sampled(42));
+    }
 }
@@ -0,0 +1,169 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use anyhow::Result;
+use async_trait::async_trait;
+use blobstore::Blobstore;
+use blobstore::BlobstoreGetData;
+use blobstore::BlobstoreIsPresent;
+use blobstore::BlobstorePutOps;
+use blobstore::ErrorKind;
+use blobstore::OverwriteStatus;
+use blobstore::PutBehaviour;
+use context::CoreContext;
+use mononoke_types::BlobstoreBytes;
+
+/// A layer over an existing blobstore that rejects puts of values larger than a fixed number of
+/// bytes. Reads are passed through unchanged.
+#[derive(Clone, Debug)]
+pub struct SizeLimitBlobstore<T> {
+    blobstore: T,
+    limit: u64,
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for SizeLimitBlobstore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SizeLimitBlobstore<{}>", &self.blobstore)
+    }
+}
+
+impl<T> SizeLimitBlobstore<T> {
+    pub fn new(blobstore: T, limit: u64) -> Self {
+        Self { blobstore, limit }
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    fn check(&self, key: &str, value: &BlobstoreBytes) -> Result<()> {
+        let size = value.len() as u64;
+        if size > self.limit {
+            return Err(ErrorKind::blob_too_large(key, size, self.limit).into());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T: Blobstore> Blobstore for SizeLimitBlobstore<T> {
+    #[inline]
+    async fn get<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<Option<BlobstoreGetData>> {
+        self.blobstore.get(ctx, key).await
+    }
+
+    async fn put<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<()> {
+        self.check(&key, &value)?;
+        self.blobstore.put(ctx, key, value).await
+    }
+
+    #[inline]
+    async fn is_present<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<BlobstoreIsPresent> {
+        self.blobstore.is_present(ctx, key).await
+    }
+}
+
+#[async_trait]
+impl<T: BlobstorePutOps> BlobstorePutOps for SizeLimitBlobstore<T> {
+    async fn put_explicit<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+        put_behaviour: PutBehaviour,
+    ) -> Result<OverwriteStatus> {
+        self.check(&key, &value)?;
+        self.blobstore
+            .put_explicit(ctx, key, value, put_behaviour)
+            .await
+    }
+
+    async fn put_with_status<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<OverwriteStatus> {
+        self.check(&key, &value)?;
+        self.blobstore.put_with_status(ctx, key, value).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use borrowed::borrowed;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
+
+    use super::*;
+
+    #[fbinit::test]
+    async fn test_size_limit(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let wrapper = SizeLimitBlobstore::new(base.clone(), 10);
+
+        // At and just under the limit.
+        for value in ["123456789", "1234567890"] {
+            let value = BlobstoreBytes::from_bytes(value);
+            wrapper
+                .put(ctx, "small".to_owned(), value.clone())
+                .await
+                .unwrap();
+            let got = wrapper.get(ctx, "small").await.unwrap().unwrap();
+            assert_eq!(got.into_bytes(), value);
+        }
+
+        // Just over the limit.
+        let value = BlobstoreBytes::from_bytes("12345678901");
+        let err = wrapper
+            .put(ctx, "large".to_owned(), value.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::BlobTooLarge { key, size: 11, limit: 10 }) if key == "large"
+        ));
+        let err = wrapper
+            .put_explicit(ctx, "large".to_owned(), value, PutBehaviour::Overwrite)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::BlobTooLarge {
+                size: 11,
+                limit: 10,
+                ..
+            })
+        ));
+        assert!(base.get(ctx, "large").await.unwrap().is_none());
+
+        // Values that are already stored can still be read, whatever their size.
+        base.put(
+            ctx,
+            "legacy".to_owned(),
+            BlobstoreBytes::from_bytes("a value that is too large"),
+        )
+        .await
+        .unwrap();
+        assert!(wrapper.get(ctx, "legacy").await.unwrap().is_some());
+    }
+}
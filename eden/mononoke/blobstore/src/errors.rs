@@ -13,4 +13,35 @@ pub enum ErrorKind {
     NotFound(String),
     #[error("Error while opening state for blob store")]
     StateOpen,
+    #[error("Blob {key} is {size} bytes, which exceeds the limit of {limit} bytes")]
+    BlobTooLarge { key: String, size: u64, limit: u64 },
+}
+
+impl ErrorKind {
+    pub fn blob_too_large(key: impl Into<String>, size: u64, limit: u64) -> Self {
+        ErrorKind::BlobTooLarge {
+            key: key.into(),
+            size,
+            limit,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_blob_too_large() {
+        let err = ErrorKind::blob_too_large("repo0000.content.blake2.abc", 1234, 1000);
+        assert!(matches!(
+            &err,
+            ErrorKind::BlobTooLarge { key, size: 1234, limit: 1000 } if key == "repo0000.content.blake2.abc"
+        ));
+
+        let msg = err.to_string();
+        assert!(msg.contains("repo0000.content.blake2.abc"));
+        assert!(msg.contains("1234"));
+        assert!(msg.contains("1000"));
+    }
 }
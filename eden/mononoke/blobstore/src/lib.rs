@@ -10,6 +10,7 @@ mod disabled;
 mod errors;
 pub mod macros;
 
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt;
 use std::io::Cursor;
@@ -29,6 +30,9 @@ use auto_impl::auto_impl;
 use bytes::Bytes;
 use clap::ArgEnum;
 use context::CoreContext;
+use futures::stream;
+use futures::stream::StreamExt;
+use futures::stream::TryStreamExt;
 use serde_derive::Deserialize;
 use serde_derive::Serialize;
 use strum_macros::AsRefStr;
@@ -321,6 +325,19 @@ impl BlobstoreIsPresent {
     }
 }
 
+/// The result of probing whether a blobstore is reachable.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BlobstoreHealth {
+    /// The blobstore is serving requests normally
+    Healthy,
+    /// The blobstore is serving requests, but some of its backends are not
+    Degraded(String),
+    /// The blobstore cannot serve requests
+    Unhealthy(String),
+    /// The blobstore has no way of checking its health
+    Unknown,
+}
+
 /// The blobstore interface, shared across all blobstores.
 /// A blobstore must provide the following guarantees:
 /// 1. `get` and `put` are atomic with respect to each other; a put will either put the entire
@@ -396,6 +413,11 @@ pub trait Blobstore: fmt::Display + fmt::Debug + Send + Sync {
             .with_context(|| format!("key {} not present", old_key))?;
         Ok(self.put(ctx, new_key, value.bytes).await?)
     }
+    /// Check whether the blobstore is reachable, without writing any data. Blobstores that
+    /// wrap another blobstore should report the health of the inner store.
+    async fn health_check<'a>(&'a self, _ctx: &'a CoreContext) -> Result<BlobstoreHealth> {
+        Ok(BlobstoreHealth::Unknown)
+    }
 }
 
 /// Mononoke binaries will not overwrite existing blobstore keys by default
@@ -491,6 +513,54 @@ pub trait BlobstoreUnlinkOps: Blobstore + BlobstorePutOps {
     async fn unlink<'a>(&'a self, ctx: &'a CoreContext, key: &'a str) -> Result<()>;
 }
 
+/// Mixin trait for blobstores that may be able to report the size of a blob without fetching it
+#[async_trait]
+#[auto_impl(Arc, Box)]
+pub trait BlobstoreSizeHint: Blobstore {
+    /// The size in bytes of the blob at `key`, if it can be found without fetching the blob.
+    /// Returns `None` if the blob is absent, or if the size can't be found cheaply.
+    async fn blob_size<'a>(&'a self, _ctx: &'a CoreContext, _key: &'a str) -> Result<Option<u64>> {
+        Ok(None)
+    }
+}
+
+/// Default number of concurrent `get` calls issued by `BlobstoreBatchOps::get_many` for
+/// blobstores without a native batch API.
+pub const DEFAULT_GET_MANY_CONCURRENCY: usize = 100;
+
+/// Mixin trait for blobstores that can fetch many keys at once
+#[async_trait]
+#[auto_impl(Arc, Box)]
+pub trait BlobstoreBatchOps: Blobstore {
+    /// Fetch the values associated with all of `keys`. The returned map is keyed by the requested
+    /// keys, with `None` for keys that are not present. The provided implementation just issues
+    /// individual `get` calls; blobstores with a native batch API should override it.
+    async fn get_many<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        keys: &'a [&'a str],
+    ) -> Result<HashMap<String, Option<BlobstoreGetData>>> {
+        get_many_buffered(self, ctx, keys, DEFAULT_GET_MANY_CONCURRENCY).await
+    }
+}
+
+/// Fetch all of `keys` from `blobstore` using individual `get` calls, with at most `concurrency`
+/// of them in flight at once.
+pub async fn get_many_buffered<'a, B: Blobstore + ?Sized>(
+    blobstore: &'a B,
+    ctx: &'a CoreContext,
+    keys: &'a [&'a str],
+    concurrency: usize,
+) -> Result<HashMap<String, Option<BlobstoreGetData>>> {
+    stream::iter(keys.iter().map(|key| async move {
+        let value = blobstore.get(ctx, key).await?;
+        Ok::<_, Error>((key.to_string(), value))
+    }))
+    .buffer_unordered(concurrency)
+    .try_collect()
+    .await
+}
+
 /// BlobstoreKeySource Interface
 /// Abstract for use with populate_healer
 #[async_trait]
@@ -501,6 +571,49 @@ pub trait BlobstoreKeySource: Blobstore {
         ctx: &'a CoreContext,
         range: &'a BlobstoreKeyParam,
     ) -> Result<BlobstoreEnumerationData>;
+
+    /// Count the keys in `range`, following continuation tokens until the enumeration is
+    /// complete. Only one page of keys is held in memory at a time.
+    async fn count_keys<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        range: &'a BlobstoreKeyRange,
+    ) -> Result<u64> {
+        let mut param = BlobstoreKeyParam::Start(range.clone());
+        let mut count = 0;
+        loop {
+            let res = self.enumerate(ctx, &param).await?;
+            count += res.keys.len() as u64;
+            match res.next_token {
+                Some(next_token) => param = next_token,
+                None => return Ok(count),
+            }
+        }
+    }
+
+    /// Count the keys in `range` by their type, which is the part of the key up to the first
+    /// `.` (for example `changeset` or `content`). Keys without a `.` are counted under the whole
+    /// key. Stores that add a prefix to keys (such as the repo prefix) remove it during
+    /// enumeration, so the counts reflect logical key types.
+    async fn key_type_histogram<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        range: &'a BlobstoreKeyRange,
+    ) -> Result<HashMap<String, u64>> {
+        let mut param = BlobstoreKeyParam::Start(range.clone());
+        let mut histogram = HashMap::new();
+        loop {
+            let res = self.enumerate(ctx, &param).await?;
+            for key in res.keys {
+                let key_type = key.split_once('.').map_or(key.as_str(), |(t, _)| t);
+                *histogram.entry(key_type.to_owned()).or_insert(0) += 1;
+            }
+            match res.next_token {
+                Some(next_token) => param = next_token,
+                None => return Ok(histogram),
+            }
+        }
+    }
 }
 
 trait_set! {
@@ -529,6 +642,10 @@ pub enum BlobstoreKeyToken {
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 pub enum BlobstoreKeyParam {
     Start(BlobstoreKeyRange),
+    /// Like `Start`, but asks for the keys in descending order, so paging stores hand out the
+    /// last keys in the range first. Stores that can't do this return an error rather than
+    /// falling back to ascending order.
+    StartReversed(BlobstoreKeyRange),
     Continuation(BlobstoreKeyToken),
 }
 
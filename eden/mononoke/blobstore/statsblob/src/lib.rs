@@ -0,0 +1,234 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use blobstore::Blobstore;
+use blobstore::BlobstoreGetData;
+use blobstore::BlobstoreIsPresent;
+use blobstore::BlobstorePutOps;
+use blobstore::OverwriteStatus;
+use blobstore::PutBehaviour;
+use context::CoreContext;
+use futures_stats::TimedFutureExt;
+use mononoke_types::BlobstoreBytes;
+
+/// The operations a `StatsBlobstore` reports latencies for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StatsOperation {
+    Get,
+    Put,
+    IsPresent,
+}
+
+/// The counters a `StatsBlobstore` increments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StatsCounter {
+    Gets,
+    Puts,
+    IsPresent,
+    /// A get that found the blob.
+    Hits,
+    /// A get that succeeded, but didn't find the blob.
+    Misses,
+}
+
+/// Somewhere for a `StatsBlobstore` to report to, such as a metrics exporter.
+pub trait StatsSink: std::fmt::Debug + Send + Sync {
+    fn increment(&self, counter: StatsCounter);
+
+    fn record_latency(&self, operation: StatsOperation, latency: Duration);
+}
+
+/// A layer over an existing blobstore that counts the operations made on it, and reports how long
+/// each of them took, to a `StatsSink`. Latencies are recorded for failed operations too.
+#[derive(Debug)]
+pub struct StatsBlobstore<T> {
+    inner: T,
+    sink: Arc<dyn StatsSink>,
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for StatsBlobstore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "StatsBlobstore<{}>", &self.inner)
+    }
+}
+
+impl<T> StatsBlobstore<T> {
+    pub fn new(inner: T, sink: Arc<dyn StatsSink>) -> Self {
+        Self { inner, sink }
+    }
+}
+
+#[async_trait]
+impl<T: Blobstore> Blobstore for StatsBlobstore<T> {
+    async fn get<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<Option<BlobstoreGetData>> {
+        self.sink.increment(StatsCounter::Gets);
+        let (stats, result) = self.inner.get(ctx, key).timed().await;
+        self.sink
+            .record_latency(StatsOperation::Get, stats.completion_time);
+        match &result {
+            Ok(Some(_)) => self.sink.increment(StatsCounter::Hits),
+            Ok(None) => self.sink.increment(StatsCounter::Misses),
+            Err(_) => {}
+        }
+        result
+    }
+
+    async fn put<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<()> {
+        self.sink.increment(StatsCounter::Puts);
+        let (stats, result) = self.inner.put(ctx, key, value).timed().await;
+        self.sink
+            .record_latency(StatsOperation::Put, stats.completion_time);
+        result
+    }
+
+    async fn is_present<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<BlobstoreIsPresent> {
+        self.sink.increment(StatsCounter::IsPresent);
+        let (stats, result) = self.inner.is_present(ctx, key).timed().await;
+        self.sink
+            .record_latency(StatsOperation::IsPresent, stats.completion_time);
+        result
+    }
+}
+
+#[async_trait]
+impl<T: BlobstorePutOps> BlobstorePutOps for StatsBlobstore<T> {
+    async fn put_explicit<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+        put_behaviour: PutBehaviour,
+    ) -> Result<OverwriteStatus> {
+        self.sink.increment(StatsCounter::Puts);
+        let (stats, result) = self
+            .inner
+            .put_explicit(ctx, key, value, put_behaviour)
+            .timed()
+            .await;
+        self.sink
+            .record_latency(StatsOperation::Put, stats.completion_time);
+        result
+    }
+
+    async fn put_with_status<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<OverwriteStatus> {
+        self.sink.increment(StatsCounter::Puts);
+        let (stats, result) = self.inner.put_with_status(ctx, key, value).timed().await;
+        self.sink
+            .record_latency(StatsOperation::Put, stats.completion_time);
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use borrowed::borrowed;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct InMemorySink {
+        counters: Mutex<HashMap<StatsCounter, u64>>,
+        latencies: Mutex<Vec<(StatsOperation, Duration)>>,
+    }
+
+    impl InMemorySink {
+        fn count(&self, counter: StatsCounter) -> u64 {
+            self.counters
+                .lock()
+                .unwrap()
+                .get(&counter)
+                .copied()
+                .unwrap_or(0)
+        }
+
+        fn operations(&self) -> Vec<StatsOperation> {
+            self.latencies
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(op, _)| *op)
+                .collect()
+        }
+    }
+
+    impl StatsSink for InMemorySink {
+        fn increment(&self, counter: StatsCounter) {
+            *self.counters.lock().unwrap().entry(counter).or_default() += 1;
+        }
+
+        fn record_latency(&self, operation: StatsOperation, latency: Duration) {
+            self.latencies.lock().unwrap().push((operation, latency));
+        }
+    }
+
+    #[fbinit::test]
+    async fn test_stats(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let sink = Arc::new(InMemorySink::default());
+        let wrapper = StatsBlobstore::new(Memblob::default(), sink.clone());
+
+        wrapper
+            .put(ctx, "key".to_owned(), BlobstoreBytes::from_bytes("value"))
+            .await
+            .unwrap();
+        assert_eq!(sink.count(StatsCounter::Puts), 1);
+
+        assert!(wrapper.get(ctx, "key").await.unwrap().is_some());
+        assert!(wrapper.get(ctx, "missing").await.unwrap().is_none());
+        assert_eq!(sink.count(StatsCounter::Gets), 2);
+        assert_eq!(sink.count(StatsCounter::Hits), 1);
+        assert_eq!(sink.count(StatsCounter::Misses), 1);
+
+        wrapper
+            .put_with_status(ctx, "key".to_owned(), BlobstoreBytes::from_bytes("value"))
+            .await
+            .unwrap();
+        wrapper.is_present(ctx, "key").await.unwrap();
+        assert_eq!(sink.count(StatsCounter::Puts), 2);
+        assert_eq!(sink.count(StatsCounter::IsPresent), 1);
+
+        assert_eq!(
+            sink.operations(),
+            vec![
+                StatsOperation::Put,
+                StatsOperation::Get,
+                StatsOperation::Get,
+                StatsOperation::Put,
+                StatsOperation::IsPresent,
+            ]
+        );
+    }
+}
@@ -0,0 +1,28 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use anyhow::Error;
+use thiserror::Error;
+
+use crate::TeeSide;
+
+#[derive(Debug, Error)]
+pub enum ErrorKind {
+    #[error("Put of {key} to the {side} blobstore ({store}) failed: {error:#}")]
+    PutFailed {
+        key: String,
+        side: TeeSide,
+        store: String,
+        error: Error,
+    },
+    #[error("Put of {key} failed on both blobstores: {first:#}; {second:#}")]
+    PutFailedOnBoth {
+        key: String,
+        first: Error,
+        second: Error,
+    },
+}
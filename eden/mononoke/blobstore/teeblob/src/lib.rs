@@ -0,0 +1,244 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::fmt;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use blobstore::Blobstore;
+use blobstore::BlobstoreGetData;
+use blobstore::BlobstoreIsPresent;
+use blobstore::BlobstorePutOps;
+use blobstore::OverwriteStatus;
+use blobstore::PutBehaviour;
+use context::CoreContext;
+use futures::future::join;
+use mononoke_types::BlobstoreBytes;
+
+mod errors;
+pub use crate::errors::ErrorKind;
+
+/// One of the two blobstores in a `TeeBlobstore`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TeeSide {
+    First,
+    Second,
+}
+
+impl fmt::Display for TeeSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TeeSide::First => write!(f, "first"),
+            TeeSide::Second => write!(f, "second"),
+        }
+    }
+}
+
+/// A blobstore that writes to two blobstores at once, e.g. while migrating from one to the
+/// other. Puts go to both blobstores concurrently, and only succeed if both do. Reads are only
+/// served by the primary blobstore.
+#[derive(Clone, Debug)]
+pub struct TeeBlobstore<A, B> {
+    first: A,
+    second: B,
+    primary: TeeSide,
+}
+
+impl<A: fmt::Display, B: fmt::Display> fmt::Display for TeeBlobstore<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "TeeBlobstore<{}, {}, primary: {}>",
+            &self.first, &self.second, self.primary
+        )
+    }
+}
+
+impl<A: fmt::Display, B: fmt::Display> TeeBlobstore<A, B> {
+    pub fn new(first: A, second: B, primary: TeeSide) -> Self {
+        Self {
+            first,
+            second,
+            primary,
+        }
+    }
+
+    /// Combine the results of a put to both blobstores, returning the primary's result if both
+    /// succeeded.
+    fn combine<T>(&self, key: String, first: Result<T>, second: Result<T>) -> Result<T> {
+        match (first, second) {
+            (Ok(first), Ok(second)) => match self.primary {
+                TeeSide::First => Ok(first),
+                TeeSide::Second => Ok(second),
+            },
+            (Err(error), Ok(_)) => Err(ErrorKind::PutFailed {
+                key,
+                side: TeeSide::First,
+                store: self.first.to_string(),
+                error,
+            }
+            .into()),
+            (Ok(_), Err(error)) => Err(ErrorKind::PutFailed {
+                key,
+                side: TeeSide::Second,
+                store: self.second.to_string(),
+                error,
+            }
+            .into()),
+            (Err(first), Err(second)) => {
+                Err(ErrorKind::PutFailedOnBoth { key, first, second }.into())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<A: Blobstore, B: Blobstore> Blobstore for TeeBlobstore<A, B> {
+    async fn get<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<Option<BlobstoreGetData>> {
+        match self.primary {
+            TeeSide::First => self.first.get(ctx, key).await,
+            TeeSide::Second => self.second.get(ctx, key).await,
+        }
+    }
+
+    async fn put<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<()> {
+        let (first, second) = join(
+            self.first.put(ctx, key.clone(), value.clone()),
+            self.second.put(ctx, key.clone(), value),
+        )
+        .await;
+        self.combine(key, first, second)
+    }
+
+    async fn is_present<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<BlobstoreIsPresent> {
+        match self.primary {
+            TeeSide::First => self.first.is_present(ctx, key).await,
+            TeeSide::Second => self.second.is_present(ctx, key).await,
+        }
+    }
+}
+
+#[async_trait]
+impl<A: BlobstorePutOps, B: BlobstorePutOps> BlobstorePutOps for TeeBlobstore<A, B> {
+    async fn put_explicit<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+        put_behaviour: PutBehaviour,
+    ) -> Result<OverwriteStatus> {
+        let (first, second) = join(
+            self.first
+                .put_explicit(ctx, key.clone(), value.clone(), put_behaviour),
+            self.second
+                .put_explicit(ctx, key.clone(), value, put_behaviour),
+        )
+        .await;
+        self.combine(key, first, second)
+    }
+
+    async fn put_with_status<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<OverwriteStatus> {
+        let (first, second) = join(
+            self.first.put_with_status(ctx, key.clone(), value.clone()),
+            self.second.put_with_status(ctx, key.clone(), value),
+        )
+        .await;
+        self.combine(key, first, second)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use blobstore_test_utils::FailingBlobstore;
+    use blobstore_test_utils::FailurePolicy;
+    use blobstore_test_utils::InjectedFailure;
+    use borrowed::borrowed;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
+
+    use super::*;
+
+    #[fbinit::test]
+    async fn test_put_writes_both(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let first = Memblob::default();
+        let second = Memblob::default();
+        let tee = TeeBlobstore::new(first.clone(), second.clone(), TeeSide::Second);
+
+        let value = BlobstoreBytes::from_bytes("value");
+        tee.put(ctx, "key".to_owned(), value.clone()).await.unwrap();
+        for store in [&first, &second] {
+            let got = store.get(ctx, "key").await.unwrap().unwrap();
+            assert_eq!(got.into_bytes(), value);
+        }
+
+        // Reads only go to the primary.
+        first
+            .put(ctx, "first_only".to_owned(), value.clone())
+            .await
+            .unwrap();
+        assert!(tee.get(ctx, "first_only").await.unwrap().is_none());
+        assert!(
+            !tee.is_present(ctx, "first_only")
+                .await
+                .unwrap()
+                .assume_not_found_if_unsure()
+        );
+        assert!(tee.get(ctx, "key").await.unwrap().is_some());
+    }
+
+    #[fbinit::test]
+    async fn test_failures(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let first = FailingBlobstore::new(Memblob::default(), FailurePolicy::Always);
+        let second = Memblob::default();
+        let tee = TeeBlobstore::new(first, second.clone(), TeeSide::First);
+
+        // A failing primary fails reads.
+        let err = tee.get(ctx, "key").await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<InjectedFailure>(),
+            Some(InjectedFailure::Get(_))
+        ));
+
+        // A put that only partly succeeds names the blobstore that failed.
+        let value = BlobstoreBytes::from_bytes("value");
+        let err = tee
+            .put(ctx, "key".to_owned(), value.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::PutFailed {
+                side: TeeSide::First,
+                ..
+            })
+        ));
+        assert!(err.to_string().contains("FailingBlobstore"));
+        assert!(second.get(ctx, "key").await.unwrap().is_some());
+    }
+}
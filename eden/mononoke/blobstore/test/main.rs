@@ -10,10 +10,14 @@
 #![cfg_attr(not(fbcode_build), allow(unused_crate_dependencies))]
 #![feature(never_type)]
 
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use anyhow::Error;
 use blobstore::Blobstore;
+use blobstore::BlobstoreKeyParam;
+use blobstore::BlobstoreKeyRange;
+use blobstore::BlobstoreKeySource;
 use blobstore::BlobstorePutOps;
 use blobstore::BlobstoreUnlinkOps;
 use blobstore::OverwriteStatus;
@@ -375,3 +379,110 @@ async fn cache_blob_tests(fb: FacebookInit, expect_zstd: bool) -> Result<(), Err
 
     Ok(())
 }
+
+/// Enumerate `param` one page at a time, returning the keys of each page in order.
+async fn enumerate_pages(
+    ctx: &CoreContext,
+    blobstore: &impl BlobstoreKeySource,
+    mut param: BlobstoreKeyParam,
+) -> Result<Vec<Vec<String>>, Error> {
+    let mut pages = Vec::new();
+    loop {
+        let res = blobstore.enumerate(ctx, &param).await?;
+        let mut page: Vec<_> = res.keys.into_iter().collect();
+        page.sort();
+        pages.push(page);
+        match res.next_token {
+            Some(next) => param = next,
+            None => return Ok(pages),
+        }
+    }
+}
+
+#[fbinit::test]
+async fn test_memblob_enumerate_pages(fb: FacebookInit) -> Result<(), Error> {
+    let ctx = CoreContext::test_mock(fb);
+    borrowed!(ctx);
+    let blobstore = Memblob::default().with_page_size(2);
+    for key in ["a", "b", "c", "d", "e", "f"] {
+        blobstore
+            .put(ctx, key.to_owned(), BlobstoreBytes::from_bytes(key))
+            .await?;
+    }
+    let range = |begin: &str, end: &str| BlobstoreKeyRange {
+        begin_key: begin.to_owned(),
+        end_key: end.to_owned(),
+    };
+    fn pages(pages: &[&[&str]]) -> Vec<Vec<String>> {
+        pages
+            .iter()
+            .map(|page| page.iter().map(|k| k.to_string()).collect())
+            .collect()
+    }
+
+    // Forward pages start from the beginning of the range.
+    assert_eq!(
+        enumerate_pages(ctx, &blobstore, BlobstoreKeyParam::Start(range("b", ""))).await?,
+        pages(&[&["b", "c"], &["d", "e"], &["f"]])
+    );
+
+    // Reversed pages start from the end, and continuations keep going in reverse.
+    assert_eq!(
+        enumerate_pages(
+            ctx,
+            &blobstore,
+            BlobstoreKeyParam::StartReversed(range("", "e"))
+        )
+        .await?,
+        pages(&[&["d", "e"], &["b", "c"], &["a"]])
+    );
+    assert_eq!(
+        enumerate_pages(
+            ctx,
+            &blobstore,
+            BlobstoreKeyParam::StartReversed(range("b", "e"))
+        )
+        .await?,
+        pages(&[&["d", "e"], &["b", "c"]])
+    );
+
+    // Continuations work for keys containing the characters used in tokens.
+    let odd_keys = Memblob::default().with_page_size(1);
+    for key in ["a\0b", "b:1:c", "c\0\0", "d"] {
+        odd_keys
+            .put(ctx, key.to_owned(), BlobstoreBytes::from_bytes(key))
+            .await?;
+    }
+    assert_eq!(
+        enumerate_pages(ctx, &odd_keys, BlobstoreKeyParam::Start(range("", ""))).await?,
+        pages(&[&["a\0b"], &["b:1:c"], &["c\0\0"], &["d"]])
+    );
+    assert_eq!(
+        enumerate_pages(
+            ctx,
+            &odd_keys,
+            BlobstoreKeyParam::StartReversed(range("", "c\0\0"))
+        )
+        .await?,
+        pages(&[&["c\0\0"], &["b:1:c"], &["a\0b"]])
+    );
+
+    // Without a page size, everything comes back at once in either direction.
+    let unpaged = Memblob::default();
+    for key in ["a", "b", "c"] {
+        unpaged
+            .put(ctx, key.to_owned(), BlobstoreBytes::from_bytes(key))
+            .await?;
+    }
+    let all: HashSet<_> = ["a", "b", "c"].iter().map(|k| k.to_string()).collect();
+    for param in [
+        BlobstoreKeyParam::Start(range("", "")),
+        BlobstoreKeyParam::StartReversed(range("", "")),
+    ] {
+        let res = unpaged.enumerate(ctx, &param).await?;
+        assert_eq!(res.keys, all);
+        assert!(res.next_token.is_none());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,233 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::fmt;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use blobstore::Blobstore;
+use blobstore::BlobstoreGetData;
+use blobstore::BlobstoreIsPresent;
+use blobstore::BlobstorePutOps;
+use blobstore::OverwriteStatus;
+use blobstore::PutBehaviour;
+use context::CoreContext;
+use mononoke_types::BlobstoreBytes;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum InjectedFailure {
+    #[error("Injected failure in get to FailingBlobstore for key {0}")]
+    Get(String),
+    #[error("Injected failure in put to FailingBlobstore for key {0}")]
+    Put(String),
+    #[error("Injected failure in is_present to FailingBlobstore for key {0}")]
+    IsPresent(String),
+}
+
+/// When a `FailingBlobstore` should fail an operation.
+#[derive(Clone)]
+pub enum FailurePolicy {
+    /// Fail every operation.
+    Always,
+    /// Fail operations on keys for which the predicate returns true.
+    MatchingKeys(Arc<dyn Fn(&str) -> bool + Send + Sync>),
+    /// Fail the first N operations, then let everything through.
+    FirstN(u64),
+}
+
+impl fmt::Debug for FailurePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Always => write!(f, "Always"),
+            Self::MatchingKeys(_) => write!(f, "MatchingKeys(..)"),
+            Self::FirstN(n) => write!(f, "FirstN({})", n),
+        }
+    }
+}
+
+/// A layer over an existing blobstore that fails operations according to a
+/// `FailurePolicy`, for testing how other layers handle errors.
+#[derive(Debug)]
+pub struct FailingBlobstore<T> {
+    blobstore: T,
+    policy: FailurePolicy,
+    calls: AtomicU64,
+}
+
+impl<T: fmt::Display> fmt::Display for FailingBlobstore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FailingBlobstore<{}>", &self.blobstore)
+    }
+}
+
+impl<T> FailingBlobstore<T> {
+    pub fn new(blobstore: T, policy: FailurePolicy) -> Self {
+        Self {
+            blobstore,
+            policy,
+            calls: AtomicU64::new(0),
+        }
+    }
+
+    /// The number of operations this blobstore has seen, failed or not.
+    pub fn calls(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    fn check(&self, key: &str, failure: impl FnOnce(String) -> InjectedFailure) -> Result<()> {
+        let call = self.calls.fetch_add(1, Ordering::Relaxed);
+        let fail = match &self.policy {
+            FailurePolicy::Always => true,
+            FailurePolicy::MatchingKeys(predicate) => predicate(key),
+            FailurePolicy::FirstN(n) => call < *n,
+        };
+        if fail {
+            Err(failure(key.to_owned()).into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[async_trait]
+impl<T: Blobstore> Blobstore for FailingBlobstore<T> {
+    async fn get<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<Option<BlobstoreGetData>> {
+        self.check(key, InjectedFailure::Get)?;
+        self.blobstore.get(ctx, key).await
+    }
+
+    async fn put<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<()> {
+        self.check(&key, InjectedFailure::Put)?;
+        self.blobstore.put(ctx, key, value).await
+    }
+
+    async fn is_present<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<BlobstoreIsPresent> {
+        self.check(key, InjectedFailure::IsPresent)?;
+        self.blobstore.is_present(ctx, key).await
+    }
+}
+
+#[async_trait]
+impl<T: BlobstorePutOps> BlobstorePutOps for FailingBlobstore<T> {
+    async fn put_explicit<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+        put_behaviour: PutBehaviour,
+    ) -> Result<OverwriteStatus> {
+        self.check(&key, InjectedFailure::Put)?;
+        self.blobstore
+            .put_explicit(ctx, key, value, put_behaviour)
+            .await
+    }
+
+    async fn put_with_status<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<OverwriteStatus> {
+        self.check(&key, InjectedFailure::Put)?;
+        self.blobstore.put_with_status(ctx, key, value).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use borrowed::borrowed;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
+
+    use super::*;
+
+    #[fbinit::test]
+    async fn test_fail_first_n_then_succeed(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let blobstore = FailingBlobstore::new(base.clone(), FailurePolicy::FirstN(2));
+        let value = BlobstoreBytes::from_bytes("value");
+
+        // A simple retry loop, as a retrying wrapper would do.
+        let mut failures = 0;
+        loop {
+            match blobstore.put(ctx, "key".to_owned(), value.clone()).await {
+                Ok(()) => break,
+                Err(err) => {
+                    assert!(matches!(
+                        err.downcast_ref::<InjectedFailure>(),
+                        Some(InjectedFailure::Put(key)) if key == "key"
+                    ));
+                    failures += 1;
+                    assert!(failures <= 2, "put should have succeeded by now");
+                }
+            }
+        }
+        assert_eq!(failures, 2);
+        assert_eq!(blobstore.calls(), 3);
+
+        let got = base.get(ctx, "key").await.unwrap().unwrap();
+        assert_eq!(got.into_bytes(), value);
+        assert!(blobstore.get(ctx, "key").await.unwrap().is_some());
+    }
+
+    #[fbinit::test]
+    async fn test_fail_always_and_matching(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        base.put(ctx, "good".to_owned(), BlobstoreBytes::from_bytes("v"))
+            .await
+            .unwrap();
+        base.put(ctx, "bad".to_owned(), BlobstoreBytes::from_bytes("v"))
+            .await
+            .unwrap();
+
+        let always = FailingBlobstore::new(base.clone(), FailurePolicy::Always);
+        let err = always.get(ctx, "good").await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<InjectedFailure>(),
+            Some(InjectedFailure::Get(_))
+        ));
+        let err = always.is_present(ctx, "good").await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<InjectedFailure>(),
+            Some(InjectedFailure::IsPresent(_))
+        ));
+
+        let matching = FailingBlobstore::new(
+            base,
+            FailurePolicy::MatchingKeys(Arc::new(|key| key.starts_with("bad"))),
+        );
+        assert!(matching.get(ctx, "good").await.unwrap().is_some());
+        assert!(matching.get(ctx, "bad").await.is_err());
+        assert!(
+            matching
+                .put(ctx, "bad2".to_owned(), BlobstoreBytes::from_bytes("v"))
+                .await
+                .is_err()
+        );
+    }
+}
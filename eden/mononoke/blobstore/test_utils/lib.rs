@@ -33,6 +33,15 @@ use lock_ext::LockExt;
 use metaconfig_types::MultiplexId;
 use mononoke_types::BlobstoreBytes;
 use mononoke_types::Timestamp;
+
+mod failing;
+pub use crate::failing::FailingBlobstore;
+pub use crate::failing::FailurePolicy;
+pub use crate::failing::InjectedFailure;
+
+mod recording;
+pub use crate::recording::RecordingBlobstore;
+
 pub struct Tickable<T> {
     pub storage: Arc<Mutex<HashMap<String, T>>>,
     // queue of pending operations
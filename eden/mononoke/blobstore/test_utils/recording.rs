@@ -0,0 +1,148 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use blobstore::Blobstore;
+use blobstore::BlobstoreGetData;
+use blobstore::BlobstorePutOps;
+use blobstore::OverwriteStatus;
+use blobstore::PutBehaviour;
+use context::CoreContext;
+use lock_ext::LockExt;
+use mononoke_types::BlobstoreBytes;
+
+#[derive(Debug, Default)]
+struct Recording {
+    values: HashMap<String, BlobstoreBytes>,
+    puts: Vec<(String, BlobstoreBytes)>,
+}
+
+/// An in-memory blobstore that records every put it is asked to do, in call order, for golden
+/// tests of code that writes to a blobstore. Reads are served from what has been put.
+#[derive(Debug, Default)]
+pub struct RecordingBlobstore {
+    recording: Mutex<Recording>,
+}
+
+impl fmt::Display for RecordingBlobstore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RecordingBlobstore")
+    }
+}
+
+impl RecordingBlobstore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every put so far, in the order they were made. Puts that didn't overwrite an existing
+    /// value (because of `PutBehaviour::IfAbsent`) are still recorded.
+    pub fn recorded_puts(&self) -> Vec<(String, BlobstoreBytes)> {
+        self.recording.with(|r| r.puts.clone())
+    }
+}
+
+#[async_trait]
+impl Blobstore for RecordingBlobstore {
+    async fn get<'a>(
+        &'a self,
+        _ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<Option<BlobstoreGetData>> {
+        Ok(self
+            .recording
+            .with(|r| r.values.get(key).cloned())
+            .map(BlobstoreGetData::from))
+    }
+
+    async fn put<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<()> {
+        self.put_with_status(ctx, key, value).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BlobstorePutOps for RecordingBlobstore {
+    async fn put_explicit<'a>(
+        &'a self,
+        _ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+        put_behaviour: PutBehaviour,
+    ) -> Result<OverwriteStatus> {
+        Ok(self.recording.with(|r| {
+            r.puts.push((key.clone(), value.clone()));
+            if put_behaviour == PutBehaviour::IfAbsent && r.values.contains_key(&key) {
+                return OverwriteStatus::Prevented;
+            }
+            r.values.insert(key, value);
+            OverwriteStatus::NotChecked
+        }))
+    }
+
+    async fn put_with_status<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<OverwriteStatus> {
+        self.put_explicit(ctx, key, value, PutBehaviour::Overwrite)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use borrowed::borrowed;
+    use fbinit::FacebookInit;
+
+    use super::*;
+
+    #[fbinit::test]
+    async fn test_records_puts_in_order(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let blobstore = RecordingBlobstore::new();
+
+        let puts = vec![
+            ("zebra".to_owned(), BlobstoreBytes::from_bytes("first")),
+            (
+                "apple".to_owned(),
+                BlobstoreBytes::from_bytes(&b"\x00\xffsecond"[..]),
+            ),
+            ("mango".to_owned(), BlobstoreBytes::from_bytes("third")),
+        ];
+        for (key, value) in puts.clone() {
+            blobstore.put(ctx, key, value).await.unwrap();
+        }
+        assert_eq!(blobstore.recorded_puts(), puts);
+
+        let got = blobstore.get(ctx, "apple").await.unwrap().unwrap();
+        assert_eq!(got.into_bytes(), puts[1].1);
+        assert!(blobstore.get(ctx, "missing").await.unwrap().is_none());
+
+        // Overwrites are recorded too, and change what is read back.
+        let value = BlobstoreBytes::from_bytes("again");
+        blobstore
+            .put(ctx, "zebra".to_owned(), value.clone())
+            .await
+            .unwrap();
+        assert_eq!(blobstore.recorded_puts().len(), 4);
+        let got = blobstore.get(ctx, "zebra").await.unwrap().unwrap();
+        assert_eq!(got.into_bytes(), value);
+    }
+}
@@ -0,0 +1,27 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::time::Duration;
+
+use thiserror::Error;
+
+/// The blobstore operations that a `TimeoutBlobstore` can time out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlobstoreOp {
+    Get,
+    Put,
+    IsPresent,
+}
+
+/// A blobstore operation that didn't complete within its timeout.
+#[derive(Debug, Error)]
+#[error("Blobstore {op:?} of {key} timed out after {elapsed:?}")]
+pub struct BlobstoreTimeout {
+    pub key: String,
+    pub op: BlobstoreOp,
+    pub elapsed: Duration,
+}
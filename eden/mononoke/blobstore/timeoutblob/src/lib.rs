@@ -0,0 +1,197 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::future::Future;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use blobstore::Blobstore;
+use blobstore::BlobstoreGetData;
+use blobstore::BlobstoreIsPresent;
+use blobstore::BlobstorePutOps;
+use blobstore::OverwriteStatus;
+use blobstore::PutBehaviour;
+use context::CoreContext;
+use mononoke_types::BlobstoreBytes;
+
+mod errors;
+pub use crate::errors::BlobstoreOp;
+pub use crate::errors::BlobstoreTimeout;
+
+/// How long each kind of operation may take before a `TimeoutBlobstore` gives up on it.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeoutOptions {
+    pub get: Duration,
+    pub put: Duration,
+    pub is_present: Duration,
+}
+
+/// A layer over an existing blobstore that fails operations which take longer than their timeout
+/// with a `BlobstoreTimeout` error, so that a slow backend can't hang its callers. The operation
+/// on the inner blobstore is dropped when it times out.
+#[derive(Clone, Debug)]
+pub struct TimeoutBlobstore<T> {
+    blobstore: T,
+    options: TimeoutOptions,
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for TimeoutBlobstore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TimeoutBlobstore<{}>", &self.blobstore)
+    }
+}
+
+impl<T> TimeoutBlobstore<T> {
+    pub fn new(blobstore: T, options: TimeoutOptions) -> Self {
+        Self { blobstore, options }
+    }
+}
+
+async fn with_timeout<R>(
+    key: &str,
+    op: BlobstoreOp,
+    timeout: Duration,
+    fut: impl Future<Output = Result<R>>,
+) -> Result<R> {
+    let start = Instant::now();
+    match tokio::time::timeout(timeout, fut).await {
+        Ok(res) => res,
+        Err(_) => Err(BlobstoreTimeout {
+            key: key.to_owned(),
+            op,
+            elapsed: start.elapsed(),
+        }
+        .into()),
+    }
+}
+
+#[async_trait]
+impl<T: Blobstore> Blobstore for TimeoutBlobstore<T> {
+    async fn get<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<Option<BlobstoreGetData>> {
+        let get = self.blobstore.get(ctx, key);
+        with_timeout(key, BlobstoreOp::Get, self.options.get, get).await
+    }
+
+    async fn put<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<()> {
+        let put = self.blobstore.put(ctx, key.clone(), value);
+        with_timeout(&key, BlobstoreOp::Put, self.options.put, put).await
+    }
+
+    async fn is_present<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<BlobstoreIsPresent> {
+        let is_present = self.blobstore.is_present(ctx, key);
+        with_timeout(
+            key,
+            BlobstoreOp::IsPresent,
+            self.options.is_present,
+            is_present,
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl<T: BlobstorePutOps> BlobstorePutOps for TimeoutBlobstore<T> {
+    async fn put_explicit<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+        put_behaviour: PutBehaviour,
+    ) -> Result<OverwriteStatus> {
+        let put = self
+            .blobstore
+            .put_explicit(ctx, key.clone(), value, put_behaviour);
+        with_timeout(&key, BlobstoreOp::Put, self.options.put, put).await
+    }
+
+    async fn put_with_status<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<OverwriteStatus> {
+        let put = self.blobstore.put_with_status(ctx, key.clone(), value);
+        with_timeout(&key, BlobstoreOp::Put, self.options.put, put).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use borrowed::borrowed;
+    use delayblob::fixed_delay;
+    use delayblob::DelayOptions;
+    use delayblob::DelayedBlobstore;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
+
+    use super::*;
+
+    #[fbinit::test]
+    async fn test_timeouts(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        // Reads are slow, and writes are fast.
+        let delayed = DelayedBlobstore::from_options(
+            Memblob::default(),
+            DelayOptions {
+                get_dist: Some(fixed_delay(Duration::from_millis(100))),
+                put_dist: None,
+            },
+        );
+        let blobstore = TimeoutBlobstore::new(
+            delayed,
+            TimeoutOptions {
+                get: Duration::from_millis(10),
+                put: Duration::from_secs(10),
+                is_present: Duration::from_secs(10),
+            },
+        );
+
+        blobstore
+            .put(ctx, "key".to_owned(), BlobstoreBytes::from_bytes("value"))
+            .await
+            .unwrap();
+        blobstore
+            .put_with_status(ctx, "key2".to_owned(), BlobstoreBytes::from_bytes("value"))
+            .await
+            .unwrap();
+
+        let err = blobstore.get(ctx, "key").await.unwrap_err();
+        match err.downcast_ref::<BlobstoreTimeout>() {
+            Some(BlobstoreTimeout { key, op, elapsed }) => {
+                assert_eq!(key, "key");
+                assert_eq!(*op, BlobstoreOp::Get);
+                assert!(*elapsed >= Duration::from_millis(10));
+            }
+            None => panic!("Unexpected error: {:?}", err),
+        }
+
+        // is_present is just as slow as get, but has long enough to finish.
+        assert!(
+            blobstore
+                .is_present(ctx, "key")
+                .await
+                .unwrap()
+                .assume_not_found_if_unsure()
+        );
+    }
+}
@@ -0,0 +1,219 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use anyhow::Result;
+use async_trait::async_trait;
+use blobstore::Blobstore;
+use blobstore::BlobstoreGetData;
+use blobstore::BlobstoreIsPresent;
+use blobstore::BlobstorePutOps;
+use blobstore::OverwriteStatus;
+use blobstore::PutBehaviour;
+use context::CoreContext;
+use mononoke_types::BlobstoreBytes;
+use regex::Regex;
+
+mod errors;
+pub use crate::errors::ErrorKind;
+
+/// The keys Mononoke writes: a non-empty run of printable ASCII characters, with no whitespace
+/// or control characters.
+pub const DEFAULT_KEY_PATTERN: &str = r"^[!-~]+$";
+
+/// A layer over an existing blobstore that rejects writes to keys that don't match a pattern,
+/// so that malformed keys never make it into storage. Reads can be checked too, to find callers
+/// that construct malformed keys.
+#[derive(Clone, Debug)]
+pub struct ValidatingKeyBlobstore<T> {
+    blobstore: T,
+    pattern: Regex,
+    validate_reads: bool,
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for ValidatingKeyBlobstore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ValidatingKeyBlobstore<{}>", &self.blobstore)
+    }
+}
+
+impl<T> ValidatingKeyBlobstore<T> {
+    /// Validate keys against `DEFAULT_KEY_PATTERN`.
+    pub fn new(blobstore: T, validate_reads: bool) -> Self {
+        let pattern = Regex::new(DEFAULT_KEY_PATTERN).expect("default key pattern is valid");
+        Self {
+            blobstore,
+            pattern,
+            validate_reads,
+        }
+    }
+
+    /// Validate keys against `pattern`, which should be anchored to match the whole key.
+    pub fn with_pattern(blobstore: T, pattern: &str, validate_reads: bool) -> Result<Self> {
+        Ok(Self {
+            blobstore,
+            pattern: Regex::new(pattern)?,
+            validate_reads,
+        })
+    }
+
+    fn validate(&self, key: &str) -> Result<()> {
+        if self.pattern.is_match(key) {
+            Ok(())
+        } else {
+            Err(ErrorKind::InvalidKey {
+                key: key.to_owned(),
+                pattern: self.pattern.as_str().to_owned(),
+            }
+            .into())
+        }
+    }
+
+    fn validate_read(&self, key: &str) -> Result<()> {
+        if self.validate_reads {
+            self.validate(key)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[async_trait]
+impl<T: Blobstore> Blobstore for ValidatingKeyBlobstore<T> {
+    async fn get<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<Option<BlobstoreGetData>> {
+        self.validate_read(key)?;
+        self.blobstore.get(ctx, key).await
+    }
+
+    async fn put<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<()> {
+        self.validate(&key)?;
+        self.blobstore.put(ctx, key, value).await
+    }
+
+    async fn is_present<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<BlobstoreIsPresent> {
+        self.validate_read(key)?;
+        self.blobstore.is_present(ctx, key).await
+    }
+}
+
+#[async_trait]
+impl<T: BlobstorePutOps> BlobstorePutOps for ValidatingKeyBlobstore<T> {
+    async fn put_explicit<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+        put_behaviour: PutBehaviour,
+    ) -> Result<OverwriteStatus> {
+        self.validate(&key)?;
+        self.blobstore
+            .put_explicit(ctx, key, value, put_behaviour)
+            .await
+    }
+
+    async fn put_with_status<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<OverwriteStatus> {
+        self.validate(&key)?;
+        self.blobstore.put_with_status(ctx, key, value).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use borrowed::borrowed;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
+
+    use super::*;
+
+    #[fbinit::test]
+    async fn test_put_valid_key(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let wrapper = ValidatingKeyBlobstore::new(base.clone(), false);
+
+        let key = "repo0000.content.blake2.abcdef".to_owned();
+        let value = BlobstoreBytes::from_bytes("value");
+        wrapper.put(ctx, key.clone(), value.clone()).await.unwrap();
+        let got = base.get(ctx, &key).await.unwrap();
+        assert_eq!(got.map(|d| d.into_bytes()), Some(value));
+    }
+
+    #[fbinit::test]
+    async fn test_put_invalid_key(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let wrapper = ValidatingKeyBlobstore::new(base.clone(), false);
+
+        let key = "repo0000.content\n.blake2.abcdef";
+        let value = BlobstoreBytes::from_bytes("value");
+        assert!(
+            wrapper
+                .put(ctx, key.to_owned(), value.clone())
+                .await
+                .is_err()
+        );
+        assert!(
+            wrapper
+                .put_with_status(ctx, key.to_owned(), value)
+                .await
+                .is_err()
+        );
+        assert!(base.get(ctx, key).await.unwrap().is_none());
+
+        // Reads aren't validated unless asked for.
+        assert!(wrapper.get(ctx, key).await.unwrap().is_none());
+        let strict = ValidatingKeyBlobstore::new(base, true);
+        assert!(strict.get(ctx, key).await.is_err());
+        assert!(strict.is_present(ctx, key).await.is_err());
+    }
+
+    #[fbinit::test]
+    async fn test_error_names_key(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let wrapper =
+            ValidatingKeyBlobstore::with_pattern(Memblob::default(), r"^repo[0-9]{4}\.", false)
+                .unwrap();
+
+        let value = BlobstoreBytes::from_bytes("value");
+        wrapper
+            .put(ctx, "repo0000.key".to_owned(), value.clone())
+            .await
+            .unwrap();
+        let err = wrapper
+            .put(ctx, "unprefixed.key".to_owned(), value)
+            .await
+            .unwrap_err();
+        match err.downcast_ref::<ErrorKind>() {
+            Some(ErrorKind::InvalidKey { key, pattern }) => {
+                assert_eq!(key, "unprefixed.key");
+                assert_eq!(pattern, r"^repo[0-9]{4}\.");
+            }
+            other => panic!("Unexpected error: {:?}", other),
+        }
+        assert!(err.to_string().contains("unprefixed.key"));
+    }
+}
@@ -0,0 +1,22 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use anyhow::Error;
+use mononoke_types::ContentId;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ErrorKind {
+    #[error("Corrupt blob {key}: contents hash to {actual}, expected {expected}")]
+    ContentHashMismatch {
+        key: String,
+        expected: ContentId,
+        actual: ContentId,
+    },
+    #[error("Corrupt blob {0}: contents could not be decoded")]
+    Undecodable(String, #[source] Error),
+}
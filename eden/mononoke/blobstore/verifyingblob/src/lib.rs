@@ -0,0 +1,289 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::str::FromStr;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use blobstore::Blobstore;
+use blobstore::BlobstoreGetData;
+use blobstore::BlobstoreIsPresent;
+use blobstore::BlobstorePutOps;
+use blobstore::OverwriteStatus;
+use blobstore::PutBehaviour;
+use context::CoreContext;
+use mononoke_types::BlobstoreBytes;
+use mononoke_types::ContentId;
+use mononoke_types::FileContents;
+use rand::thread_rng;
+use rand::Rng;
+
+mod errors;
+pub use crate::errors::ErrorKind;
+
+const CONTENT_KEY_PREFIX: &str = "content.blake2.";
+
+/// Which reads a `VerifyingBlobstore` checks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VerifyMode {
+    /// Verify every read of a content blob.
+    Always,
+    /// Verify each read of a content blob with the given probability, between 0.0 and 1.0.
+    Sampled(f64),
+}
+
+/// A layer over an existing blobstore that checks content blobs it reads against the content id
+/// in their key, and fails the read if they don't match. Other keys, and content that is split
+/// into chunks (whose id can't be computed without fetching the chunks), are passed through
+/// unverified.
+#[derive(Clone, Debug)]
+pub struct VerifyingBlobstore<T> {
+    blobstore: T,
+    mode: VerifyMode,
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for VerifyingBlobstore<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "VerifyingBlobstore<{}>", &self.blobstore)
+    }
+}
+
+impl<T> VerifyingBlobstore<T> {
+    pub fn new(blobstore: T, mode: VerifyMode) -> Self {
+        Self { blobstore, mode }
+    }
+
+    fn should_verify(&self) -> bool {
+        match self.mode {
+            VerifyMode::Always => true,
+            VerifyMode::Sampled(rate) => thread_rng().gen::<f64>() < rate,
+        }
+    }
+}
+
+/// Returns the content id encoded in `key`, if it is a content key. The key may have a prefix
+/// (such as the repo prefix) ending in a `.`.
+fn content_id_from_key(key: &str) -> Option<ContentId> {
+    let (prefix, hash) = key.rsplit_once(CONTENT_KEY_PREFIX)?;
+    if !prefix.is_empty() && !prefix.ends_with('.') {
+        return None;
+    }
+    ContentId::from_str(hash).ok()
+}
+
+fn verify(key: &str, expected: ContentId, data: &BlobstoreGetData) -> Result<()> {
+    let contents = FileContents::from_encoded_bytes(data.as_raw_bytes().clone())
+        .map_err(|e| ErrorKind::Undecodable(key.to_owned(), e))?;
+    if let FileContents::Bytes(bytes) = contents {
+        let actual = FileContents::content_id_for_bytes(&bytes);
+        if actual != expected {
+            return Err(ErrorKind::ContentHashMismatch {
+                key: key.to_owned(),
+                expected,
+                actual,
+            }
+            .into());
+        }
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl<T: Blobstore> Blobstore for VerifyingBlobstore<T> {
+    async fn get<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<Option<BlobstoreGetData>> {
+        let data = self.blobstore.get(ctx, key).await?;
+        if let Some(data) = &data {
+            if let Some(expected) = content_id_from_key(key) {
+                if self.should_verify() {
+                    verify(key, expected, data)?;
+                }
+            }
+        }
+        Ok(data)
+    }
+
+    #[inline]
+    async fn put<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<()> {
+        self.blobstore.put(ctx, key, value).await
+    }
+
+    #[inline]
+    async fn is_present<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<BlobstoreIsPresent> {
+        self.blobstore.is_present(ctx, key).await
+    }
+}
+
+#[async_trait]
+impl<T: BlobstorePutOps> BlobstorePutOps for VerifyingBlobstore<T> {
+    async fn put_explicit<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+        put_behaviour: PutBehaviour,
+    ) -> Result<OverwriteStatus> {
+        self.blobstore
+            .put_explicit(ctx, key, value, put_behaviour)
+            .await
+    }
+
+    async fn put_with_status<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<OverwriteStatus> {
+        self.blobstore.put_with_status(ctx, key, value).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use borrowed::borrowed;
+    use bytes::Bytes;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
+    use mononoke_types::BlobstoreKey;
+    use mononoke_types::BlobstoreValue;
+
+    use super::*;
+
+    /// Store `contents` in `blobstore` under its content key, returning the key.
+    async fn put_contents(
+        ctx: &CoreContext,
+        blobstore: &impl Blobstore,
+        prefix: &str,
+        contents: &'static str,
+    ) -> String {
+        let blob = FileContents::new_bytes(contents).into_blob();
+        let key = format!("{}{}", prefix, blob.id().blobstore_key());
+        blobstore.put(ctx, key.clone(), blob.into()).await.unwrap();
+        key
+    }
+
+    /// Overwrite the value at `key` with the encoding of different contents.
+    async fn corrupt(ctx: &CoreContext, blobstore: &impl Blobstore, key: &str) {
+        let bad = FileContents::new_bytes("corrupted").into_blob();
+        blobstore
+            .put(ctx, key.to_owned(), bad.into())
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_content_id_from_key() {
+        let id = FileContents::new_bytes("foo").content_id();
+        let key = id.blobstore_key();
+        assert_eq!(content_id_from_key(&key), Some(id));
+        assert_eq!(content_id_from_key(&format!("repo0042.{}", key)), Some(id));
+        assert_eq!(content_id_from_key(&format!("repo0042{}", key)), None);
+        assert_eq!(content_id_from_key("content.blake2.xyz"), None);
+        assert_eq!(content_id_from_key("alias.sha1.abcd"), None);
+    }
+
+    #[fbinit::test]
+    async fn test_detects_corruption(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let wrapper = VerifyingBlobstore::new(base.clone(), VerifyMode::Always);
+
+        for prefix in ["", "repo0000."] {
+            let key = put_contents(ctx, &base, prefix, "hello world").await;
+            let good = wrapper.get(ctx, &key).await.unwrap().unwrap();
+            assert_eq!(good, base.get(ctx, &key).await.unwrap().unwrap());
+
+            corrupt(ctx, &base, &key).await;
+            let err = wrapper.get(ctx, &key).await.unwrap_err();
+            match err.downcast_ref::<ErrorKind>() {
+                Some(ErrorKind::ContentHashMismatch {
+                    key: bad_key,
+                    expected,
+                    actual,
+                }) => {
+                    assert_eq!(bad_key, &key);
+                    assert_eq!(
+                        *expected,
+                        FileContents::new_bytes("hello world").content_id()
+                    );
+                    assert_eq!(*actual, FileContents::new_bytes("corrupted").content_id());
+                }
+                other => panic!("Unexpected error: {:?}", other),
+            }
+        }
+    }
+
+    #[fbinit::test]
+    async fn test_detects_undecodable(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let wrapper = VerifyingBlobstore::new(base.clone(), VerifyMode::Always);
+
+        let key = put_contents(ctx, &base, "", "hello world").await;
+        base.put(
+            ctx,
+            key.clone(),
+            BlobstoreBytes::from_bytes(Bytes::from_static(b"\xff\xff\xff")),
+        )
+        .await
+        .unwrap();
+        let err = wrapper.get(ctx, &key).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::Undecodable(..))
+        ));
+    }
+
+    #[fbinit::test]
+    async fn test_passes_through(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx);
+        let base = Memblob::default();
+        let wrapper = VerifyingBlobstore::new(base.clone(), VerifyMode::Always);
+
+        // Keys that aren't content keys are never verified.
+        let value = BlobstoreBytes::from_bytes("not file contents");
+        wrapper
+            .put(ctx, "changeset.blake2.abc".to_owned(), value.clone())
+            .await
+            .unwrap();
+        let got = wrapper.get(ctx, "changeset.blake2.abc").await.unwrap();
+        assert_eq!(got.map(|d| d.into_bytes()), Some(value));
+
+        // Missing keys are still missing.
+        let id = FileContents::new_bytes("absent").content_id();
+        assert!(
+            wrapper
+                .get(ctx, &id.blobstore_key())
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // Corruption goes unnoticed when nothing is sampled.
+        let key = put_contents(ctx, &base, "", "hello world").await;
+        corrupt(ctx, &base, &key).await;
+        let never = VerifyingBlobstore::new(base.clone(), VerifyMode::Sampled(0.0));
+        assert!(never.get(ctx, &key).await.unwrap().is_some());
+        let always = VerifyingBlobstore::new(base, VerifyMode::Sampled(1.0));
+        assert!(always.get(ctx, &key).await.is_err());
+    }
+}
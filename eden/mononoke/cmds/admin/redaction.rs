@@ -342,12 +342,10 @@ async fn redaction_create_key_list_impl<'a, 'b>(
     let store_keys = |blobstore| {
         cloned!(blobstore_keys);
         async move {
-            RedactionKeyList {
-                keys: blobstore_keys,
-            }
-            .into_blob()
-            .store(ctx, &blobstore)
-            .await
+            RedactionKeyList::new(blobstore_keys)
+                .into_blob()
+                .store(ctx, &blobstore)
+                .await
         }
     };
 
@@ -27,15 +27,20 @@ use futures::future;
 use futures::stream::FuturesUnordered;
 use futures::stream::StreamExt;
 use futures::stream::TryStreamExt;
+use hooks::hook_error_to_json;
 use hooks::CrossRepoPushSource;
 use hooks::PushAuthoredBy;
 use mononoke_types::ChangesetId;
+use mononoke_types::Globalrev;
+use mononoke_types::MPath;
+use mononoke_types::PrefixTrie;
 use repo_factory::RepoFactory;
 use slog::debug;
 use slog::info;
 use slog::Logger;
 use tailer::HookExecutionInstance;
 use tailer::Tailer;
+use tailer::TailerLimits;
 use time_ext::DurationExt;
 use tokio::fs::File;
 use tokio::fs::OpenOptions;
@@ -103,14 +108,69 @@ async fn run_hook_tailer<'a>(
     logger: &Logger,
 ) -> Result<(), Error> {
     let config_store = matches.config_store();
-    let bookmark_name = matches.value_of("bookmark").unwrap();
-    let bookmark = BookmarkName::new(bookmark_name)?;
+    let bookmarks = matches
+        .values_of("bookmark")
+        .unwrap()
+        .map(BookmarkName::new)
+        .collect::<Result<Vec<_>>>()?;
+    let bookmark = bookmarks[0].clone();
+    // clap can't make flags conflict with a repeated --bookmark, so check that by hand.
+    let single_bookmark_args = [
+        "changeset",
+        "changeset_file",
+        "from_globalrev",
+        "report_slowest",
+        "summarize_by_hook",
+        "stats_file",
+        "json",
+    ];
+    if bookmarks.len() > 1 {
+        if let Some(arg) = single_bookmark_args
+            .iter()
+            .find(|arg| matches.is_present(arg))
+        {
+            bail!("--{} can't be used with more than one --bookmark", arg.replace('_', "-"));
+        }
+    }
     let common_config = cmdlib::args::load_common_config(config_store, matches)?;
-    let limit = cmdlib::args::get_usize(matches, "limit", 1000);
+    let limit = cmdlib::args::get_u64(matches, "limit", 1000);
     let concurrency = cmdlib::args::get_usize(matches, "concurrency", 20);
     let log_interval = cmdlib::args::get_usize(matches, "log_interval", 500);
+    let hook_timeout = matches
+        .value_of("hook_timeout_secs")
+        .map(|secs| secs.parse::<u64>().map(Duration::from_secs))
+        .transpose()?;
     let exclude_merges = matches.is_present("exclude_merges");
+    let excluded_authors = matches
+        .values_of("exclude_author")
+        .map(|authors| authors.map(String::from).collect())
+        .unwrap_or_default();
     let stats_file = matches.value_of("stats_file");
+    let json = matches.is_present("json");
+    let summarize_by_hook = matches.is_present("summarize_by_hook");
+    let globalrev_range = match (
+        matches.value_of("from_globalrev"),
+        matches.value_of("to_globalrev"),
+    ) {
+        (Some(start), Some(end)) => Some((
+            Globalrev::new(start.parse::<u64>()?),
+            Globalrev::new(end.parse::<u64>()?),
+        )),
+        (None, None) => None,
+        _ => bail!("--from-globalrev and --to-globalrev must be given together"),
+    };
+    let report_slowest = matches
+        .value_of("report_slowest")
+        .map(|n| n.parse::<usize>())
+        .transpose()?;
+    let path_filter = matches
+        .values_of("path_filter")
+        .map(|paths| {
+            paths
+                .map(|path| MPath::new(path).map(Some))
+                .collect::<Result<PrefixTrie>>()
+        })
+        .transpose()?;
     let cross_repo_push_source = match matches.value_of("push_source") {
         Some("native-to-this-repo") => CrossRepoPushSource::NativeToThisRepo,
         Some("push-redirected") => CrossRepoPushSource::PushRedirected,
@@ -159,18 +219,40 @@ async fn run_hook_tailer<'a>(
         blobrepo.clone(),
         config,
         bookmark,
-        concurrency,
+        TailerLimits {
+            max_changesets: limit,
+            concurrency,
+        },
+        hook_timeout,
         log_interval,
         exclude_merges,
         exclusions,
+        excluded_authors,
+        path_filter,
         &disabled_hooks,
         cross_repo_push_source,
         push_authored_by,
     )
     .await?;
 
+    if bookmarks.len() > 1 {
+        return run_for_bookmarks(tail, bookmarks, limit, logger).await;
+    }
+
+    if let Some((start, end)) = globalrev_range {
+        return run_globalrev_ordered(tail, start, end, logger).await;
+    }
+
+    if summarize_by_hook {
+        return run_with_summary(tail, limit, logger).await;
+    }
+
+    if let Some(slowest) = report_slowest {
+        return run_with_timings(tail, limit, slowest, logger).await;
+    }
+
     let mut stream = if inclusions.is_empty() {
-        tail.run_with_limit(limit).boxed()
+        tail.run().boxed()
     } else {
         tail.run_changesets(inclusions).boxed()
     };
@@ -180,7 +262,21 @@ async fn run_hook_tailer<'a>(
     info!(logger, "==== Hooks results ====");
 
     while let Some(instance) = stream.next().await {
-        let instance = instance?;
+        let instance = match instance {
+            Ok(instance) => instance,
+            Err(e) if json => {
+                println!("{}", hook_error_to_json(None, &e));
+                summary.errored += 1;
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        if json {
+            for outcome in instance.outcomes.iter() {
+                println!("{}", outcome.to_json());
+            }
+        }
 
         if let Some(ref mut stats_file) = stats_file {
             let line = format!(
@@ -210,10 +306,146 @@ async fn run_hook_tailer<'a>(
     );
     info!(logger, "Changesets accepted: {}", summary.accepted);
     info!(logger, "Changesets rejected: {}", summary.rejected);
+    if json {
+        info!(logger, "Changesets errored: {}", summary.errored);
+    }
 
     if summary.rejected > 0 {
         return Err(format_err!("Hook rejections: {}", summary.rejected));
     }
+    if summary.errored > 0 {
+        return Err(format_err!("Hook errors: {}", summary.errored));
+    }
+
+    Ok(())
+}
+
+async fn run_for_bookmarks(
+    tail: &Tailer,
+    bookmarks: Vec<BookmarkName>,
+    limit: u64,
+    logger: &Logger,
+) -> Result<(), Error> {
+    let results = tail.run_for_bookmarks(bookmarks, limit).await?;
+
+    let mut rejected = 0;
+    for (bookmark, outcomes) in results {
+        info!(logger, "==== Hooks results for {} ====", bookmark);
+        for outcome in outcomes {
+            if outcome.is_rejection() {
+                rejected += 1;
+                info!(logger, "{}", outcome);
+            } else {
+                debug!(logger, "{}", outcome);
+            }
+        }
+    }
+
+    if rejected > 0 {
+        return Err(format_err!("Hook rejections: {}", rejected));
+    }
+
+    Ok(())
+}
+
+async fn run_globalrev_ordered(
+    tail: &Tailer,
+    start: Globalrev,
+    end: Globalrev,
+    logger: &Logger,
+) -> Result<(), Error> {
+    let outcomes = tail.run_globalrev_ordered(start, end).await?;
+
+    let mut rejected = 0;
+    info!(logger, "==== Hooks results ====");
+    for outcome in outcomes {
+        if outcome.is_rejection() {
+            rejected += 1;
+            info!(logger, "{}", outcome);
+        } else {
+            debug!(logger, "{}", outcome);
+        }
+    }
+
+    if rejected > 0 {
+        return Err(format_err!("Hook rejections: {}", rejected));
+    }
+
+    Ok(())
+}
+
+async fn run_with_timings(
+    tail: &Tailer,
+    limit: u64,
+    slowest: usize,
+    logger: &Logger,
+) -> Result<(), Error> {
+    let mut results = tail.run_with_timings(limit).await?;
+
+    let mut rejected = 0;
+    info!(logger, "==== Hooks results ====");
+    for (_, outcomes) in results.iter() {
+        for outcome in outcomes {
+            if outcome.is_rejection() {
+                rejected += 1;
+                info!(logger, "{}", outcome);
+            } else {
+                debug!(logger, "{}", outcome);
+            }
+        }
+    }
+
+    results.sort_by_key(|(timing, _)| std::cmp::Reverse(timing.load_ms + timing.hooks_ms));
+    info!(logger, "==== Slowest changesets ====");
+    for (timing, _) in results.iter().take(slowest) {
+        info!(
+            logger,
+            "{}: load {}ms, hooks {}ms", timing.cs_id, timing.load_ms, timing.hooks_ms
+        );
+    }
+
+    if rejected > 0 {
+        return Err(format_err!("Hook rejections: {}", rejected));
+    }
+
+    Ok(())
+}
+
+async fn run_with_summary(tail: &Tailer, limit: u64, logger: &Logger) -> Result<(), Error> {
+    let (outcomes, summary) = tail.run_with_summary(limit).await?;
+
+    info!(logger, "==== Hooks results ====");
+    for outcome in outcomes.iter() {
+        if outcome.is_rejection() {
+            info!(logger, "{}", outcome);
+        } else {
+            debug!(logger, "{}", outcome);
+        }
+    }
+
+    info!(logger, "==== Hooks summary ====");
+    for (hook_name, tally) in summary.by_hook.iter() {
+        info!(
+            logger,
+            "{}: accepted {}, rejected {}, errored {}",
+            hook_name,
+            tally.accepted,
+            tally.rejected,
+            tally.errored
+        );
+    }
+    if summary.other_errors > 0 {
+        info!(logger, "Changesets errored: {}", summary.other_errors);
+    }
+
+    let total = summary.total();
+    if total.rejected > 0 {
+        return Err(format_err!("Hook rejections: {}", total.rejected));
+    }
+    let errored = total.errored + summary.other_errors;
+    if errored > 0 {
+        return Err(format_err!("Hook errors: {}", errored));
+    }
 
     Ok(())
 }
@@ -222,6 +454,7 @@ async fn run_hook_tailer<'a>(
 struct HookExecutionSummary {
     accepted: u64,
     rejected: u64,
+    errored: u64,
     completion_time: Duration,
     poll_time: Duration,
 }
@@ -259,7 +492,8 @@ fn setup_app<'a, 'b>() -> MononokeClapApp<'a, 'b> {
             Arg::with_name("bookmark")
                 .long("bookmark")
                 .short("B")
-                .help("bookmark to tail")
+                .multiple(true)
+                .help("bookmark to tail; if given more than once, run hooks for the ancestors of each bookmark")
                 .takes_value(true)
                 .required(true),
         )
@@ -313,19 +547,85 @@ fn setup_app<'a, 'b>() -> MononokeClapApp<'a, 'b> {
                 .long("exclude-merges")
                 .help("exclude changesets that are merges (more than one parent)"),
         )
+        .arg(
+            Arg::with_name("exclude_author")
+                .long("exclude-author")
+                .multiple(true)
+                .takes_value(true)
+                .help("skip changesets whose author is exactly this, such as automation accounts"),
+        )
+        .arg(
+            Arg::with_name("hook_timeout_secs")
+                .long("hook-timeout-secs")
+                .takes_value(true)
+                .help("report a changeset as timed out if its hooks take longer than this, rather than waiting for them"),
+        )
+        .arg(
+            Arg::with_name("path_filter")
+                .long("path-filter")
+                .multiple(true)
+                .takes_value(true)
+                .help("only run hooks for changesets that touch files under this path"),
+        )
         .arg(
             Arg::with_name("limit")
                 .long("limit")
                 .takes_value(true)
-                .help("limit number of commits to process (non-continuous only)")
+                .help("limit number of commits to process, 0 for no limit (non-continuous only)")
                 .default_value("1000"),
         )
+        .arg(
+            Arg::with_name("from_globalrev")
+                .long("from-globalrev")
+                .takes_value(true)
+                .requires("to_globalrev")
+                .conflicts_with_all(&[
+                    "changeset",
+                    "changeset_file",
+                    "report_slowest",
+                    "summarize_by_hook",
+                    "stats_file",
+                    "json",
+                ])
+                .help("run hooks for the changesets with globalrevs from this one (inclusive), in globalrev order"),
+        )
+        .arg(
+            Arg::with_name("to_globalrev")
+                .long("to-globalrev")
+                .takes_value(true)
+                .requires("from_globalrev")
+                .help("with --from-globalrev, the globalrev to stop at (exclusive)"),
+        )
+        .arg(
+            Arg::with_name("report_slowest")
+                .long("report-slowest")
+                .takes_value(true)
+                .conflicts_with_all(&[
+                    "changeset",
+                    "changeset_file",
+                    "summarize_by_hook",
+                    "stats_file",
+                    "json",
+                ])
+                .help("after running hooks for the bookmark, log the N changesets that took longest to load and run hooks for"),
+        )
+        .arg(
+            Arg::with_name("summarize_by_hook")
+                .long("summarize-by-hook")
+                .conflicts_with_all(&["changeset", "changeset_file", "stats_file", "json"])
+                .help("after running hooks for the bookmark, log how many changes each hook accepted, rejected and failed on"),
+        )
         .arg(
             Arg::with_name("stats_file")
                 .long("stats-file")
                 .takes_value(true)
                 .help("Log hook execution statistics to a file (CSV format)"),
         )
+        .arg(
+            Arg::with_name("json")
+                .long("json")
+                .help("print each hook outcome to stdout as a line of JSON, and report changesets that fail to run hooks instead of stopping"),
+        )
         .arg(
             Arg::with_name("push_source")
                 .long("push-source")
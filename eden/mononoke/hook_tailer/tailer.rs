@@ -5,18 +5,26 @@
  * GNU General Public License version 2.
  */
 
+use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Error;
 use anyhow::Result;
 use blobrepo::BlobRepo;
 use blobstore::Loadable;
+use bonsai_globalrev_mapping::BonsaiGlobalrevMappingEntry;
+use bonsai_globalrev_mapping::BonsaisOrGlobalrevs;
 use bookmarks::BookmarkName;
 use cloned::cloned;
 use context::CoreContext;
 use futures::compat::Stream01CompatExt;
 use futures::future;
+use futures::future::BoxFuture;
+use futures::future::Future;
+use futures::future::FutureExt;
 use futures::future::TryFutureExt;
 use futures::stream;
 use futures::stream::Stream;
@@ -25,37 +33,150 @@ use futures::stream::TryStreamExt;
 use futures_stats::FutureStats;
 use futures_stats::TimedFutureExt;
 use hooks::hook_loader::load_hooks;
+use hooks::ChangesetHookExecutionID;
 use hooks::CrossRepoPushSource;
+use hooks::ErrorKind as HookErrorKind;
+use hooks::HookExecution;
 use hooks::HookManager;
 use hooks::HookOutcome;
+use hooks::HookRejectionInfo;
 use hooks::PushAuthoredBy;
 use hooks_content_stores::repo_text_only_fetcher;
 use metaconfig_types::RepoConfig;
+use mononoke_types::BonsaiChangeset;
 use mononoke_types::ChangesetId;
+use mononoke_types::Globalrev;
+use mononoke_types::PrefixTrie;
 use permission_checker::AclProvider;
 use revset::AncestorsNodeStream;
 use scuba_ext::MononokeScubaSampleBuilder;
 use slog::debug;
 use slog::info;
+use slog::warn;
 use thiserror::Error;
+use time_ext::DurationExt;
 use tokio::task;
 
+/// The hook name reported for changesets whose hooks didn't finish within the tailer's hook
+/// timeout.
+pub const TIMEOUT_HOOK_NAME: &str = "hook_tailer_timeout";
+
 pub struct HookExecutionInstance {
     pub cs_id: ChangesetId,
     pub file_count: usize,
+    pub load_stats: FutureStats,
     pub stats: FutureStats,
     pub outcomes: Vec<HookOutcome>,
 }
 
+impl HookExecutionInstance {
+    pub fn timing(&self) -> ChangesetHookTiming {
+        ChangesetHookTiming {
+            cs_id: self.cs_id,
+            load_ms: self.load_stats.completion_time.as_millis_unchecked(),
+            hooks_ms: self.stats.completion_time.as_millis_unchecked(),
+        }
+    }
+}
+
+/// How long it took to load a changeset and to run hooks for it, in milliseconds.
+#[derive(Clone, Copy, Debug)]
+pub struct ChangesetHookTiming {
+    pub cs_id: ChangesetId,
+    pub load_ms: u64,
+    pub hooks_ms: u64,
+}
+
+/// Outcome counts for a single hook.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HookTally {
+    pub accepted: u64,
+    pub rejected: u64,
+    /// Runs where the hook itself failed, rather than rejecting the change.
+    pub errored: u64,
+}
+
+/// Outcome counts for a tailer run, grouped by hook name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HookOutcomeSummary {
+    pub by_hook: BTreeMap<String, HookTally>,
+    /// Changesets that failed for a reason that can't be attributed to a hook, such as failing
+    /// to load the changeset.
+    pub other_errors: u64,
+}
+
+impl HookOutcomeSummary {
+    pub fn add_outcome(&mut self, outcome: &HookOutcome) {
+        let tally = self
+            .by_hook
+            .entry(outcome.get_hook_name().to_string())
+            .or_default();
+        if outcome.is_rejection() {
+            tally.rejected += 1;
+        } else {
+            tally.accepted += 1;
+        }
+    }
+
+    /// Record a changeset that failed to run. If a hook failed, it is counted against that hook.
+    pub fn add_error(&mut self, error: &Error) {
+        match error.downcast_ref::<HookErrorKind>() {
+            Some(HookErrorKind::HookRuntimeError(hook_name)) => {
+                self.by_hook.entry(hook_name.clone()).or_default().errored += 1;
+            }
+            _ => self.other_errors += 1,
+        }
+    }
+
+    pub fn total(&self) -> HookTally {
+        self.by_hook
+            .values()
+            .fold(HookTally::default(), |total, tally| HookTally {
+                accepted: total.accepted + tally.accepted,
+                rejected: total.rejected + tally.rejected,
+                errored: total.errored + tally.errored,
+            })
+    }
+}
+
+pub fn summarize(outcomes: &[HookOutcome]) -> HookOutcomeSummary {
+    let mut summary = HookOutcomeSummary::default();
+    for outcome in outcomes {
+        summary.add_outcome(outcome);
+    }
+    summary
+}
+
+/// Bounds on how much work a `Tailer` does.
+#[derive(Clone, Copy, Debug)]
+pub struct TailerLimits {
+    /// The number of ancestors of the bookmark to run hooks for. 0 means unbounded.
+    pub max_changesets: u64,
+    /// The number of changesets to run hooks for in parallel.
+    pub concurrency: usize,
+}
+
+impl Default for TailerLimits {
+    fn default() -> Self {
+        Self {
+            max_changesets: 1000,
+            concurrency: 100,
+        }
+    }
+}
+
 pub struct Tailer {
     ctx: CoreContext,
     repo: BlobRepo,
     hook_manager: Arc<HookManager>,
     bookmark: BookmarkName,
-    concurrency: usize,
+    limits: TailerLimits,
+    hook_timeout: Option<Duration>,
     log_interval: usize,
     exclude_merges: bool,
     excludes: HashSet<ChangesetId>,
+    excluded_authors: Arc<HashSet<String>>,
+    path_filter: Option<Arc<PrefixTrie>>,
     cross_repo_push_source: CrossRepoPushSource,
     push_authored_by: PushAuthoredBy,
 }
@@ -67,10 +188,13 @@ impl Tailer {
         repo: BlobRepo,
         config: RepoConfig,
         bookmark: BookmarkName,
-        concurrency: usize,
+        limits: TailerLimits,
+        hook_timeout: Option<Duration>,
         log_interval: usize,
         exclude_merges: bool,
         excludes: HashSet<ChangesetId>,
+        excluded_authors: HashSet<String>,
+        path_filter: Option<PrefixTrie>,
         disabled_hooks: &HashSet<String>,
         cross_repo_push_source: CrossRepoPushSource,
         push_authored_by: PushAuthoredBy,
@@ -101,15 +225,20 @@ impl Tailer {
             repo,
             hook_manager: Arc::new(hook_manager),
             bookmark,
-            concurrency,
+            limits,
+            hook_timeout,
             log_interval,
             exclude_merges,
             excludes,
+            excluded_authors: Arc::new(excluded_authors),
+            path_filter: path_filter.map(Arc::new),
             cross_repo_push_source,
             push_authored_by,
         })
     }
 
+    /// Run hooks for `changesets`, yielding results in the order given. The bookmark isn't
+    /// resolved; it's only passed to the hooks as the bookmark being moved.
     pub fn run_changesets<'a, I>(
         &'a self,
         changesets: I,
@@ -118,12 +247,18 @@ impl Tailer {
         I: IntoIterator<Item = ChangesetId> + 'a,
     {
         let stream = stream::iter(changesets.into_iter().map(Ok));
-        self.run_on_stream(stream)
+        self.run_on_stream(&self.bookmark, stream)
     }
 
+    /// Run hooks for the ancestors of the bookmark, up to the configured `max_changesets`.
+    pub fn run<'a>(&'a self) -> impl Stream<Item = Result<HookExecutionInstance, Error>> + 'a {
+        self.run_with_limit(self.limits.max_changesets)
+    }
+
+    /// Run hooks for up to `limit` ancestors of the bookmark. A limit of 0 means unbounded.
     pub fn run_with_limit<'a>(
         &'a self,
-        limit: usize,
+        limit: u64,
     ) -> impl Stream<Item = Result<HookExecutionInstance, Error>> + 'a {
         async move {
             let bm_rev = self
@@ -133,21 +268,159 @@ impl Tailer {
                 .await?
                 .ok_or_else(|| ErrorKind::NoSuchBookmark(self.bookmark.clone()))?;
 
-            let stream = AncestorsNodeStream::new(
-                self.ctx.clone(),
-                &self.repo.get_changeset_fetcher(),
-                bm_rev,
-            )
-            .compat()
-            .take(limit);
-
-            Ok(self.run_on_stream(stream))
+            Ok(self.run_ancestors(&self.bookmark, bm_rev, limit))
         }
         .try_flatten_stream()
     }
 
+    /// Run hooks for up to `limit` ancestors of the bookmark, returning how long each changeset
+    /// took alongside its outcomes.
+    pub async fn run_with_timings(
+        &self,
+        limit: u64,
+    ) -> Result<Vec<(ChangesetHookTiming, Vec<HookOutcome>)>, Error> {
+        self.run_with_limit(limit)
+            .map_ok(|instance| (instance.timing(), instance.outcomes))
+            .try_collect()
+            .await
+    }
+
+    /// Run hooks for up to `limit` ancestors of the bookmark, tallying the outcomes by hook.
+    /// Changesets that fail to run are counted in the summary rather than ending the run.
+    pub fn run_with_summary<'a>(
+        &'a self,
+        limit: u64,
+    ) -> BoxFuture<'a, Result<(Vec<HookOutcome>, HookOutcomeSummary), Error>> {
+        async move {
+            let bm_rev = self
+                .repo
+                .bookmarks()
+                .get(self.ctx.clone(), &self.bookmark)
+                .await?
+                .ok_or_else(|| ErrorKind::NoSuchBookmark(self.bookmark.clone()))?;
+
+            let mut outcomes = Vec::new();
+            let mut errors = Vec::new();
+            let mut stream = self.run_ancestors(&self.bookmark, bm_rev, limit).boxed();
+            while let Some(instance) = stream.next().await {
+                match instance {
+                    Ok(instance) => outcomes.extend(instance.outcomes),
+                    Err(e) => {
+                        warn!(self.ctx.logger(), "Hooks failed to run: {:?}", e);
+                        errors.push(e);
+                    }
+                }
+            }
+
+            let mut summary = summarize(&outcomes);
+            for e in errors.iter() {
+                summary.add_error(e);
+            }
+            Ok((outcomes, summary))
+        }
+        .boxed()
+    }
+
+    /// Run hooks for the changesets with globalrevs in `[start, end)`, in ascending globalrev
+    /// order. Globalrevs in the range that have no changeset are skipped with a warning.
+    pub fn run_globalrev_ordered<'a>(
+        &'a self,
+        start: Globalrev,
+        end: Globalrev,
+    ) -> BoxFuture<'a, Result<Vec<HookOutcome>, Error>> {
+        async move {
+            let globalrevs: Vec<_> = Globalrev::range(start, end).collect();
+            let entries = self
+                .repo
+                .bonsai_globalrev_mapping()
+                .get(
+                    &self.ctx,
+                    BonsaisOrGlobalrevs::Globalrev(globalrevs.clone()),
+                )
+                .await?;
+
+            let (cs_ids, missing) = order_by_globalrev(&globalrevs, entries);
+            for globalrev in missing {
+                warn!(
+                    self.ctx.logger(),
+                    "Skipping globalrev {} as it has no changeset",
+                    globalrev.id()
+                );
+            }
+
+            // Hooks run concurrently, but results come back in the order of `cs_ids`.
+            self.run_changesets(cs_ids)
+                .try_fold(Vec::new(), |mut outcomes, instance| {
+                    outcomes.extend(instance.outcomes);
+                    future::ready(Ok(outcomes))
+                })
+                .await
+        }
+        .boxed()
+    }
+
+    /// Run hooks for up to `limit` ancestors of each of the given bookmarks, as if they were
+    /// being pushed to that bookmark. Bookmarks that don't exist are skipped with a warning.
+    /// A limit of 0 means unbounded.
+    pub async fn run_for_bookmarks(
+        &self,
+        bookmarks: Vec<BookmarkName>,
+        limit: u64,
+    ) -> Result<HashMap<BookmarkName, Vec<HookOutcome>>, Error> {
+        let mut results = HashMap::new();
+
+        for bookmark in bookmarks {
+            let bm_rev = match self
+                .repo
+                .bookmarks()
+                .get(self.ctx.clone(), &bookmark)
+                .await?
+            {
+                Some(bm_rev) => bm_rev,
+                None => {
+                    warn!(
+                        self.ctx.logger(),
+                        "Skipping bookmark {} as it does not exist", bookmark
+                    );
+                    continue;
+                }
+            };
+
+            let outcomes = self
+                .run_ancestors(&bookmark, bm_rev, limit)
+                .try_fold(Vec::new(), |mut outcomes, instance| {
+                    outcomes.extend(instance.outcomes);
+                    future::ready(Ok(outcomes))
+                })
+                .await?;
+
+            results.insert(bookmark, outcomes);
+        }
+
+        Ok(results)
+    }
+
+    fn run_ancestors<'a>(
+        &'a self,
+        bookmark: &'a BookmarkName,
+        start: ChangesetId,
+        limit: u64,
+    ) -> impl Stream<Item = Result<HookExecutionInstance, Error>> + 'a {
+        let limit = match limit {
+            0 => usize::MAX,
+            limit => usize::try_from(limit).unwrap_or(usize::MAX),
+        };
+        let stream =
+            AncestorsNodeStream::new(self.ctx.clone(), &self.repo.get_changeset_fetcher(), start)
+                .compat()
+                .take(limit);
+
+        self.run_on_stream(bookmark, stream)
+    }
+
     fn run_on_stream<'a, S>(
         &'a self,
+        bookmark: &'a BookmarkName,
         stream: S,
     ) -> impl Stream<Item = Result<HookExecutionInstance, Error>> + 'a
     where
@@ -172,24 +445,32 @@ impl Tailer {
                             self.ctx,
                             self.repo,
                             self.hook_manager,
-                            self.bookmark,
+                            bookmark,
                             self.exclude_merges,
+                            self.excluded_authors,
+                            self.path_filter,
                             self.cross_repo_push_source,
                             self.push_authored_by,
+                            self.hook_timeout,
                         );
 
                         let maybe_outcomes = task::spawn(async move {
-                            run_hooks_for_changeset(
+                            let run = run_hooks_for_changeset(
                                 &ctx,
                                 &repo,
                                 hook_manager.as_ref(),
                                 &bookmark,
                                 cs_id,
                                 exclude_merges,
+                                &excluded_authors,
+                                path_filter.as_deref(),
                                 cross_repo_push_source,
                                 push_authored_by,
-                            )
-                            .await
+                            );
+                            match hook_timeout {
+                                Some(timeout) => with_timeout(&ctx, cs_id, timeout, run).await,
+                                None => run.await,
+                            }
                         })
                         .await??;
 
@@ -198,7 +479,7 @@ impl Tailer {
                     Err(e) => Err(e),
                 }
             })
-            .buffered(self.concurrency)
+            .buffered(self.limits.concurrency)
             .try_filter_map(|maybe_outcomes| future::ready(Ok(maybe_outcomes)))
     }
 }
@@ -210,16 +491,39 @@ async fn run_hooks_for_changeset(
     bm: &BookmarkName,
     cs_id: ChangesetId,
     exclude_merges: bool,
+    excluded_authors: &HashSet<String>,
+    path_filter: Option<&PrefixTrie>,
     cross_repo_push_source: CrossRepoPushSource,
     push_authored_by: PushAuthoredBy,
 ) -> Result<Option<HookExecutionInstance>, Error> {
-    let cs = cs_id.load(ctx, repo.blobstore()).await?;
+    let (load_stats, cs) = cs_id.load(ctx, repo.blobstore()).timed().await;
+    let cs = cs?;
 
     if exclude_merges && cs.is_merge() {
         info!(ctx.logger(), "Skipped merge commit {}", cs_id);
         return Ok(None);
     }
 
+    if is_excluded_author(&cs, excluded_authors) {
+        debug!(
+            ctx.logger(),
+            "Skipped commit {} by excluded author {}",
+            cs_id,
+            cs.author()
+        );
+        return Ok(None);
+    }
+
+    if let Some(path_filter) = path_filter {
+        if !touches_filtered_paths(&cs, path_filter) {
+            debug!(
+                ctx.logger(),
+                "Skipped commit {} as it touches no filtered paths", cs_id
+            );
+            return Ok(None);
+        }
+    }
+
     debug!(ctx.logger(), "Running hooks for changeset {:?}", cs);
 
     let file_count = cs.file_changes_map().len();
@@ -241,13 +545,637 @@ async fn run_hooks_for_changeset(
     Ok(Some(HookExecutionInstance {
         cs_id,
         file_count,
+        load_stats,
         stats,
         outcomes,
     }))
 }
 
+/// Waits for `run` to finish running hooks for `cs_id`. If that takes longer than `timeout`, gives
+/// up on it and instead returns a rejection by `TIMEOUT_HOOK_NAME`, so that a hung hook doesn't
+/// stall the whole run.
+async fn with_timeout<F>(
+    ctx: &CoreContext,
+    cs_id: ChangesetId,
+    timeout: Duration,
+    run: F,
+) -> Result<Option<HookExecutionInstance>, Error>
+where
+    F: Future<Output = Result<Option<HookExecutionInstance>, Error>>,
+{
+    let (stats, res) = tokio::time::timeout(timeout, run).timed().await;
+    match res {
+        Ok(res) => res,
+        Err(_) => {
+            warn!(
+                ctx.logger(),
+                "Hooks for {} timed out after {:?}", cs_id, timeout
+            );
+            Ok(Some(HookExecutionInstance {
+                cs_id,
+                file_count: 0,
+                load_stats: stats.clone(),
+                stats,
+                outcomes: vec![timeout_outcome(cs_id, timeout)],
+            }))
+        }
+    }
+}
+
+fn timeout_outcome(cs_id: ChangesetId, timeout: Duration) -> HookOutcome {
+    HookOutcome::ChangesetHook(
+        ChangesetHookExecutionID {
+            cs_id,
+            hook_name: TIMEOUT_HOOK_NAME.to_string(),
+        },
+        HookExecution::Rejected(HookRejectionInfo::new_long(
+            "Hooks timed out",
+            format!("Hooks did not finish within {:?}", timeout),
+        )),
+    )
+}
+
+/// Returns the changesets for `globalrevs` in the same order, along with the globalrevs that
+/// have no entry in `entries`.
+fn order_by_globalrev(
+    globalrevs: &[Globalrev],
+    entries: Vec<BonsaiGlobalrevMappingEntry>,
+) -> (Vec<ChangesetId>, Vec<Globalrev>) {
+    let by_globalrev: HashMap<_, _> = entries
+        .into_iter()
+        .map(|entry| (entry.globalrev, entry.bcs_id))
+        .collect();
+
+    let mut cs_ids = Vec::new();
+    let mut missing = Vec::new();
+    for globalrev in globalrevs {
+        match by_globalrev.get(globalrev) {
+            Some(cs_id) => cs_ids.push(*cs_id),
+            None => missing.push(*globalrev),
+        }
+    }
+    (cs_ids, missing)
+}
+
+/// Returns true if the author of `cs` is exactly one of `excluded_authors`.
+fn is_excluded_author(cs: &BonsaiChangeset, excluded_authors: &HashSet<String>) -> bool {
+    excluded_authors.contains(cs.author())
+}
+
+/// Returns true if any of the files changed in `cs` is under a path in `path_filter`.
+fn touches_filtered_paths(cs: &BonsaiChangeset, path_filter: &PrefixTrie) -> bool {
+    cs.file_changes_map()
+        .keys()
+        .any(|path| path_filter.contains_prefix(path))
+}
+
 #[derive(Debug, Error)]
 pub enum ErrorKind {
     #[error("No such bookmark '{0}'")]
     NoSuchBookmark(BookmarkName),
 }
+
+#[cfg(test)]
+mod test {
+    use anyhow::format_err;
+    use async_trait::async_trait;
+    use fbinit::FacebookInit;
+    use hooks::ChangesetHook;
+    use hooks::FileContentManager;
+    use hooks::FileHookExecutionID;
+    use metaconfig_types::BookmarkParams;
+    use metaconfig_types::HookConfig;
+    use metaconfig_types::HookParams;
+    use mononoke_types::BonsaiChangesetMut;
+    use mononoke_types::DateTime;
+    use mononoke_types::FileChange;
+    use mononoke_types::FileType;
+    use mononoke_types::MPath;
+    use mononoke_types_mocks::changesetid::FOURS_CSID;
+    use mononoke_types_mocks::changesetid::ONES_CSID;
+    use mononoke_types_mocks::changesetid::THREES_CSID;
+    use mononoke_types_mocks::changesetid::TWOS_CSID;
+    use mononoke_types_mocks::contentid::ONES_CTID;
+    use permission_checker::DefaultAclProvider;
+    use tests_utils::bookmark;
+    use tests_utils::CreateCommitContext;
+
+    use super::*;
+
+    fn changeset_outcome(hook_name: &str, exec: HookExecution) -> HookOutcome {
+        HookOutcome::ChangesetHook(
+            ChangesetHookExecutionID {
+                cs_id: ONES_CSID,
+                hook_name: hook_name.to_string(),
+            },
+            exec,
+        )
+    }
+
+    fn file_outcome(hook_name: &str, exec: HookExecution) -> HookOutcome {
+        HookOutcome::FileHook(
+            FileHookExecutionID {
+                cs_id: ONES_CSID,
+                hook_name: hook_name.to_string(),
+                path: MPath::new("dir/file").unwrap(),
+            },
+            exec,
+        )
+    }
+
+    fn rejected() -> HookExecution {
+        HookExecution::Rejected(HookRejectionInfo::new("rejected"))
+    }
+
+    #[test]
+    fn test_summarize() {
+        let outcomes = vec![
+            changeset_outcome("block_empty_commit", HookExecution::Accepted),
+            changeset_outcome("block_empty_commit", rejected()),
+            changeset_outcome("block_empty_commit", HookExecution::Accepted),
+            file_outcome("limit_filesize", rejected()),
+            file_outcome("limit_filesize", rejected()),
+        ];
+
+        let mut summary = summarize(&outcomes);
+        summary.add_error(
+            &format_err!("hook crashed").context(HookErrorKind::HookRuntimeError(
+                "limit_filesize".to_string(),
+            )),
+        );
+        summary.add_error(&format_err!("changeset not found"));
+
+        assert_eq!(
+            summary.by_hook["block_empty_commit"],
+            HookTally {
+                accepted: 2,
+                rejected: 1,
+                errored: 0,
+            }
+        );
+        assert_eq!(
+            summary.by_hook["limit_filesize"],
+            HookTally {
+                accepted: 0,
+                rejected: 2,
+                errored: 1,
+            }
+        );
+        assert_eq!(summary.other_errors, 1);
+        assert_eq!(
+            summary.total(),
+            HookTally {
+                accepted: 2,
+                rejected: 3,
+                errored: 1,
+            }
+        );
+    }
+
+    fn changeset_by(author: &str) -> BonsaiChangeset {
+        BonsaiChangesetMut {
+            parents: vec![],
+            author: author.to_string(),
+            author_date: DateTime::from_timestamp(0, 0).unwrap(),
+            committer: None,
+            committer_date: None,
+            message: format!("commit by {}", author),
+            extra: Default::default(),
+            file_changes: Default::default(),
+            is_snapshot: false,
+        }
+        .freeze()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_excluded_authors() {
+        let excluded = HashSet::from(["svcscm <svcscm@fb.com>".to_string()]);
+        let bot = changeset_by("svcscm <svcscm@fb.com>");
+        let human = changeset_by("Jane Doe <jane@example.com>");
+
+        let processed: Vec<_> = [&bot, &human]
+            .into_iter()
+            .filter(|cs| !is_excluded_author(cs, &excluded))
+            .map(|cs| cs.get_changeset_id())
+            .collect();
+        assert_eq!(processed, vec![human.get_changeset_id()]);
+
+        // Only exact matches are excluded, so case, whitespace and partial matches don't count.
+        for author in [
+            "svcscm",
+            "SVCSCM <svcscm@fb.com>",
+            " svcscm <svcscm@fb.com>",
+            "svcscm <svcscm@fb.com> ",
+            "",
+        ] {
+            assert!(!is_excluded_author(&changeset_by(author), &excluded));
+        }
+        assert!(!is_excluded_author(&bot, &HashSet::new()));
+    }
+
+    fn changeset_touching(paths: &[&str]) -> BonsaiChangeset {
+        BonsaiChangesetMut {
+            parents: vec![],
+            author: "author".to_string(),
+            author_date: DateTime::from_timestamp(0, 0).unwrap(),
+            committer: None,
+            committer_date: None,
+            message: "message".to_string(),
+            extra: Default::default(),
+            file_changes: paths
+                .iter()
+                .map(|path| {
+                    (
+                        MPath::new(path).unwrap(),
+                        FileChange::tracked(ONES_CTID, FileType::Regular, 1, None),
+                    )
+                })
+                .collect(),
+            is_snapshot: false,
+        }
+        .freeze()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_touches_filtered_paths() {
+        let filter = |prefixes: &[&str]| {
+            prefixes
+                .iter()
+                .map(|prefix| Ok(Some(MPath::new(prefix)?)))
+                .collect::<Result<PrefixTrie>>()
+                .unwrap()
+        };
+        let src_only = changeset_touching(&["src/main.rs", "src/lib/util.rs"]);
+        let docs_and_src = changeset_touching(&["docs/README", "src/main.rs"]);
+
+        // A changeset touching only src/ is skipped by a docs/ filter.
+        let docs = filter(&["docs"]);
+        assert!(!touches_filtered_paths(&src_only, &docs));
+        assert!(touches_filtered_paths(&docs_and_src, &docs));
+
+        // And kept by a filter that includes src/.
+        assert!(touches_filtered_paths(&src_only, &filter(&["docs", "src"])));
+        assert!(touches_filtered_paths(&src_only, &filter(&["src/lib"])));
+
+        // Prefixes match whole path elements only.
+        assert!(!touches_filtered_paths(
+            &src_only,
+            &filter(&["sr", "src/main"])
+        ));
+    }
+
+    #[test]
+    fn test_order_by_globalrev() {
+        let globalrevs: Vec<_> = Globalrev::range(Globalrev::new(10), Globalrev::new(15)).collect();
+        // The mapping returns entries in no particular order, and 13 is missing.
+        let entries = vec![
+            BonsaiGlobalrevMappingEntry::new(FOURS_CSID, Globalrev::new(14)),
+            BonsaiGlobalrevMappingEntry::new(ONES_CSID, Globalrev::new(10)),
+            BonsaiGlobalrevMappingEntry::new(THREES_CSID, Globalrev::new(12)),
+            BonsaiGlobalrevMappingEntry::new(TWOS_CSID, Globalrev::new(11)),
+        ];
+
+        let (cs_ids, missing) = order_by_globalrev(&globalrevs, entries);
+        assert_eq!(cs_ids, vec![ONES_CSID, TWOS_CSID, THREES_CSID, FOURS_CSID]);
+        assert_eq!(missing, vec![Globalrev::new(13)]);
+    }
+
+    #[fbinit::test]
+    async fn test_hook_timeout(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let timeout = Duration::from_millis(10);
+
+        // A run that finishes in time is returned as it is.
+        let fast = with_timeout(&ctx, ONES_CSID, timeout, async { Ok(None) }).await?;
+        assert!(fast.is_none());
+
+        // A run whose hooks hang is replaced by a timeout rejection.
+        let slow_hook = async {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+            Ok(None)
+        };
+        let instance = with_timeout(&ctx, TWOS_CSID, timeout, slow_hook)
+            .await?
+            .expect("timed out changesets are reported");
+        assert_eq!(instance.cs_id, TWOS_CSID);
+        assert_eq!(instance.outcomes.len(), 1);
+        let outcome = &instance.outcomes[0];
+        assert_eq!(outcome.get_hook_name(), TIMEOUT_HOOK_NAME);
+        assert_eq!(outcome.get_changeset_id(), TWOS_CSID);
+        assert!(outcome.is_rejection());
+        assert!(instance.stats.completion_time >= timeout);
+
+        // Errors from a run that finishes in time are passed through.
+        let failing = async { Err(format_err!("failed to load")) };
+        assert!(
+            with_timeout(&ctx, ONES_CSID, timeout, failing)
+                .await
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_slow_hook_is_rejected_not_errored(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let timeout = Duration::from_millis(10);
+        let (stats, ()) = async {}.timed().await;
+
+        // Hooks that would eventually accept, or fail, are both reported as a timeout rejection
+        // once they take longer than the timeout.
+        let slow_accept = async {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            Ok(Some(HookExecutionInstance {
+                cs_id: ONES_CSID,
+                file_count: 1,
+                load_stats: stats.clone(),
+                stats: stats.clone(),
+                outcomes: vec![changeset_outcome("slow_hook", HookExecution::Accepted)],
+            }))
+        };
+        let slow_error = async {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            Err(format_err!("hook crashed"))
+        };
+
+        for instance in [
+            with_timeout(&ctx, ONES_CSID, timeout, slow_accept).await?,
+            with_timeout(&ctx, ONES_CSID, timeout, slow_error).await?,
+        ] {
+            let instance = instance.expect("timed out changesets are reported");
+            assert_eq!(instance.cs_id, ONES_CSID);
+            assert_eq!(instance.file_count, 0);
+            match instance.outcomes.as_slice() {
+                [HookOutcome::ChangesetHook(id, HookExecution::Rejected(info))] => {
+                    assert_eq!(id.hook_name, TIMEOUT_HOOK_NAME);
+                    assert_eq!(id.cs_id, ONES_CSID);
+                    assert!(info.long_description.contains("10ms"));
+                }
+                other => panic!("Unexpected outcomes: {:?}", other),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Create a repo with a linear stack of `len` changesets, with `main` pointing at the top.
+    /// Returns the changesets from the bottom of the stack up.
+    async fn linear_repo(ctx: &CoreContext, len: usize) -> Result<(BlobRepo, Vec<ChangesetId>)> {
+        let repo: BlobRepo = test_repo_factory::build_empty(ctx.fb)?;
+        let mut cs_ids = Vec::new();
+        for i in 0..len {
+            let mut commit = CreateCommitContext::new_root(ctx, &repo)
+                .add_file(format!("file{}", i).as_str(), "content");
+            if let Some(parent) = cs_ids.last() {
+                commit = commit.add_parent(*parent);
+            }
+            cs_ids.push(commit.commit().await?);
+        }
+        if let Some(top) = cs_ids.last() {
+            bookmark(ctx, &repo, "main").set_to(*top).await?;
+        }
+        Ok((repo, cs_ids))
+    }
+
+    /// A config that runs `always_fail_changeset` for `bookmark`, so every changeset hooks are
+    /// run for has one rejection.
+    fn always_fail_config(bookmark: &str) -> Result<RepoConfig> {
+        Ok(RepoConfig {
+            bookmarks: vec![BookmarkParams {
+                bookmark: BookmarkName::new(bookmark)?.into(),
+                hooks: vec!["always_fail_changeset".to_string()],
+                only_fast_forward: false,
+                rewrite_dates: None,
+                allowed_users: None,
+                allowed_hipster_group: None,
+                hooks_skip_ancestors_of: vec![],
+                ensure_ancestor_of: None,
+                allow_move_to_public_commits_without_hooks: false,
+            }],
+            hooks: vec![HookParams {
+                name: "always_fail_changeset".to_string(),
+                config: HookConfig::default(),
+            }],
+            ..Default::default()
+        })
+    }
+
+    /// Create a tailer for `main` in `repo`, with the hooks in `config`.
+    async fn test_tailer(
+        ctx: &CoreContext,
+        repo: BlobRepo,
+        config: RepoConfig,
+        limits: TailerLimits,
+    ) -> Result<Tailer> {
+        Tailer::new(
+            ctx.clone(),
+            DefaultAclProvider::new(ctx.fb).as_ref(),
+            repo,
+            config,
+            BookmarkName::new("main")?,
+            limits,
+            None,
+            1,
+            false,
+            HashSet::new(),
+            HashSet::new(),
+            None,
+            &HashSet::new(),
+            CrossRepoPushSource::NativeToThisRepo,
+            PushAuthoredBy::User,
+        )
+        .await
+    }
+
+    #[fbinit::test]
+    async fn test_max_changesets(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let (repo, cs_ids) = linear_repo(&ctx, 5).await?;
+        // Ancestors are walked from the bookmark down.
+        let newest_first: Vec<_> = cs_ids.iter().rev().copied().collect();
+
+        let limits = TailerLimits {
+            max_changesets: 3,
+            concurrency: 2,
+        };
+        let tailer = test_tailer(&ctx, repo.clone(), RepoConfig::default(), limits).await?;
+        let walked: Vec<_> = tailer.run().map_ok(|i| i.cs_id).try_collect().await?;
+        assert_eq!(walked, newest_first[..3]);
+
+        // An explicit limit overrides max_changesets, and 0 means no limit.
+        let walked: Vec<_> = tailer
+            .run_with_limit(1)
+            .map_ok(|i| i.cs_id)
+            .try_collect()
+            .await?;
+        assert_eq!(walked, newest_first[..1]);
+        let walked: Vec<_> = tailer
+            .run_with_limit(0)
+            .map_ok(|i| i.cs_id)
+            .try_collect()
+            .await?;
+        assert_eq!(walked, newest_first);
+
+        let unbounded = TailerLimits {
+            max_changesets: 0,
+            concurrency: 2,
+        };
+        let tailer = test_tailer(&ctx, repo, RepoConfig::default(), unbounded).await?;
+        let walked: Vec<_> = tailer.run().map_ok(|i| i.cs_id).try_collect().await?;
+        assert_eq!(walked, newest_first);
+
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_run_changesets_order(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let (repo, cs_ids) = linear_repo(&ctx, 5).await?;
+        let limits = TailerLimits {
+            max_changesets: 1,
+            concurrency: 3,
+        };
+        let tailer = test_tailer(&ctx, repo, RepoConfig::default(), limits).await?;
+
+        // Results come back in the order given, regardless of concurrency, history or the
+        // changeset limit.
+        let order = vec![cs_ids[2], cs_ids[0], cs_ids[4], cs_ids[1], cs_ids[3]];
+        let ran: Vec<_> = tailer
+            .run_changesets(order.clone())
+            .map_ok(|i| i.cs_id)
+            .try_collect()
+            .await?;
+        assert_eq!(ran, order);
+
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_run_for_bookmarks_missing(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let (repo, cs_ids) = linear_repo(&ctx, 3).await?;
+        let tailer = test_tailer(
+            &ctx,
+            repo,
+            always_fail_config("main")?,
+            TailerLimits::default(),
+        )
+        .await?;
+
+        let main = BookmarkName::new("main")?;
+        let missing = BookmarkName::new("missing")?;
+        let results = tailer
+            .run_for_bookmarks(vec![missing.clone(), main.clone()], 0)
+            .await?;
+
+        // The missing bookmark is skipped rather than failing the run.
+        assert!(!results.contains_key(&missing));
+        assert_eq!(results.len(), 1);
+
+        let mut rejected: Vec<_> = results[&main]
+            .iter()
+            .map(|outcome| {
+                assert_eq!(outcome.get_hook_name(), "always_fail_changeset");
+                assert!(outcome.is_rejection());
+                outcome.get_changeset_id()
+            })
+            .collect();
+        rejected.sort();
+        let mut expected = cs_ids;
+        expected.sort();
+        assert_eq!(rejected, expected);
+
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_run_globalrev_ordered(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let (repo, cs_ids) = linear_repo(&ctx, 4).await?;
+        // Globalrevs don't follow history order, and 13 has no changeset.
+        let entries = vec![
+            BonsaiGlobalrevMappingEntry::new(cs_ids[0], Globalrev::new(12)),
+            BonsaiGlobalrevMappingEntry::new(cs_ids[1], Globalrev::new(10)),
+            BonsaiGlobalrevMappingEntry::new(cs_ids[2], Globalrev::new(14)),
+            BonsaiGlobalrevMappingEntry::new(cs_ids[3], Globalrev::new(11)),
+        ];
+        repo.bonsai_globalrev_mapping()
+            .bulk_import(&ctx, &entries)
+            .await?;
+
+        let limits = TailerLimits {
+            max_changesets: 0,
+            concurrency: 3,
+        };
+        let tailer = test_tailer(&ctx, repo, always_fail_config("main")?, limits).await?;
+        let outcomes = tailer
+            .run_globalrev_ordered(Globalrev::new(10), Globalrev::new(15))
+            .await?;
+
+        let ran: Vec<_> = outcomes
+            .iter()
+            .map(|outcome| {
+                assert!(outcome.is_rejection());
+                outcome.get_changeset_id()
+            })
+            .collect();
+        assert_eq!(ran, vec![cs_ids[1], cs_ids[3], cs_ids[0], cs_ids[2]]);
+
+        Ok(())
+    }
+
+    /// A changeset hook that accepts every changeset after sleeping for the given duration.
+    struct SleepyHook(Duration);
+
+    #[async_trait]
+    impl ChangesetHook for SleepyHook {
+        async fn run<'this: 'cs, 'ctx: 'this, 'cs, 'fetcher: 'cs>(
+            &'this self,
+            _ctx: &'ctx CoreContext,
+            _bookmark: &BookmarkName,
+            _changeset: &'cs BonsaiChangeset,
+            _content_manager: &'fetcher dyn FileContentManager,
+            _cross_repo_push_source: CrossRepoPushSource,
+            _push_authored_by: PushAuthoredBy,
+        ) -> Result<HookExecution, Error> {
+            tokio::time::sleep(self.0).await;
+            Ok(HookExecution::Accepted)
+        }
+    }
+
+    #[fbinit::test]
+    async fn test_run_with_timings(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let (repo, cs_ids) = linear_repo(&ctx, 1).await?;
+        let mut tailer =
+            test_tailer(&ctx, repo, RepoConfig::default(), TailerLimits::default()).await?;
+        let hook_manager =
+            Arc::get_mut(&mut tailer.hook_manager).expect("hook manager is not shared yet");
+        hook_manager.register_changeset_hook(
+            "sleepy",
+            Box::new(SleepyHook(Duration::from_millis(20))),
+            HookConfig::default(),
+        );
+        hook_manager.set_hooks_for_bookmark(
+            BookmarkName::new("main")?.into(),
+            vec!["sleepy".to_string()],
+        );
+
+        let timings = tailer.run_with_timings(0).await?;
+        match timings.as_slice() {
+            [(timing, outcomes)] => {
+                assert_eq!(timing.cs_id, cs_ids[0]);
+                assert!(timing.hooks_ms >= 20);
+                assert_eq!(outcomes.len(), 1);
+                assert_eq!(outcomes[0].get_hook_name(), "sleepy");
+                assert!(!outcomes[0].is_rejection());
+            }
+            other => panic!("Unexpected timings: {:?}", other),
+        }
+
+        Ok(())
+    }
+}
@@ -22,7 +22,9 @@ use futures::future;
 use futures::stream::futures_unordered;
 use futures::stream::TryStreamExt;
 use futures::TryFutureExt;
+use hooks::hook_error_to_json;
 use hooks::hook_loader::load_hooks;
+use hooks::hook_outcomes_to_json;
 use hooks::ChangesetHook;
 use hooks::CrossRepoPushSource;
 use hooks::ErrorKind;
@@ -59,6 +61,7 @@ use permission_checker::DefaultAclProvider;
 use regex::Regex;
 use repo_blobstore::RepoBlobstoreRef;
 use scuba_ext::MononokeScubaSampleBuilder;
+use serde_json::json;
 use sorted_vector_map::sorted_vector_map;
 use tests_utils::bookmark;
 use tests_utils::create_commit;
@@ -574,6 +577,67 @@ async fn test_changeset_hook_mix(fb: FacebookInit) {
     run_changeset_hooks(ctx, "bm1", hooks, bookmarks, regexes, expected).await;
 }
 
+#[fbinit::test]
+async fn test_hook_outcomes_json(fb: FacebookInit) {
+    let ctx = CoreContext::test_mock(fb);
+    let bookmarks = hashmap! {
+        "bm1".to_string() => vec!["accept".to_string(), "reject".to_string()]
+    };
+    let mut hook_manager =
+        setup_hook_manager(ctx.fb, bookmarks, hashmap! {}, ContentFetcherType::InMemory).await;
+    hook_manager.register_changeset_hook(
+        "accept",
+        always_accepting_changeset_hook(),
+        Default::default(),
+    );
+    hook_manager.register_changeset_hook(
+        "reject",
+        always_rejecting_changeset_hook(),
+        Default::default(),
+    );
+
+    let changeset = default_changeset();
+    let cs_id = changeset.get_changeset_id().to_string();
+    let mut outcomes = hook_manager
+        .run_hooks_for_bookmark(
+            &ctx,
+            vec![changeset].iter(),
+            &BookmarkName::new("bm1").unwrap(),
+            None,
+            CrossRepoPushSource::NativeToThisRepo,
+            PushAuthoredBy::User,
+        )
+        .await
+        .unwrap();
+    outcomes.sort_by(|a, b| a.get_hook_name().cmp(b.get_hook_name()));
+
+    assert_eq!(
+        hook_outcomes_to_json(&outcomes),
+        json!([
+            {
+                "hook_name": "accept",
+                "cs_id": cs_id,
+                "path": null,
+                "status": "accepted",
+            },
+            {
+                "hook_name": "reject",
+                "cs_id": cs_id,
+                "path": null,
+                "status": "rejected",
+                "description": "desc",
+                "message": "long_desc",
+            },
+        ])
+    );
+
+    let error = hook_error_to_json(None, &Error::msg("hook crashed"));
+    assert_eq!(
+        error,
+        json!({ "cs_id": null, "status": "errored", "message": "hook crashed" })
+    );
+}
+
 #[fbinit::test]
 async fn test_changeset_hook_file_text(fb: FacebookInit) {
     let ctx = CoreContext::test_mock(fb);
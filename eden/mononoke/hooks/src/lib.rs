@@ -56,6 +56,8 @@ use permission_checker::NeverMember;
 use regex::Regex;
 use scuba::builder::ServerData;
 use scuba_ext::MononokeScubaSampleBuilder;
+use serde_json::json;
+use serde_json::Value as JsonValue;
 use slog::debug;
 
 /// Manages hooks and allows them to be installed and uninstalled given a name
@@ -441,7 +443,7 @@ impl<'a> HookInstance<'a> {
             .add("failed_hooks", failed_hooks)
             .log();
 
-        result.map_err(|e| e.context(format!("while executing hook {}", hook_name)))
+        result.map_err(|e| e.context(ErrorKind::HookRuntimeError(hook_name.to_string())))
     }
 }
 
@@ -595,6 +597,28 @@ impl HookOutcome {
         }
     }
 
+    /// Render this outcome as JSON, for consumption by tools. The `status` field is either
+    /// `"accepted"` or `"rejected"`; rejections also carry the rejection's `description` and
+    /// `message`.
+    pub fn to_json(&self) -> JsonValue {
+        let mut value = json!({
+            "hook_name": self.get_hook_name(),
+            "cs_id": self.get_changeset_id().to_string(),
+            "path": self.get_file_path().map(|path| path.to_string()),
+        });
+        match self.get_execution() {
+            HookExecution::Accepted => {
+                value["status"] = json!("accepted");
+            }
+            HookExecution::Rejected(info) => {
+                value["status"] = json!("rejected");
+                value["description"] = json!(info.description);
+                value["message"] = json!(info.long_description);
+            }
+        }
+        value
+    }
+
     pub fn into_rejection(self) -> Option<HookRejection> {
         match self {
             HookOutcome::ChangesetHook(_, HookExecution::Accepted)
@@ -619,6 +643,21 @@ impl HookOutcome {
     }
 }
 
+/// Render a list of hook outcomes as a JSON array. See `HookOutcome::to_json`.
+pub fn hook_outcomes_to_json(outcomes: &[HookOutcome]) -> JsonValue {
+    JsonValue::Array(outcomes.iter().map(HookOutcome::to_json).collect())
+}
+
+/// Render a failure to run hooks as JSON, in the same shape as `HookOutcome::to_json`, with a
+/// `status` of `"errored"`.
+pub fn hook_error_to_json(cs_id: Option<ChangesetId>, error: &Error) -> JsonValue {
+    json!({
+        "cs_id": cs_id.map(|cs_id| cs_id.to_string()),
+        "status": "errored",
+        "message": format!("{:#}", error),
+    })
+}
+
 /// Instance of a hook rejecting a changeset.
 #[derive(Clone, Debug, PartialEq)]
 pub struct HookRejection {
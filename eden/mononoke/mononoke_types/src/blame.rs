@@ -459,6 +459,26 @@ impl Blame {
         Blame::new(ranges)
     }
 
+    /// Merge consecutive ranges that come from the same changeset and path, and whose lines
+    /// are also consecutive in the origin file, into a single range. Every line keeps the same
+    /// attribution, so this only reduces the number of ranges.
+    pub fn coalesce_ranges(&self) -> Blame {
+        let mut ranges: Vec<BlameRange> = Vec::with_capacity(self.ranges.len());
+        for range in self.ranges.iter() {
+            match ranges.last_mut() {
+                Some(last)
+                    if last.csid == range.csid
+                        && last.path == range.path
+                        && last.origin_offset + last.length == range.origin_offset =>
+                {
+                    last.length += range.length;
+                }
+                _ => ranges.push(range.clone()),
+            }
+        }
+        Blame { ranges }
+    }
+
     pub fn lines<'a>(&'a self) -> BlameLines<'a> {
         BlameLines::new(&self.ranges)
     }
@@ -680,6 +700,49 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_coalesce_ranges() -> Result<(), Error> {
+        let p0 = MPath::new("path/zero")?;
+        let p1 = MPath::new("path/one")?;
+        let range = |offset, length, csid, path: &MPath, origin_offset| BlameRange {
+            offset,
+            length,
+            csid,
+            path: path.clone(),
+            origin_offset,
+        };
+
+        // Adjacent ranges from the same origin are merged.
+        let blame = Blame::new(vec![
+            range(0, 2, ONES_CSID, &p0, 3),
+            range(2, 3, ONES_CSID, &p0, 5),
+            range(5, 1, ONES_CSID, &p0, 8),
+            range(6, 2, TWOS_CSID, &p1, 0),
+        ])?;
+        let coalesced = blame.coalesce_ranges();
+        assert_eq!(
+            coalesced.ranges(),
+            &vec![
+                range(0, 6, ONES_CSID, &p0, 3),
+                range(6, 2, TWOS_CSID, &p1, 0)
+            ]
+        );
+        assert!(coalesced.lines().eq(blame.lines()));
+
+        // Interleaved origins, and ranges from the same changeset but a different path or a
+        // different part of the origin file, are left alone.
+        let blame = Blame::new(vec![
+            range(0, 2, ONES_CSID, &p0, 0),
+            range(2, 1, TWOS_CSID, &p0, 2),
+            range(3, 1, ONES_CSID, &p0, 2),
+            range(4, 1, ONES_CSID, &p1, 3),
+            range(5, 1, ONES_CSID, &p1, 7),
+        ])?;
+        assert_eq!(blame.coalesce_ranges(), blame);
+
+        Ok(())
+    }
+
     #[test]
     fn test_blame_merge_lines() -> Result<(), Error> {
         // Merging blame generated for to parents of changeset 1.
@@ -200,6 +200,16 @@ impl BlameV2 {
         }
     }
 
+    /// Returns the changeset the given (0-based) line is attributed to, or
+    /// `None` if the line is beyond the end of the file or the blame was
+    /// rejected.
+    pub fn changeset_for_line(&self, line: u32) -> Option<ChangesetId> {
+        match self {
+            BlameV2::Blame(blame_data) => blame_data.changeset_for_line(line),
+            BlameV2::Rejected(_) => None,
+        }
+    }
+
     pub fn annotate(&self, content: &str) -> Result<String> {
         match self {
             BlameV2::Blame(blame_data) => blame_data.annotate(content),
@@ -305,6 +315,20 @@ pub struct BlameData {
 }
 
 impl BlameData {
+    /// Find the changeset for a line by binary searching the ranges, which
+    /// are contiguous and ordered by offset.
+    fn changeset_for_line(&self, line: u32) -> Option<ChangesetId> {
+        let index = self
+            .ranges
+            .partition_point(|range| range.offset + range.length <= line);
+        let range = self.ranges.get(index)?;
+        if range.offset <= line {
+            self.csids.get(range.csid_index as usize).copied()
+        } else {
+            None
+        }
+    }
+
     /// Create a new BlameData for a brand new file.
     fn new_root<C: AsRef<[u8]>>(csid: ChangesetId, path: MPath, content: C) -> Self {
         let mut ranges = Vec::new();
@@ -1411,6 +1435,67 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_changeset_for_line() -> Result<()> {
+        let mut csids = VecMap::new();
+        csids.insert(0, ONES_CSID);
+        csids.insert(1, TWOS_CSID);
+        csids.insert(3, THREES_CSID);
+
+        let blame = BlameV2::Blame(BlameData {
+            ranges: vec![
+                BlameRangeIndexes {
+                    offset: 0,
+                    length: 2,
+                    csid_index: 1,
+                    path_index: 0,
+                    origin_offset: 0,
+                    parent: None,
+                },
+                BlameRangeIndexes {
+                    offset: 2,
+                    length: 1,
+                    csid_index: 3,
+                    path_index: 0,
+                    origin_offset: 4,
+                    parent: None,
+                },
+                BlameRangeIndexes {
+                    offset: 3,
+                    length: 3,
+                    csid_index: 0,
+                    path_index: 0,
+                    origin_offset: 1,
+                    parent: None,
+                },
+            ],
+            csids,
+            max_csid_index: 3,
+            paths: vec![MPath::new("file")?],
+        });
+
+        assert_eq!(blame.changeset_for_line(0), Some(TWOS_CSID));
+        assert_eq!(blame.changeset_for_line(1), Some(TWOS_CSID));
+        assert_eq!(blame.changeset_for_line(2), Some(THREES_CSID));
+        assert_eq!(blame.changeset_for_line(3), Some(ONES_CSID));
+        assert_eq!(blame.changeset_for_line(5), Some(ONES_CSID));
+        assert_eq!(blame.changeset_for_line(6), None);
+        assert_eq!(blame.changeset_for_line(u32::MAX), None);
+
+        // Agrees with the line iterator for every line.
+        for (index, line) in blame.lines()?.enumerate() {
+            assert_eq!(
+                blame.changeset_for_line(index as u32),
+                Some(*line.changeset_id)
+            );
+        }
+
+        let rejected = BlameV2::rejected(BlameRejected::TooBig);
+        assert_eq!(rejected.changeset_for_line(0), None);
+
+        Ok(())
+    }
+
     #[test]
     fn test_linear() -> Result<()> {
         let path1 = MPath::new("path")?;
@@ -5,7 +5,9 @@
  * GNU General Public License version 2.
  */
 
+use std::borrow::Cow;
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 
 use anyhow::bail;
 use anyhow::Context;
@@ -14,12 +16,17 @@ use anyhow::Result;
 use fbthrift::compact_protocol;
 use quickcheck::Arbitrary;
 use quickcheck::Gen;
+#[cfg(feature = "serde")]
+use serde_derive::Deserialize;
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
 use sorted_vector_map::SortedVectorMap;
 
 use crate::blob::Blob;
 use crate::blob::BlobstoreValue;
 use crate::blob::ChangesetBlob;
 use crate::datetime::DateTime;
+use crate::errors::CopySourceError;
 use crate::errors::ErrorKind;
 use crate::file_change::BasicFileChange;
 use crate::file_change::FileChange;
@@ -29,8 +36,27 @@ use crate::thrift;
 use crate::typed_hash::ChangesetId;
 use crate::typed_hash::ChangesetIdContext;
 
+/// How a path's file change differs between two changesets, as reported by
+/// `BonsaiChangeset::file_change_diff`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum FileChangeDiff {
+    /// The path is added or modified in `self`, but absent or deleted in `other`.
+    AddedOnlyInSelf,
+    /// The path is added or modified in `other`, but absent or deleted in `self`.
+    AddedOnlyInOther,
+    /// Both changesets change the path with the same file type but different content.
+    ChangedContent,
+    /// Both changesets change the path, with different file types.
+    ChangedType,
+}
+
 /// A struct callers can use to build up a `BonsaiChangeset`.
+///
+/// With the `serde` feature this can also be serialized (e.g. to JSON), in which case hashes are
+/// written as hex strings and the maps as lists of pairs, as paths are not valid keys in most
+/// formats.
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BonsaiChangesetMut {
     pub parents: Vec<ChangesetId>,
     pub author: String,
@@ -41,11 +67,41 @@ pub struct BonsaiChangesetMut {
     // max(author date, max(committer date of parents) + epsilon)
     pub committer_date: Option<DateTime>,
     pub message: String,
+    #[cfg_attr(feature = "serde", serde(with = "sorted_map_as_pairs"))]
     pub extra: SortedVectorMap<String, Vec<u8>>,
+    #[cfg_attr(feature = "serde", serde(with = "sorted_map_as_pairs"))]
     pub file_changes: SortedVectorMap<MPath, FileChange>,
     pub is_snapshot: bool,
 }
 
+#[cfg(feature = "serde")]
+mod sorted_map_as_pairs {
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serialize;
+    use serde::Serializer;
+    use sorted_vector_map::SortedVectorMap;
+
+    pub fn serialize<K, V, S>(map: &SortedVectorMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize + Ord,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map.iter())
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<SortedVectorMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Ord,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let pairs = Vec::<(K, V)>::deserialize(deserializer)?;
+        Ok(pairs.into_iter().collect())
+    }
+}
+
 impl BonsaiChangesetMut {
     /// Create from a thrift `BonsaiChangeset`.
     fn from_thrift(tc: thrift::BonsaiChangeset) -> Result<Self> {
@@ -123,16 +179,8 @@ impl BonsaiChangesetMut {
     /// doesn't exist in its parent is invalid. Instead, it only checks for internal consistency.
     pub fn verify(&self) -> Result<()> {
         // Check that the copy info ID refers to a parent in the parent set.
-        for (path, fc) in &self.file_changes {
-            if let Some(&(ref copy_from_path, ref copy_from_id)) = fc.copy_from() {
-                if !self.parents.contains(copy_from_id) {
-                    bail!(ErrorKind::InvalidBonsaiChangeset(format!(
-                        "copy information for path '{}' (from '{}') has parent {} which isn't \
-                             recognized",
-                        path, copy_from_path, copy_from_id
-                    )));
-                }
-            }
+        if let Err(e) = self.validate_copy_sources() {
+            bail!(ErrorKind::InvalidBonsaiChangeset(e.to_string()));
         }
 
         // Check that the list of file changes doesn't have any path conflicts.
@@ -159,6 +207,37 @@ impl BonsaiChangesetMut {
 
         Ok(())
     }
+
+    /// Check that every file change copied from another changeset is copied from one of this
+    /// changeset's parents. Reports the first file change that isn't.
+    pub fn validate_copy_sources(&self) -> Result<(), CopySourceError> {
+        for (path, fc) in &self.file_changes {
+            if let Some((copy_from_path, copy_from_cs)) = fc.copy_from() {
+                if !self.parents.contains(copy_from_cs) {
+                    return Err(CopySourceError {
+                        path: path.clone(),
+                        copy_from_path: copy_from_path.clone(),
+                        copy_from_cs: *copy_from_cs,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sort the parents by changeset id, so that changesets imported from sources that
+    /// list parents in different orders end up with the same hash.
+    ///
+    /// This discards the (p1, p2) ordering that Mercurial relies on, so it must not be
+    /// used on changesets that need to stay Mercurial-compatible.
+    pub fn normalize_parents(&mut self) {
+        self.parents.sort();
+    }
+
+    /// Whether the parents are already in the order `normalize_parents` would put them.
+    pub fn is_parents_normalized(&self) -> bool {
+        self.parents.windows(2).all(|pair| pair[0] <= pair[1])
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
@@ -233,6 +312,16 @@ impl BonsaiChangeset {
         &self.inner.file_changes
     }
 
+    /// The total size of the file contents added or modified by this changeset. Deletions carry
+    /// no contents, so they don't contribute.
+    pub fn added_content_size(&self) -> u64 {
+        self.inner
+            .file_changes
+            .values()
+            .filter_map(FileChange::size)
+            .sum()
+    }
+
     /// Get the author for this changeset.
     pub fn author(&self) -> &str {
         &self.inner.author
@@ -269,6 +358,13 @@ impl BonsaiChangeset {
             .map(|(k, v)| (k.as_str(), v.as_slice()))
     }
 
+    /// Get the extra field `key` as a string. Returns `None` if the field is absent or isn't
+    /// valid UTF-8.
+    pub fn extra_as_str(&self, key: &str) -> Option<Cow<'_, str>> {
+        let value = self.inner.extra.get(key)?;
+        std::str::from_utf8(value).ok().map(Cow::Borrowed)
+    }
+
     pub fn get_changeset_id(&self) -> ChangesetId {
         self.id
     }
@@ -278,6 +374,39 @@ impl BonsaiChangeset {
         self.inner.is_snapshot
     }
 
+    /// Compare the file changes of this changeset against `other`, returning the paths that
+    /// differ sorted by path. Tracked and untracked changes are treated the same way, and a
+    /// deletion is treated the same as the path not being mentioned at all. If both the content
+    /// and the file type differ, the path is reported as `ChangedType`.
+    pub fn file_change_diff(&self, other: &BonsaiChangeset) -> Vec<(MPath, FileChangeDiff)> {
+        let self_changes = self.file_changes_map();
+        let other_changes = other.file_changes_map();
+        let paths: BTreeSet<&MPath> = self_changes.keys().chain(other_changes.keys()).collect();
+
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                let ours = self_changes.get(path).and_then(FileChange::simplify);
+                let theirs = other_changes.get(path).and_then(FileChange::simplify);
+                let diff = match (ours, theirs) {
+                    (Some(ours), Some(theirs)) => {
+                        if ours.file_type() != theirs.file_type() {
+                            FileChangeDiff::ChangedType
+                        } else if ours.content_id() != theirs.content_id() {
+                            FileChangeDiff::ChangedContent
+                        } else {
+                            return None;
+                        }
+                    }
+                    (Some(_), None) => FileChangeDiff::AddedOnlyInSelf,
+                    (None, Some(_)) => FileChangeDiff::AddedOnlyInOther,
+                    (None, None) => return None,
+                };
+                Some((path.clone(), diff))
+            })
+            .collect()
+    }
+
     /// Allow mutating this instance of `BonsaiChangeset`.
     pub fn into_mut(self) -> BonsaiChangesetMut {
         self.inner
@@ -450,6 +579,117 @@ mod test {
         );
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_roundtrip() {
+        let cs = BonsaiChangesetMut {
+            parents: vec![
+                ChangesetId::from_byte_array([3; 32]),
+                ChangesetId::from_byte_array([1; 32]),
+                ChangesetId::from_byte_array([2; 32]),
+            ],
+            author: "foo".into(),
+            author_date: DateTime::from_timestamp(1234567890, 36800).unwrap(),
+            committer: Some("bar".into()),
+            committer_date: Some(DateTime::from_timestamp(1500000000, -36800).unwrap()),
+            message: "Commit message\n\nwith a body".into(),
+            extra: sorted_vector_map![
+                "convert_revision".to_string() => b"svn:uuid/path@1234".to_vec(),
+                "binary".to_string() => vec![0, 159, 146, 150],
+            ],
+            file_changes: sorted_vector_map![
+                MPath::new("a/b").unwrap() => FileChange::tracked(
+                    ContentId::from_byte_array([1; 32]),
+                    FileType::Regular,
+                    42,
+                    None,
+                ),
+                MPath::new("c/d").unwrap() => FileChange::tracked(
+                    ContentId::from_byte_array([2; 32]),
+                    FileType::Symlink,
+                    84,
+                    Some((
+                        MPath::new("e/f").unwrap(),
+                        ChangesetId::from_byte_array([3; 32]),
+                    )),
+                ),
+                MPath::new("g/h").unwrap() => FileChange::Deletion,
+            ],
+            is_snapshot: false,
+        };
+
+        let json = serde_json::to_string(&cs).unwrap();
+        assert!(json.contains(&ChangesetId::from_byte_array([3; 32]).to_string()));
+        assert!(json.contains(&ContentId::from_byte_array([2; 32]).to_string()));
+
+        let cs2: BonsaiChangesetMut = serde_json::from_str(&json).unwrap();
+        assert_eq!(cs, cs2);
+        assert_eq!(
+            cs.freeze().unwrap().get_changeset_id(),
+            cs2.freeze().unwrap().get_changeset_id()
+        );
+    }
+
+    #[test]
+    fn file_change_diff() {
+        fn create(file_changes: SortedVectorMap<MPath, FileChange>) -> BonsaiChangeset {
+            BonsaiChangesetMut {
+                parents: vec![],
+                author: "foo".into(),
+                author_date: DateTime::from_timestamp(1, 2).unwrap(),
+                committer: None,
+                committer_date: None,
+                message: "a".into(),
+                extra: SortedVectorMap::new(),
+                file_changes,
+                is_snapshot: false,
+            }
+            .freeze()
+            .unwrap()
+        }
+
+        fn change(id: u8, file_type: FileType) -> FileChange {
+            FileChange::tracked(ContentId::from_byte_array([id; 32]), file_type, 42, None)
+        }
+
+        let path = |p| MPath::new(p).unwrap();
+
+        let base = create(sorted_vector_map![
+            path("added") => change(1, FileType::Regular),
+            path("edited") => change(2, FileType::Regular),
+            path("removed") => FileChange::Deletion,
+            path("retyped") => change(3, FileType::Regular),
+            path("same") => change(4, FileType::Regular),
+        ]);
+        assert_eq!(base.file_change_diff(&base), vec![]);
+
+        let other = create(sorted_vector_map![
+            path("edited") => change(5, FileType::Regular),
+            path("removed") => change(6, FileType::Regular),
+            path("retyped") => change(3, FileType::Executable),
+            path("same") => change(4, FileType::Regular),
+            path("untouched") => FileChange::Deletion,
+        ]);
+        assert_eq!(
+            base.file_change_diff(&other),
+            vec![
+                (path("added"), FileChangeDiff::AddedOnlyInSelf),
+                (path("edited"), FileChangeDiff::ChangedContent),
+                (path("removed"), FileChangeDiff::AddedOnlyInOther),
+                (path("retyped"), FileChangeDiff::ChangedType),
+            ]
+        );
+        assert_eq!(
+            other.file_change_diff(&base),
+            vec![
+                (path("added"), FileChangeDiff::AddedOnlyInOther),
+                (path("edited"), FileChangeDiff::ChangedContent),
+                (path("removed"), FileChangeDiff::AddedOnlyInSelf),
+                (path("retyped"), FileChangeDiff::ChangedType),
+            ]
+        );
+    }
+
     #[test]
     fn bonsai_snapshots() {
         fn create(untracked: bool, missing: bool, is_snapshot: bool) -> Result<BonsaiChangeset> {
@@ -495,4 +735,150 @@ mod test {
         create(false, true, false).expect_err("Non-snapshot can't have missing");
         create(true, true, false).unwrap_err();
     }
+
+    #[test]
+    fn added_content_size() {
+        let cs = BonsaiChangesetMut {
+            parents: vec![],
+            author: "foo".into(),
+            author_date: DateTime::from_timestamp(1, 2).unwrap(),
+            committer: None,
+            committer_date: None,
+            message: "a".into(),
+            extra: SortedVectorMap::new(),
+            file_changes: sorted_vector_map![
+                MPath::new("added").unwrap() => FileChange::tracked(
+                    ContentId::from_byte_array([1; 32]),
+                    FileType::Regular,
+                    42,
+                    None,
+                ),
+                MPath::new("deleted").unwrap() => FileChange::Deletion,
+                MPath::new("modified").unwrap() => FileChange::tracked(
+                    ContentId::from_byte_array([2; 32]),
+                    FileType::Executable,
+                    100,
+                    None,
+                ),
+            ],
+            is_snapshot: false,
+        }
+        .freeze()
+        .unwrap();
+        assert_eq!(cs.added_content_size(), 142);
+    }
+
+    #[test]
+    fn validate_copy_sources() {
+        let p1 = ChangesetId::from_byte_array([1; 32]);
+        let p2 = ChangesetId::from_byte_array([2; 32]);
+        let other = ChangesetId::from_byte_array([3; 32]);
+        let create = |copy_from_cs| BonsaiChangesetMut {
+            parents: vec![p1, p2],
+            author: "foo".into(),
+            author_date: DateTime::from_timestamp(1, 2).unwrap(),
+            committer: None,
+            committer_date: None,
+            message: "a".into(),
+            extra: SortedVectorMap::new(),
+            file_changes: sorted_vector_map![
+                MPath::new("copied").unwrap() => FileChange::tracked(
+                    ContentId::from_byte_array([4; 32]),
+                    FileType::Regular,
+                    42,
+                    Some((MPath::new("original").unwrap(), copy_from_cs)),
+                ),
+                MPath::new("plain").unwrap() => FileChange::Deletion,
+            ],
+            is_snapshot: false,
+        };
+
+        assert_eq!(create(p1).validate_copy_sources(), Ok(()));
+        assert_eq!(create(p2).validate_copy_sources(), Ok(()));
+
+        let err = create(other).validate_copy_sources().unwrap_err();
+        assert_eq!(
+            err,
+            CopySourceError {
+                path: MPath::new("copied").unwrap(),
+                copy_from_path: MPath::new("original").unwrap(),
+                copy_from_cs: other,
+            }
+        );
+        assert!(err.to_string().contains("'copied'"));
+        create(other)
+            .freeze()
+            .expect_err("copy from a non-parent is invalid");
+    }
+
+    #[test]
+    fn normalize_parents() {
+        let create = |parents| BonsaiChangesetMut {
+            parents,
+            author: "foo".into(),
+            author_date: DateTime::from_timestamp(1, 2).unwrap(),
+            committer: None,
+            committer_date: None,
+            message: "a".into(),
+            extra: SortedVectorMap::new(),
+            file_changes: SortedVectorMap::new(),
+            is_snapshot: false,
+        };
+        let low = ChangesetId::from_byte_array([1; 32]);
+        let high = ChangesetId::from_byte_array([3; 32]);
+
+        let mut forward = create(vec![low, high]);
+        let mut reversed = create(vec![high, low]);
+        assert!(forward.is_parents_normalized());
+        assert!(!reversed.is_parents_normalized());
+        assert_ne!(
+            forward.clone().freeze().unwrap().get_changeset_id(),
+            reversed.clone().freeze().unwrap().get_changeset_id()
+        );
+
+        reversed.normalize_parents();
+        assert!(reversed.is_parents_normalized());
+        assert_eq!(reversed.parents, vec![low, high]);
+        assert_eq!(reversed, forward);
+
+        // Normalizing is idempotent.
+        forward.normalize_parents();
+        assert_eq!(forward, reversed);
+        assert_eq!(
+            forward.freeze().unwrap().get_changeset_id(),
+            reversed.freeze().unwrap().get_changeset_id()
+        );
+
+        let mut single = create(vec![high]);
+        assert!(single.is_parents_normalized());
+        single.normalize_parents();
+        assert_eq!(single.parents, vec![high]);
+        assert!(create(vec![]).is_parents_normalized());
+    }
+
+    #[test]
+    fn extra_as_str() {
+        let cs = BonsaiChangesetMut {
+            parents: vec![],
+            author: "foo".into(),
+            author_date: DateTime::from_timestamp(1, 2).unwrap(),
+            committer: None,
+            committer_date: None,
+            message: "a".into(),
+            extra: sorted_vector_map![
+                "convert_revision".to_string() => b"abcdef".to_vec(),
+                "empty".to_string() => vec![],
+                "malformed".to_string() => vec![0xff, 0xfe],
+            ],
+            file_changes: SortedVectorMap::new(),
+            is_snapshot: false,
+        }
+        .freeze()
+        .unwrap();
+
+        assert_eq!(cs.extra_as_str("convert_revision").unwrap(), "abcdef");
+        assert_eq!(cs.extra_as_str("empty").unwrap(), "");
+        assert_eq!(cs.extra_as_str("malformed"), None);
+        assert_eq!(cs.extra_as_str("absent"), None);
+    }
 }
@@ -63,18 +63,26 @@ impl ContentChunk {
     pub fn into_bytes(self) -> Bytes {
         self.0
     }
+
+    /// The id this chunk is stored under, which depends only on its bytes.
+    pub fn chunk_id(&self) -> ContentChunkId {
+        compute_chunk_id(&self.0)
+    }
+}
+
+/// The id that a chunk holding `bytes` is stored under. Chunks with identical bytes have the same
+/// id, so this can be used to check whether a chunk is already stored before uploading it.
+pub fn compute_chunk_id(bytes: &[u8]) -> ContentChunkId {
+    let mut context = ContentChunkIdContext::new();
+    context.update(bytes);
+    context.finish()
 }
 
 impl BlobstoreValue for ContentChunk {
     type Key = ContentChunkId;
 
     fn into_blob(self) -> ContentChunkBlob {
-        let id = {
-            let mut context = ContentChunkIdContext::new();
-            context.update(&self.0);
-            context.finish()
-        };
-
+        let id = self.chunk_id();
         let data = compact_protocol::serialize(&self.into_thrift());
 
         Blob::new(id, data)
@@ -143,6 +151,21 @@ mod test {
                 .expect("blob roundtrips should always be valid");
              blob_id == pointer.chunk_id() && chunk.size() == pointer.size()
         }
+
+        fn chunk_id_matches_blob(chunk: ContentChunk) -> bool {
+            chunk.chunk_id() == *chunk.clone().into_blob().id()
+        }
+    }
+
+    #[test]
+    fn chunk_id_depends_on_bytes() {
+        let a = ContentChunk::new_bytes(&b"some bytes"[..]);
+        let b = ContentChunk::new_bytes(b"some bytes".to_vec());
+        let c = ContentChunk::new_bytes(&b"other bytes"[..]);
+
+        assert_eq!(a.chunk_id(), b.chunk_id());
+        assert_ne!(a.chunk_id(), c.chunk_id());
+        assert_eq!(compute_chunk_id(b"some bytes"), a.chunk_id());
     }
 
     #[test]
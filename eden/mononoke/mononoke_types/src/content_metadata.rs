@@ -5,19 +5,29 @@
  * GNU General Public License version 2.
  */
 
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
 use anyhow::bail;
 use anyhow::Context;
 use anyhow::Result;
+use blobstore::Blobstore;
 use blobstore::BlobstoreBytes;
 use bytes::Bytes;
+use context::CoreContext;
 use fbthrift::compact_protocol;
 use quickcheck::Arbitrary;
 use quickcheck::Gen;
+use sha1::Sha1;
+use sha2::Digest;
+use sha2::Sha256;
 
 use crate::blob::Blob;
 use crate::blob::BlobstoreValue;
 use crate::blob::ContentMetadataBlob;
 use crate::errors::ErrorKind;
+use crate::file_contents::FileContents;
 use crate::hash;
 use crate::thrift;
 use crate::thrift_field;
@@ -61,6 +71,76 @@ impl ContentAlias {
     }
 }
 
+/// An in-memory index of aliases to the content they refer to, which can also
+/// be queried in reverse for all the aliases of a piece of content.  The alias
+/// type is generic so that callers can use whichever alias representation
+/// they already have (e.g. the filestore's `Alias`).
+#[derive(Debug, Clone)]
+pub struct ContentAliasIndex<A> {
+    by_alias: HashMap<A, ContentId>,
+    by_content_id: HashMap<ContentId, Vec<A>>,
+}
+
+impl<A> Default for ContentAliasIndex<A> {
+    fn default() -> Self {
+        Self {
+            by_alias: HashMap::new(),
+            by_content_id: HashMap::new(),
+        }
+    }
+}
+
+impl<A: Clone + Eq + Hash> ContentAliasIndex<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `alias` refers to `content_id`.  If the alias previously
+    /// referred to different content, it is moved, and the previous content
+    /// id is returned.
+    pub fn insert(&mut self, alias: A, content_id: ContentId) -> Option<ContentId> {
+        let previous = self.by_alias.insert(alias.clone(), content_id);
+        match previous {
+            Some(previous) if previous == content_id => return Some(previous),
+            Some(previous) => {
+                if let Some(aliases) = self.by_content_id.get_mut(&previous) {
+                    aliases.retain(|a| a != &alias);
+                    if aliases.is_empty() {
+                        self.by_content_id.remove(&previous);
+                    }
+                }
+            }
+            None => {}
+        }
+        self.by_content_id
+            .entry(content_id)
+            .or_default()
+            .push(alias);
+        previous
+    }
+
+    /// The content that `alias` refers to, if known.
+    pub fn by_alias(&self, alias: &A) -> Option<ContentId> {
+        self.by_alias.get(alias).copied()
+    }
+
+    /// All aliases known for `content_id`, in the order they were inserted.
+    pub fn aliases_for(&self, content_id: &ContentId) -> Vec<A> {
+        self.by_content_id
+            .get(content_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_alias.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_alias.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct ContentMetadata {
     pub total_size: u64,
@@ -71,6 +151,28 @@ pub struct ContentMetadata {
 }
 
 impl ContentMetadata {
+    /// Compute the metadata for `contents`, fetching its chunks from `blobstore` one at a time
+    /// if it is chunked. The result is the same however the contents are stored.
+    pub async fn compute<'a>(
+        ctx: &'a CoreContext,
+        blobstore: &'a impl Blobstore,
+        content_id: ContentId,
+        contents: &'a FileContents,
+    ) -> Result<Self> {
+        let size = contents.size();
+        let mut computer = AliasComputer::new(size);
+        match contents {
+            FileContents::Bytes(bytes) => computer.update(bytes),
+            FileContents::Chunked(chunked) => {
+                for pointer in chunked.iter_chunks() {
+                    let chunk = pointer.verify(ctx, blobstore).await?;
+                    computer.update(&chunk.into_bytes());
+                }
+            }
+        }
+        computer.finalize(content_id, size)
+    }
+
     pub fn from_thrift(cab: thrift::ContentMetadata) -> Result<Self> {
         let total_size = thrift_field!(ContentMetadata, cab, total_size)?;
         let total_size: u64 = total_size.try_into()?;
@@ -90,6 +192,36 @@ impl ContentMetadata {
         Ok(res)
     }
 
+    /// A compact single-line description of the size and aliases, suitable for log messages.
+    pub fn alias_summary(&self) -> String {
+        format!(
+            "size={} content_id={} sha1={} sha256={} git_sha1={}",
+            self.total_size, self.content_id, self.sha1, self.sha256, self.git_sha1
+        )
+    }
+
+    /// Whether `other` describes the same content: the same content id, size and aliases.
+    pub fn aliases_match(&self, other: &ContentMetadata) -> bool {
+        self.first_mismatch(other).is_none()
+    }
+
+    /// The name of the first field that differs between `self` and `other`, if any.
+    pub fn first_mismatch(&self, other: &ContentMetadata) -> Option<&'static str> {
+        if self.content_id != other.content_id {
+            Some("content_id")
+        } else if self.total_size != other.total_size {
+            Some("total_size")
+        } else if self.sha1 != other.sha1 {
+            Some("sha1")
+        } else if self.sha256 != other.sha256 {
+            Some("sha256")
+        } else if self.git_sha1 != other.git_sha1 {
+            Some("git_sha1")
+        } else {
+            None
+        }
+    }
+
     fn into_thrift(self) -> thrift::ContentMetadata {
         thrift::ContentMetadata {
             total_size: Some(self.total_size as i64),
@@ -101,6 +233,73 @@ impl ContentMetadata {
     }
 }
 
+/// Computes the aliases of some content incrementally, as its chunks are
+/// uploaded, so that the content doesn't need to be read again afterwards.
+/// The total size must be known up front, as it is part of the git SHA-1.
+pub struct AliasComputer {
+    size: u64,
+    seen: u64,
+    sha1: Sha1,
+    sha256: Sha256,
+    git_sha1: Sha1,
+}
+
+impl AliasComputer {
+    pub fn new(size: u64) -> Self {
+        let mut git_sha1 = Sha1::new();
+        git_sha1.update(hash::RichGitSha1::from_byte_array([0; 20], "blob", size).prefix());
+        Self {
+            size,
+            seen: 0,
+            sha1: Sha1::new(),
+            sha256: Sha256::new(),
+            git_sha1,
+        }
+    }
+
+    /// Feed the next chunk of the content.
+    pub fn update(&mut self, data: &[u8]) {
+        self.seen += data.len() as u64;
+        self.sha1.update(data);
+        self.sha256.update(data);
+        self.git_sha1.update(data);
+    }
+
+    /// Produce the metadata for the content.  Fails if `size` doesn't match
+    /// both the size given to `new` and the amount of data fed in.
+    pub fn finalize(self, content_id: ContentId, size: u64) -> Result<ContentMetadata> {
+        for actual in [self.size, self.seen] {
+            if actual != size {
+                bail!(ErrorKind::ContentSizeMismatch {
+                    expected: size,
+                    actual,
+                });
+            }
+        }
+        Ok(ContentMetadata {
+            total_size: size,
+            content_id,
+            sha1: hash::Sha1::from_byte_array(self.sha1.finalize().into()),
+            sha256: hash::Sha256::from_byte_array(self.sha256.finalize().into()),
+            git_sha1: hash::RichGitSha1::from_byte_array(
+                self.git_sha1.finalize().into(),
+                "blob",
+                size,
+            ),
+        })
+    }
+}
+
+impl fmt::Display for ContentMetadata {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(fmt, "size: {}", self.total_size)?;
+        writeln!(fmt, "content_id: {}", self.content_id)?;
+        writeln!(fmt, "sha1: {}", self.sha1)?;
+        writeln!(fmt, "sha256: {}", self.sha256)?;
+        write!(fmt, "git_sha1: {}", self.git_sha1)
+    }
+}
+
 impl Arbitrary for ContentMetadata {
     fn arbitrary(g: &mut Gen) -> Self {
         // Large u64 values can't be represented in thrift
@@ -135,9 +334,186 @@ impl BlobstoreValue for ContentMetadata {
 
 #[cfg(test)]
 mod test {
+    use blobstore::Storable;
+    use borrowed::borrowed;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
     use quickcheck::quickcheck;
 
     use super::*;
+    use crate::content_chunk::new_blob_and_pointer;
+    use crate::file_contents::ChunkedFileContents;
+    use crate::hash::Blake2;
+
+    fn known_metadata() -> ContentMetadata {
+        ContentMetadata {
+            total_size: 42,
+            content_id: ContentId::new(Blake2::from_byte_array([1; 32])),
+            sha1: hash::Sha1::from_byte_array([2; 20]),
+            sha256: hash::Sha256::from_byte_array([3; 32]),
+            git_sha1: hash::RichGitSha1::from_byte_array([4; 20], "blob", 42),
+        }
+    }
+
+    #[test]
+    fn content_metadata_display() {
+        assert_eq!(
+            known_metadata().to_string(),
+            "size: 42\n\
+             content_id: 0101010101010101010101010101010101010101010101010101010101010101\n\
+             sha1: 0202020202020202020202020202020202020202\n\
+             sha256: 0303030303030303030303030303030303030303030303030303030303030303\n\
+             git_sha1: 0404040404040404040404040404040404040404"
+        );
+    }
+
+    #[test]
+    fn content_metadata_alias_summary() {
+        assert_eq!(
+            known_metadata().alias_summary(),
+            "size=42 \
+             content_id=0101010101010101010101010101010101010101010101010101010101010101 \
+             sha1=0202020202020202020202020202020202020202 \
+             sha256=0303030303030303030303030303030303030303030303030303030303030303 \
+             git_sha1=0404040404040404040404040404040404040404"
+        );
+    }
+
+    #[test]
+    fn content_metadata_first_mismatch() {
+        let metadata = known_metadata();
+        assert!(metadata.aliases_match(&known_metadata()));
+        assert_eq!(metadata.first_mismatch(&known_metadata()), None);
+
+        let other = ContentMetadata {
+            total_size: 43,
+            ..known_metadata()
+        };
+        assert!(!metadata.aliases_match(&other));
+        assert_eq!(metadata.first_mismatch(&other), Some("total_size"));
+
+        let other = ContentMetadata {
+            sha256: hash::Sha256::from_byte_array([9; 32]),
+            ..known_metadata()
+        };
+        assert!(!metadata.aliases_match(&other));
+        assert_eq!(metadata.first_mismatch(&other), Some("sha256"));
+        assert_eq!(other.first_mismatch(&metadata), Some("sha256"));
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    enum TestAlias {
+        Sha1(hash::Sha1),
+        Sha256(hash::Sha256),
+    }
+
+    #[test]
+    fn content_alias_index() {
+        let content_id = ContentId::new(Blake2::from_byte_array([1; 32]));
+        let other_id = ContentId::new(Blake2::from_byte_array([5; 32]));
+        let sha1 = TestAlias::Sha1(hash::Sha1::from_byte_array([2; 20]));
+        let sha256 = TestAlias::Sha256(hash::Sha256::from_byte_array([3; 32]));
+        let unknown = TestAlias::Sha1(hash::Sha1::from_byte_array([4; 20]));
+
+        let mut index = ContentAliasIndex::new();
+        assert!(index.is_empty());
+        assert_eq!(index.insert(sha1.clone(), content_id), None);
+        assert_eq!(index.insert(sha256.clone(), content_id), None);
+        // Inserting the same mapping again doesn't duplicate it.
+        assert_eq!(index.insert(sha1.clone(), content_id), Some(content_id));
+        assert_eq!(index.len(), 2);
+
+        assert_eq!(index.by_alias(&sha1), Some(content_id));
+        assert_eq!(index.by_alias(&sha256), Some(content_id));
+        assert_eq!(index.by_alias(&unknown), None);
+        assert_eq!(
+            index.aliases_for(&content_id),
+            vec![sha1.clone(), sha256.clone()]
+        );
+        assert_eq!(index.aliases_for(&other_id), vec![]);
+
+        // Moving an alias to other content removes it from the original.
+        assert_eq!(index.insert(sha1.clone(), other_id), Some(content_id));
+        assert_eq!(index.by_alias(&sha1), Some(other_id));
+        assert_eq!(index.aliases_for(&content_id), vec![sha256]);
+        assert_eq!(index.aliases_for(&other_id), vec![sha1]);
+    }
+
+    #[test]
+    fn alias_computer_matches_one_shot() -> Result<()> {
+        let content = Bytes::from(b"the quick brown fox jumps over the lazy dog\n".repeat(100));
+        let size = content.len() as u64;
+        let content_id = FileContents::content_id_for_bytes(&content);
+
+        let mut git_sha1 = Sha1::new();
+        git_sha1.update(format!("blob {}\0", size));
+        git_sha1.update(&content);
+        let expected = ContentMetadata {
+            total_size: size,
+            content_id,
+            sha1: hash::Sha1::from_byte_array(Sha1::digest(&content).into()),
+            sha256: hash::Sha256::from_byte_array(Sha256::digest(&content).into()),
+            git_sha1: hash::RichGitSha1::from_byte_array(git_sha1.finalize().into(), "blob", size),
+        };
+
+        for chunk_size in [1, 7, 64, content.len()] {
+            let mut computer = AliasComputer::new(size);
+            for chunk in content.chunks(chunk_size) {
+                computer.update(chunk);
+            }
+            assert_eq!(computer.finalize(content_id, size)?, expected);
+        }
+
+        let mut computer = AliasComputer::new(size);
+        computer.update(&content[..10]);
+        assert!(computer.finalize(content_id, size).is_err());
+        assert!(AliasComputer::new(size).finalize(content_id, 0).is_err());
+
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn compute_inline_and_chunked(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let blobstore = Memblob::default();
+        borrowed!(ctx, blobstore);
+
+        let content = Bytes::from(b"the quick brown fox jumps over the lazy dog\n".repeat(100));
+        let content_id = FileContents::content_id_for_bytes(&content);
+
+        let inline = FileContents::Bytes(content.clone());
+        let expected = ContentMetadata::compute(ctx, blobstore, content_id, &inline).await?;
+        assert_eq!(expected.total_size, content.len() as u64);
+        assert_eq!(
+            expected.sha256,
+            hash::Sha256::from_byte_array(Sha256::digest(&content).into())
+        );
+
+        for chunk_size in [1000, content.len()] {
+            let mut pointers = Vec::new();
+            for chunk in content.chunks(chunk_size) {
+                let (blob, pointer) = new_blob_and_pointer(Bytes::copy_from_slice(chunk));
+                blob.store(ctx, blobstore).await?;
+                pointers.push(pointer);
+            }
+            let chunked = FileContents::Chunked(ChunkedFileContents::new(content_id, pointers));
+            assert_eq!(
+                ContentMetadata::compute(ctx, blobstore, content_id, &chunked).await?,
+                expected
+            );
+        }
+
+        // A missing chunk is an error.
+        let (_, pointer) = new_blob_and_pointer(Bytes::from_static(b"never stored"));
+        let missing = FileContents::Chunked(ChunkedFileContents::new(content_id, vec![pointer]));
+        assert!(
+            ContentMetadata::compute(ctx, blobstore, content_id, &missing)
+                .await
+                .is_err()
+        );
+
+        Ok(())
+    }
 
     quickcheck! {
         fn content_metadata_thrift_roundtrip(cab: ContentMetadata) -> bool {
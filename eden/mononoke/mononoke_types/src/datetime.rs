@@ -20,6 +20,8 @@ use chrono::Local;
 use chrono::LocalResult;
 use chrono::NaiveDateTime;
 use chrono::TimeZone;
+use chrono::Timelike;
+use chrono::Utc;
 use chrono_english::parse_date_string;
 use chrono_english::Dialect;
 use quickcheck::empty_shrinker;
@@ -81,6 +83,24 @@ impl DateTime {
         Ok(Self::new(dt))
     }
 
+    /// Construct a new `DateTime` from an RFC2822 string, such as
+    /// `Wed, 02 Oct 2002 13:00:00 +0200`.
+    ///
+    /// RFC2822 is the date format used in email headers. See
+    /// <https://tools.ietf.org/html/rfc2822#section-3.3>.
+    pub fn from_rfc2822(rfc2822: &str) -> Result<Self> {
+        let dt = ChronoDateTime::parse_from_rfc2822(rfc2822)
+            .with_context(|| ErrorKind::InvalidDateTime("while parsing rfc2822".into()))?;
+        Ok(Self::new(dt))
+    }
+
+    /// Format this `DateTime` as an RFC2822 string, preserving its timezone offset.
+    ///
+    /// RFC2822 only represents offsets to the minute, so any seconds in the offset are lost.
+    pub fn to_rfc2822(&self) -> String {
+        self.0.to_rfc2822()
+    }
+
     pub fn from_thrift(dt: thrift::DateTime) -> Result<Self> {
         Self::from_timestamp(dt.timestamp_secs, dt.tz_offset_secs)
     }
@@ -104,6 +124,39 @@ impl DateTime {
         (self.tz_offset_secs() / 60) as i16
     }
 
+    /// The same moment in time, but with a zero timezone offset.
+    ///
+    /// Comparisons between `DateTime`s already ignore the offset, but anything that looks at the
+    /// offset (such as formatting or serialization) will see the canonical UTC form.
+    pub fn to_utc(&self) -> Self {
+        Self::new(self.0.with_timezone(&Utc).into())
+    }
+
+    /// Whether this `DateTime` has a zero timezone offset.
+    #[inline]
+    pub fn is_utc(&self) -> bool {
+        self.tz_offset_secs() == 0
+    }
+
+    /// The start of the day containing this `DateTime`, in its own timezone offset.
+    pub fn truncate_to_day(&self) -> Self {
+        let local = self.0.naive_local();
+        self.with_local_time(local.date().and_hms_opt(0, 0, 0))
+    }
+
+    /// The start of the hour containing this `DateTime`, in its own timezone offset.
+    pub fn truncate_to_hour(&self) -> Self {
+        let local = self.0.naive_local();
+        self.with_local_time(local.date().and_hms_opt(local.hour(), 0, 0))
+    }
+
+    fn with_local_time(&self, local: Option<NaiveDateTime>) -> Self {
+        let dt = local
+            .and_then(|local| self.0.offset().from_local_datetime(&local).single())
+            .expect("a fixed offset maps each valid local time to exactly one moment");
+        Self::new(dt)
+    }
+
     #[inline]
     pub fn as_chrono(&self) -> &ChronoDateTime<FixedOffset> {
         &self.0
@@ -206,6 +259,29 @@ impl Timestamp {
         self.0 / SEC_IN_NS
     }
 
+    /// Construct a timestamp from nanoseconds since the Unix epoch.
+    pub fn from_unix_nanos(nanos: i64) -> Self {
+        Timestamp(nanos)
+    }
+
+    /// Nanoseconds since the Unix epoch.
+    pub fn to_unix_nanos(&self) -> i64 {
+        self.0
+    }
+
+    /// Construct a timestamp from milliseconds since the Unix epoch. Values
+    /// too large to represent in nanoseconds saturate at the representable
+    /// range.
+    pub fn from_unix_millis(millis: i64) -> Self {
+        Timestamp(millis.saturating_mul(MS_IN_NS))
+    }
+
+    /// Milliseconds since the Unix epoch, rounded down, so that a timestamp
+    /// just before the epoch gives -1 rather than 0.
+    pub fn to_unix_millis(&self) -> i64 {
+        self.0.div_euclid(MS_IN_NS)
+    }
+
     pub fn since_nanos(&self) -> i64 {
         let now = Self::now().timestamp_nanos();
         now - self.0
@@ -269,6 +345,83 @@ mod test {
         DateTime::from_rfc3339("2018-01-01T12:23:36").expect_err("unexpected Ok - no timezone");
     }
 
+    quickcheck! {
+        fn rfc2822_roundtrip(dt: DateTime) -> bool {
+            // RFC2822 offsets have minute granularity.
+            let tz_offset_secs = dt.tz_offset_secs() / 60 * 60;
+            let dt = DateTime::from_timestamp(dt.timestamp_secs(), tz_offset_secs)
+                .expect("arbitrary instances should always be valid");
+            let dt2 = DateTime::from_rfc2822(&dt.to_rfc2822())
+                .expect("roundtrip instances should always be valid");
+            dt == dt2 && dt.tz_offset_secs() == dt2.tz_offset_secs()
+        }
+    }
+
+    #[test]
+    fn rfc2822() {
+        let dt = DateTime::from_rfc2822("Wed, 02 Oct 2002 13:00:00 +0200")
+            .expect("unexpected err - +0200");
+        assert_eq!(dt.timestamp_secs(), 1_033_556_400);
+        assert_eq!(dt.tz_offset_secs(), -7200);
+        assert_eq!(dt.to_rfc2822(), "Wed, 02 Oct 2002 13:00:00 +0200");
+
+        // Offsets that aren't whole hours are preserved.
+        let dt = DateTime::from_rfc2822("Wed, 02 Oct 2002 13:00:00 +0530")
+            .expect("unexpected err - +0530");
+        assert_eq!(dt.tz_offset_secs(), -19_800);
+        assert_eq!(dt.to_rfc2822(), "Wed, 02 Oct 2002 13:00:00 +0530");
+        let dt = DateTime::from_rfc2822("Wed, 02 Oct 2002 13:00:00 -0945")
+            .expect("unexpected err - -0945");
+        assert_eq!(dt.tz_offset_secs(), 35_100);
+        assert_eq!(dt.to_rfc2822(), "Wed, 02 Oct 2002 13:00:00 -0945");
+
+        DateTime::from_rfc2822("2002-10-02T13:00:00+02:00").expect_err("unexpected Ok - rfc3339");
+        DateTime::from_rfc2822("Wed, 02 Oct 2002").expect_err("unexpected Ok - no time");
+    }
+
+    #[test]
+    fn to_utc() {
+        let dt = DateTime::from_rfc2822("Wed, 02 Oct 2002 13:00:00 +0530")
+            .expect("unexpected err - +0530");
+        assert!(!dt.is_utc());
+
+        let utc = dt.to_utc();
+        assert!(utc.is_utc());
+        assert_eq!(utc.tz_offset_secs(), 0);
+        assert_eq!(utc.timestamp_secs(), dt.timestamp_secs());
+        assert_eq!(utc, dt);
+        assert_eq!(utc.to_rfc2822(), "Wed, 02 Oct 2002 07:30:00 +0000");
+        assert!(utc.to_utc().is_utc());
+    }
+
+    #[test]
+    fn truncate() {
+        let dt = DateTime::from_rfc3339("2002-10-02T01:45:12.5+05:30").expect("unexpected err");
+
+        let day = dt.truncate_to_day();
+        assert_eq!(
+            day,
+            DateTime::from_rfc3339("2002-10-02T00:00:00+05:30").unwrap()
+        );
+        assert_eq!(day.tz_offset_secs(), dt.tz_offset_secs());
+        // In UTC the same moment is still on the previous day.
+        assert_eq!(
+            dt.to_utc().truncate_to_day(),
+            DateTime::from_rfc3339("2002-10-01T00:00:00Z").unwrap()
+        );
+
+        let hour = dt.truncate_to_hour();
+        assert_eq!(
+            hour,
+            DateTime::from_rfc3339("2002-10-02T01:00:00+05:30").unwrap()
+        );
+        assert_eq!(hour.tz_offset_secs(), dt.tz_offset_secs());
+        assert_eq!(hour.to_rfc2822(), "Wed, 02 Oct 2002 01:00:00 +0530");
+        // Truncating is idempotent.
+        assert_eq!(hour.truncate_to_hour(), hour);
+        assert_eq!(day.truncate_to_day(), day);
+    }
+
     #[test]
     fn bad_inputs() {
         DateTime::from_timestamp(0, 86_400)
@@ -315,6 +468,40 @@ mod test {
         assert_eq!(dt0, dt1);
     }
 
+    #[test]
+    fn unix_nanos_and_millis() {
+        // 2002-10-02T11:00:00Z, 1969-07-20T20:17:40Z and the epoch.
+        for secs in [1_033_556_400, -14_182_940, 0] {
+            let ts = Timestamp::from_timestamp_secs(secs);
+            assert_eq!(ts.to_unix_nanos(), secs * SEC_IN_NS);
+            assert_eq!(ts.to_unix_millis(), secs * 1000);
+            assert_eq!(Timestamp::from_unix_nanos(ts.to_unix_nanos()), ts);
+            assert_eq!(Timestamp::from_unix_millis(ts.to_unix_millis()), ts);
+            assert_eq!(ts.to_unix_nanos(), ts.to_unix_millis() * MS_IN_NS);
+        }
+
+        // Sub-millisecond precision is rounded down, including before the epoch.
+        assert_eq!(Timestamp::from_unix_nanos(1_999_999).to_unix_millis(), 1);
+        assert_eq!(Timestamp::from_unix_nanos(-1).to_unix_millis(), -1);
+        assert_eq!(Timestamp::from_unix_nanos(-1_000_000).to_unix_millis(), -1);
+        assert_eq!(Timestamp::from_unix_nanos(-1_000_001).to_unix_millis(), -2);
+    }
+
+    #[test]
+    fn unix_millis_saturates() {
+        let max = Timestamp::from_unix_millis(i64::MAX);
+        assert_eq!(max, Timestamp::from_unix_nanos(i64::MAX));
+        assert_eq!(max.to_unix_millis(), i64::MAX / MS_IN_NS);
+
+        let min = Timestamp::from_unix_millis(i64::MIN);
+        assert_eq!(min, Timestamp::from_unix_nanos(i64::MIN));
+        assert_eq!(min.to_unix_millis(), i64::MIN.div_euclid(MS_IN_NS));
+
+        // The largest value that still fits is converted exactly.
+        let millis = i64::MAX / MS_IN_NS;
+        assert_eq!(Timestamp::from_unix_millis(millis).to_unix_millis(), millis);
+    }
+
     #[test]
     fn seconds() {
         let ts0 = Timestamp::from_timestamp_nanos(SEC_IN_NS);
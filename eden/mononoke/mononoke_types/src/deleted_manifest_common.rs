@@ -11,11 +11,18 @@ use std::fmt::Debug;
 use anyhow::Result;
 use blobstore::Blobstore;
 use blobstore::Loadable;
+use bounded_traversal::bounded_traversal_stream;
 use context::CoreContext;
+use futures::future;
 use futures::stream::BoxStream;
+use futures::FutureExt;
+use futures::StreamExt;
+use futures::TryFutureExt;
+use futures::TryStreamExt;
 
 use crate::blob::BlobstoreValue;
 use crate::ChangesetId;
+use crate::MPath;
 use crate::MPathElement;
 use crate::MononokeId;
 
@@ -45,6 +52,59 @@ pub trait DeletedManifestCommon:
         basename: &MPathElement,
     ) -> Result<Option<Self::Id>>;
 
+    /// Find the node for `path`, relative to this manifest, if that path was deleted. Returns
+    /// `None` as soon as a path element is missing, or if the path exists but wasn't deleted.
+    async fn lookup_deleted(
+        &self,
+        ctx: &CoreContext,
+        blobstore: &impl Blobstore,
+        path: &MPath,
+    ) -> Result<Option<Self::Id>> {
+        let mut current = self.clone();
+        let mut current_id = None;
+        for element in path {
+            match current.lookup(ctx, blobstore, element).await? {
+                Some(id) => {
+                    current = id.load(ctx, blobstore).await?;
+                    current_id = Some(id);
+                }
+                None => return Ok(None),
+            }
+        }
+        Ok(current_id.filter(|_| current.is_deleted()))
+    }
+
+    /// List the paths, relative to this manifest, of every deleted node below it. Nodes are
+    /// loaded lazily as the stream is polled, with a bounded number in flight at once.
+    fn list_deleted<'a>(
+        &self,
+        ctx: &'a CoreContext,
+        blobstore: &'a impl Blobstore,
+    ) -> BoxStream<'a, Result<MPath>> {
+        self.clone()
+            .into_subentries(ctx, blobstore)
+            .map_ok(|(name, id)| (MPath::from(name), id))
+            .try_collect::<Vec<_>>()
+            .map_ok(move |top_level| {
+                bounded_traversal_stream(256, top_level, move |(path, id): (MPath, Self::Id)| {
+                    async move {
+                        let manifest = id.load(ctx, blobstore).await?;
+                        let is_deleted = manifest.is_deleted();
+                        let subentries = manifest
+                            .into_subentries(ctx, blobstore)
+                            .map_ok(|(name, id)| (path.join_element(Some(&name)), id))
+                            .try_collect::<Vec<_>>()
+                            .await?;
+                        anyhow::Ok((is_deleted.then_some(path), subentries))
+                    }
+                    .boxed()
+                })
+                .try_filter_map(future::ok)
+            })
+            .try_flatten_stream()
+            .boxed()
+    }
+
     /// List all subentries on this manifest. Use with care, some manifests can
     /// have hundreds of thousands of subentries.
     fn into_subentries<'a>(
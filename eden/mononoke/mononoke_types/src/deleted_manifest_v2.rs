@@ -231,3 +231,107 @@ impl Storable for DeletedManifestV2 {
         Ok(id)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use blobstore::Loadable;
+    use borrowed::borrowed;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
+
+    use super::*;
+    use crate::hash::Blake2;
+    use crate::MPath;
+
+    #[fbinit::test]
+    async fn test_lookup_deleted(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let blobstore = Memblob::default();
+        borrowed!(ctx, blobstore);
+        let cs_id = ChangesetId::new(Blake2::from_byte_array([1; 32]));
+        let element = |name: &str| MPathElement::new(name.as_bytes().to_vec()).unwrap();
+
+        // dir/deleted was deleted in cs_id, and dir itself still exists.
+        let deleted = DeletedManifestV2::new(Some(cs_id), Default::default());
+        let deleted_id = deleted.store(ctx, blobstore).await?;
+        let dir = DeletedManifestV2::copy_and_update_subentries(
+            ctx,
+            blobstore,
+            None,
+            None,
+            BTreeMap::from([(element("deleted"), Some(deleted_id))]),
+        )
+        .await?;
+        let dir_id = dir.store(ctx, blobstore).await?;
+        let root = DeletedManifestV2::copy_and_update_subentries(
+            ctx,
+            blobstore,
+            None,
+            None,
+            BTreeMap::from([(element("dir"), Some(dir_id))]),
+        )
+        .await?;
+
+        for (path, expected) in [
+            ("dir/deleted", Some(deleted_id)),
+            ("dir", None),
+            ("dir/live", None),
+            ("dir/deleted/below", None),
+            ("other/deleted", None),
+        ] {
+            let path = MPath::new(path)?;
+            assert_eq!(root.lookup_deleted(ctx, blobstore, &path).await?, expected);
+        }
+
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_list_deleted(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let blobstore = Memblob::default();
+        borrowed!(ctx, blobstore);
+        let cs_id = ChangesetId::new(Blake2::from_byte_array([1; 32]));
+        let element = |name: &str| MPathElement::new(name.as_bytes().to_vec()).unwrap();
+
+        // Builds a node with the given subentries, deleted in cs_id if `deleted`.
+        let node = |deleted: bool, subentries: Vec<(&str, DeletedManifestV2Id)>| {
+            let subentries = subentries
+                .into_iter()
+                .map(|(name, id)| (element(name), Some(id)))
+                .collect();
+            async move {
+                let node = DeletedManifestV2::copy_and_update_subentries(
+                    ctx,
+                    blobstore,
+                    None,
+                    deleted.then_some(cs_id),
+                    subentries,
+                )
+                .await?;
+                node.store(ctx, blobstore).await
+            }
+        };
+
+        // "top" and "dir/sub/file" were deleted; "dir" and "dir/sub" still exist.
+        let top = node(true, vec![]).await?;
+        let file = node(true, vec![]).await?;
+        let sub = node(false, vec![("file", file)]).await?;
+        let dir = node(false, vec![("sub", sub)]).await?;
+        let root_id = node(false, vec![("dir", dir), ("top", top)]).await?;
+        let root = root_id.load(ctx, blobstore).await?;
+
+        let mut deleted: Vec<_> = root.list_deleted(ctx, blobstore).try_collect().await?;
+        deleted.sort();
+        assert_eq!(
+            deleted,
+            vec![MPath::new("dir/sub/file")?, MPath::new("top")?]
+        );
+
+        let empty = DeletedManifestV2::new(None, Default::default());
+        let none: Vec<_> = empty.list_deleted(ctx, blobstore).try_collect().await?;
+        assert!(none.is_empty());
+
+        Ok(())
+    }
+}
@@ -8,6 +8,9 @@
 use thiserror::Error;
 
 use crate::path::MPath;
+use crate::svnrev::Svnrev;
+use crate::typed_hash::ChangesetId;
+use crate::typed_hash::ContentChunkId;
 
 #[derive(Debug, Error)]
 pub enum ErrorKind {
@@ -37,4 +40,69 @@ pub enum ErrorKind {
     InvalidBonsaiChangeset(String),
     #[error("Failed to parse RepositoryId from '{0}'")]
     FailedToParseRepositoryId(String),
+    #[error("fastlog batches disagree on the parents of {0}")]
+    InconsistentFastlogParents(ChangesetId),
+    #[error("content size mismatch: expected {expected} bytes, got {actual}")]
+    ContentSizeMismatch { expected: u64, actual: u64 },
+    #[error("content chunk {chunk_id} is {actual} bytes, but its pointer expects {expected}")]
+    ChunkSizeMismatch {
+        chunk_id: ContentChunkId,
+        expected: u64,
+        actual: u64,
+    },
+    #[error("svnrev {0} is mapped to more than one changeset")]
+    DuplicateSvnrev(Svnrev),
+    #[error("changeset {0} is mapped to more than one svnrev")]
+    DuplicateSvnrevChangeset(ChangesetId),
+}
+
+/// Why a path element was rejected by `MPathElement::new_checked`.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum MPathElementError {
+    #[error("path elements cannot be empty")]
+    Empty,
+    #[error("path elements cannot contain '\\0'")]
+    ContainsNull,
+    #[error("path elements cannot contain '\\1'")]
+    ContainsMetadataSeparator,
+    #[error("path elements cannot contain '/'")]
+    ContainsSlash,
+    #[error("path elements cannot contain '\\n'")]
+    ContainsNewline,
+    #[error("path elements cannot be . or .. to avoid traversal attacks")]
+    DotOrDotDot,
+    #[error("path elements cannot exceed {max} bytes")]
+    TooLong { len: usize, max: usize },
+}
+
+/// A path that is deeper than allowed, as reported by `check_path_depth`.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[error("path '{path}' has depth {depth}, which exceeds the limit of {max}")]
+pub struct TooDeepError {
+    pub path: MPath,
+    pub depth: usize,
+    pub max: usize,
+}
+
+/// A file change in a bonsai changeset that is copied from a changeset that isn't one of its
+/// parents, as reported by `BonsaiChangesetMut::validate_copy_sources`.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[error(
+    "copy information for path '{path}' (from '{copy_from_path}') has parent {copy_from_cs} which isn't recognized"
+)]
+pub struct CopySourceError {
+    pub path: MPath,
+    pub copy_from_path: MPath,
+    pub copy_from_cs: ChangesetId,
+}
+
+/// Why a changeset id prefix didn't resolve to exactly one changeset, as reported by
+/// `ChangesetIdsResolvedFromPrefix::into_unique`.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum PrefixResolutionError {
+    #[error("no changeset matches the prefix")]
+    NoMatch,
+    /// The prefix matches more than one changeset. Holds a bounded sample of the matches.
+    #[error("the prefix is ambiguous, matching changesets including {0:?}")]
+    Ambiguous(Vec<ChangesetId>),
 }
@@ -5,6 +5,9 @@
  * GNU General Public License version 2.
  */
 
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
 use std::collections::VecDeque;
 
 use anyhow::Context;
@@ -135,6 +138,80 @@ impl FastlogBatch {
         .boxed()
     }
 
+    /// Combine the history in this batch with the history in `other`. Entries from this batch
+    /// come first, followed by the entries of `other` that aren't already present, and then the
+    /// combined list is reordered as little as possible so that every changeset comes before its
+    /// parents, which keeps all parent offsets positive. A changeset that appears in both must
+    /// have the same parents in both, except that a parent that lies outside one of the batches
+    /// is taken from the other. Like `new_from_raw_list`, the result is truncated to
+    /// `max_entries_in_fastlog_batch()` entries.
+    pub async fn merge<'a, B: Blobstore>(
+        &'a self,
+        ctx: &'a CoreContext,
+        blobstore: &'a B,
+        other: &'a FastlogBatch,
+    ) -> Result<FastlogBatch> {
+        let (ours, theirs) = futures::try_join!(
+            self.fetch_raw_list(ctx, blobstore),
+            other.fetch_raw_list(ctx, blobstore),
+        )?;
+
+        let mut merged: Vec<(ChangesetId, Vec<Option<ChangesetId>>)> = Vec::new();
+        let mut positions: HashMap<ChangesetId, usize> = HashMap::new();
+        for (cs_id, parents) in resolve_parents(ours)
+            .into_iter()
+            .chain(resolve_parents(theirs))
+        {
+            match positions.get(&cs_id) {
+                Some(&pos) => {
+                    let existing = &mut merged[pos].1;
+                    if existing.len() != parents.len() {
+                        return Err(ErrorKind::InconsistentFastlogParents(cs_id).into());
+                    }
+                    for (existing, parent) in existing.iter_mut().zip(parents) {
+                        match (*existing, parent) {
+                            (Some(a), Some(b)) if a != b => {
+                                return Err(ErrorKind::InconsistentFastlogParents(cs_id).into());
+                            }
+                            (None, Some(b)) => *existing = Some(b),
+                            _ => {}
+                        }
+                    }
+                }
+                None => {
+                    positions.insert(cs_id, merged.len());
+                    merged.push((cs_id, parents));
+                }
+            }
+        }
+
+        let merged = sort_children_first(merged, &positions)?;
+        let positions: HashMap<_, _> = merged
+            .iter()
+            .enumerate()
+            .map(|(pos, (cs_id, _))| (*cs_id, pos))
+            .collect();
+
+        let len = merged.len();
+        let raw_list = merged
+            .into_iter()
+            .enumerate()
+            .map(|(pos, (cs_id, parents))| {
+                let offsets = parents
+                    .into_iter()
+                    .map(|parent| {
+                        // Parents outside the merged history point past its end.
+                        let parent_pos = parent
+                            .and_then(|parent| positions.get(&parent).copied())
+                            .unwrap_or(len);
+                        ParentOffset(parent_pos as i32 - pos as i32)
+                    })
+                    .collect();
+                (cs_id, offsets)
+            });
+        FastlogBatch::new_from_raw_list(ctx, blobstore, raw_list.collect::<Vec<_>>()).await
+    }
+
     pub fn latest(&self) -> &VecDeque<(ChangesetId, Vec<ParentOffset>)> {
         &self.latest
     }
@@ -221,6 +298,85 @@ impl FastlogBatch {
     }
 }
 
+/// Replace the parent offsets in a raw list with the parents they point to, or `None` for
+/// parents outside the list. Repeated edges to the same parent are dropped.
+fn resolve_parents(
+    raw_list: Vec<(ChangesetId, Vec<ParentOffset>)>,
+) -> Vec<(ChangesetId, Vec<Option<ChangesetId>>)> {
+    let cs_ids: Vec<_> = raw_list.iter().map(|(cs_id, _)| *cs_id).collect();
+    raw_list
+        .into_iter()
+        .enumerate()
+        .map(|(pos, (cs_id, offsets))| {
+            let mut parents = Vec::with_capacity(offsets.len());
+            for offset in offsets {
+                let parent = usize::try_from(pos as i64 + offset.num() as i64)
+                    .ok()
+                    .and_then(|parent_pos| cs_ids.get(parent_pos).copied());
+                if parent.is_none() || !parents.contains(&parent) {
+                    parents.push(parent);
+                }
+            }
+            (cs_id, parents)
+        })
+        .collect()
+}
+
+/// Order the merged history so that every changeset comes before all of its parents, keeping
+/// the existing order wherever the parent relationships allow. Fails if the parents form a cycle.
+fn sort_children_first(
+    merged: Vec<(ChangesetId, Vec<Option<ChangesetId>>)>,
+    positions: &HashMap<ChangesetId, usize>,
+) -> Result<Vec<(ChangesetId, Vec<Option<ChangesetId>>)>> {
+    let parent_positions: Vec<Vec<usize>> = merged
+        .iter()
+        .map(|(_, parents)| {
+            parents
+                .iter()
+                .filter_map(|parent| parent.and_then(|parent| positions.get(&parent).copied()))
+                .collect()
+        })
+        .collect();
+
+    let mut children_left = vec![0usize; merged.len()];
+    for parent_pos in parent_positions.iter().flatten() {
+        children_left[*parent_pos] += 1;
+    }
+
+    let mut ready: BinaryHeap<_> = children_left
+        .iter()
+        .enumerate()
+        .filter(|(_, children)| **children == 0)
+        .map(|(pos, _)| Reverse(pos))
+        .collect();
+    let mut order = Vec::with_capacity(merged.len());
+    while let Some(Reverse(pos)) = ready.pop() {
+        order.push(pos);
+        for parent_pos in &parent_positions[pos] {
+            children_left[*parent_pos] -= 1;
+            if children_left[*parent_pos] == 0 {
+                ready.push(Reverse(*parent_pos));
+            }
+        }
+    }
+
+    if order.len() != merged.len() {
+        let (cs_id, _) = children_left
+            .iter()
+            .zip(&merged)
+            .find(|(children, _)| **children > 0)
+            .map(|(_, entry)| entry)
+            .expect("unsorted entries must be part of a cycle");
+        return Err(ErrorKind::InconsistentFastlogParents(*cs_id).into());
+    }
+
+    let mut merged: Vec<_> = merged.into_iter().map(Some).collect();
+    Ok(order
+        .into_iter()
+        .filter_map(|pos| merged[pos].take())
+        .collect())
+}
+
 impl BlobstoreValue for FastlogBatch {
     type Key = FastlogBatchId;
 
@@ -329,6 +485,104 @@ mod test {
         Ok(())
     }
 
+    fn cs(i: u8) -> ChangesetId {
+        ChangesetId::new(Blake2::from_byte_array([i; 32]))
+    }
+
+    #[fbinit::test]
+    async fn test_fastlog_batch_merge(fb: FacebookInit) -> Result<()> {
+        let blobstore = Arc::new(Memblob::default());
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx, blobstore: &Arc<_>);
+
+        // 1 -> 2 -> 3, with 3's parent outside the batch.
+        let first = vec![
+            (cs(1), vec![ParentOffset(1)]),
+            (cs(2), vec![ParentOffset(1), ParentOffset(1)]),
+            (cs(3), vec![ParentOffset(1)]),
+        ];
+        // 3 -> 4 -> 5, a root.
+        let second = vec![
+            (cs(3), vec![ParentOffset(1)]),
+            (cs(4), vec![ParentOffset(1)]),
+            (cs(5), vec![]),
+        ];
+        let first = FastlogBatch::new_from_raw_list(ctx, blobstore, first).await?;
+        let second = FastlogBatch::new_from_raw_list(ctx, blobstore, second).await?;
+
+        let merged = first.merge(ctx, blobstore, &second).await?;
+        assert_eq!(
+            merged.fetch_raw_list(ctx, blobstore).await?,
+            vec![
+                (cs(1), vec![ParentOffset(1)]),
+                (cs(2), vec![ParentOffset(1)]),
+                (cs(3), vec![ParentOffset(1)]),
+                (cs(4), vec![ParentOffset(1)]),
+                (cs(5), vec![]),
+            ]
+        );
+
+        // Merging the other way round still has to put children before their parents.
+        let merged = second.merge(ctx, blobstore, &first).await?;
+        let raw_list = merged.fetch_raw_list(ctx, blobstore).await?;
+        assert!(
+            raw_list
+                .iter()
+                .flat_map(|(_, offsets)| offsets)
+                .all(|offset| offset.num() >= 0)
+        );
+        assert_eq!(
+            raw_list,
+            vec![
+                (cs(1), vec![ParentOffset(1)]),
+                (cs(2), vec![ParentOffset(1)]),
+                (cs(3), vec![ParentOffset(1)]),
+                (cs(4), vec![ParentOffset(1)]),
+                (cs(5), vec![]),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_fastlog_batch_merge_conflict(fb: FacebookInit) -> Result<()> {
+        let blobstore = Arc::new(Memblob::default());
+        let ctx = CoreContext::test_mock(fb);
+        borrowed!(ctx, blobstore: &Arc<_>);
+
+        let first = vec![(cs(1), vec![ParentOffset(1)]), (cs(2), vec![])];
+        let second = vec![(cs(1), vec![ParentOffset(1)]), (cs(3), vec![])];
+        let first = FastlogBatch::new_from_raw_list(ctx, blobstore, first).await?;
+        let second = FastlogBatch::new_from_raw_list(ctx, blobstore, second).await?;
+
+        let err = first.merge(ctx, blobstore, &second).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::InconsistentFastlogParents(cs_id)) if *cs_id == cs(1)
+        ));
+
+        // Each batch is fine on its own, but together 1 and 2 are each other's parent.
+        let first = vec![
+            (cs(1), vec![ParentOffset(1)]),
+            (cs(2), vec![ParentOffset(1)]),
+        ];
+        let second = vec![
+            (cs(2), vec![ParentOffset(1)]),
+            (cs(1), vec![ParentOffset(1)]),
+        ];
+        let first = FastlogBatch::new_from_raw_list(ctx, blobstore, first).await?;
+        let second = FastlogBatch::new_from_raw_list(ctx, blobstore, second).await?;
+
+        let err = first.merge(ctx, blobstore, &second).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::InconsistentFastlogParents(_))
+        ));
+
+        Ok(())
+    }
+
     #[quickcheck_async::tokio]
     async fn fastlog_roundtrip(fb: FacebookInit, hashes: Vec<(ChangesetId, i32)>) -> TestResult {
         let blobstore = Arc::new(Memblob::default());
@@ -198,6 +198,11 @@ impl FileChange {
         }
     }
 
+    /// Whether this change records a copy (or rename) from another path.
+    pub fn is_copy(&self) -> bool {
+        self.copy_from().is_some()
+    }
+
     pub fn size(&self) -> Option<u64> {
         match &self {
             Self::Change(tc) => Some(tc.size()),
@@ -349,6 +354,38 @@ impl FileType {
         }
     }
 
+    #[inline]
+    pub fn is_regular(&self) -> bool {
+        *self == FileType::Regular
+    }
+
+    #[inline]
+    pub fn is_executable(&self) -> bool {
+        *self == FileType::Executable
+    }
+
+    #[inline]
+    pub fn is_symlink(&self) -> bool {
+        *self == FileType::Symlink
+    }
+
+    /// Map POSIX mode bits (as returned by `stat(2)`) to a file type. Regular files are
+    /// executable if the owner execute bit is set, matching how Git and Mercurial record them.
+    /// Returns `None` for types that can't be stored, such as directories, sockets or FIFOs.
+    pub fn from_mode_bits(mode: u32) -> Option<Self> {
+        const S_IFMT: u32 = 0o170000;
+        const S_IFREG: u32 = 0o100000;
+        const S_IFLNK: u32 = 0o120000;
+        const S_IXUSR: u32 = 0o100;
+
+        match mode & S_IFMT {
+            S_IFLNK => Some(FileType::Symlink),
+            S_IFREG if mode & S_IXUSR != 0 => Some(FileType::Executable),
+            S_IFREG => Some(FileType::Regular),
+            _ => None,
+        }
+    }
+
     pub fn from_thrift(ft: thrift::FileType) -> Result<Self> {
         let file_type = match ft {
             thrift::FileType::Regular => FileType::Regular,
@@ -440,6 +477,36 @@ mod test {
         }
     }
 
+    #[test]
+    fn filetype_predicates() {
+        assert!(FileType::Regular.is_regular());
+        assert!(!FileType::Regular.is_executable());
+        assert!(!FileType::Regular.is_symlink());
+        assert!(FileType::Executable.is_executable());
+        assert!(!FileType::Executable.is_regular());
+        assert!(FileType::Symlink.is_symlink());
+        assert!(!FileType::Symlink.is_regular());
+    }
+
+    #[test]
+    fn filetype_from_mode_bits() {
+        assert_eq!(FileType::from_mode_bits(0o120000), Some(FileType::Symlink));
+        assert_eq!(FileType::from_mode_bits(0o120777), Some(FileType::Symlink));
+        assert_eq!(
+            FileType::from_mode_bits(0o100755),
+            Some(FileType::Executable)
+        );
+        assert_eq!(FileType::from_mode_bits(0o100644), Some(FileType::Regular));
+        assert_eq!(FileType::from_mode_bits(0o100600), Some(FileType::Regular));
+
+        // Directories, sockets, FIFOs and devices can't be represented.
+        assert_eq!(FileType::from_mode_bits(0o040755), None);
+        assert_eq!(FileType::from_mode_bits(0o140755), None);
+        assert_eq!(FileType::from_mode_bits(0o010644), None);
+        assert_eq!(FileType::from_mode_bits(0o020644), None);
+        assert_eq!(FileType::from_mode_bits(0o060644), None);
+    }
+
     #[test]
     fn bad_filetype_thrift() {
         let thrift_ft = thrift::FileType(42);
@@ -459,4 +526,30 @@ mod test {
         TrackedFileChange::from_thrift(thrift_fc, &MPath::new("foo").unwrap())
             .expect_err("unexpected OK - bad content ID");
     }
+
+    #[test]
+    fn filechange_copy_from() {
+        let content_id = ContentId::from_byte_array([1; 32]);
+        let source = (
+            MPath::new("from").unwrap(),
+            ChangesetId::from_byte_array([2; 32]),
+        );
+
+        let added = FileChange::tracked(content_id, FileType::Regular, 1, None);
+        assert_eq!(added.copy_from(), None);
+        assert!(!added.is_copy());
+
+        let copied = FileChange::tracked(content_id, FileType::Regular, 1, Some(source.clone()));
+        assert_eq!(copied.copy_from(), Some(&source));
+        assert!(copied.is_copy());
+
+        let untracked = FileChange::untracked(content_id, FileType::Regular, 1);
+        assert_eq!(untracked.copy_from(), None);
+        assert!(!untracked.is_copy());
+
+        for deleted in [FileChange::Deletion, FileChange::UntrackedDeletion] {
+            assert_eq!(deleted.copy_from(), None);
+            assert!(!deleted.is_copy());
+        }
+    }
 }
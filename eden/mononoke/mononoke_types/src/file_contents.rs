@@ -7,12 +7,20 @@
 
 use std::fmt;
 use std::fmt::Debug;
+use std::ops::Range;
 
 use anyhow::bail;
 use anyhow::Context;
 use anyhow::Result;
+use blobstore::Blobstore;
+use blobstore::Loadable;
 use bytes::Bytes;
+use bytes::BytesMut;
+use context::CoreContext;
 use fbthrift::compact_protocol;
+use futures::stream;
+use futures::stream::StreamExt;
+use futures::stream::TryStreamExt;
 use quickcheck::empty_shrinker;
 use quickcheck::single_shrinker;
 use quickcheck::Arbitrary;
@@ -21,6 +29,7 @@ use quickcheck::Gen;
 use crate::blob::Blob;
 use crate::blob::BlobstoreValue;
 use crate::blob::ContentBlob;
+use crate::content_chunk::ContentChunk;
 use crate::errors::ErrorKind;
 use crate::thrift;
 use crate::typed_hash::ContentChunkId;
@@ -89,6 +98,54 @@ impl FileContents {
         context.update(&bytes);
         context.finish()
     }
+
+    /// Decide how to store `bytes`: inline if they are no larger than `chunk_threshold`, and
+    /// otherwise as chunks of `chunk_size` bytes (the last of which may be shorter). This only
+    /// plans the layout; storing the chunks and the contents is left to the caller.
+    pub fn from_bytes_with_policy(
+        bytes: Bytes,
+        chunk_threshold: u64,
+        chunk_size: u64,
+    ) -> FileContentsPlan {
+        let len = bytes.len() as u64;
+        if len <= chunk_threshold {
+            return FileContentsPlan::Inline(bytes);
+        }
+
+        let chunk_size = chunk_size.max(1);
+        let ranges = (0..len)
+            .step_by(chunk_size as usize)
+            .map(|start| start as usize..(start + chunk_size).min(len) as usize)
+            .collect();
+        FileContentsPlan::Chunked { bytes, ranges }
+    }
+}
+
+/// How some file contents should be stored, as decided by
+/// `FileContents::from_bytes_with_policy`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FileContentsPlan {
+    /// Store the bytes inline, as `FileContents::Bytes`
+    Inline(Bytes),
+    /// Store each range of `bytes` as a `ContentChunk`, and point to them from
+    /// `FileContents::Chunked`
+    Chunked {
+        bytes: Bytes,
+        ranges: Vec<Range<usize>>,
+    },
+}
+
+impl FileContentsPlan {
+    /// The bytes of each chunk to store, in order. Inline plans have no chunks.
+    pub fn chunks(&self) -> Vec<Bytes> {
+        match self {
+            FileContentsPlan::Inline(_) => vec![],
+            FileContentsPlan::Chunked { bytes, ranges } => ranges
+                .iter()
+                .map(|range| bytes.slice(range.clone()))
+                .collect(),
+        }
+    }
 }
 
 impl BlobstoreValue for FileContents {
@@ -211,6 +268,29 @@ impl ChunkedFileContents {
     pub fn size(&self) -> u64 {
         self.size
     }
+
+    /// Fetch all the chunks of this file, with at most `concurrency` fetches in flight at once,
+    /// and join them into the file's contents. Fails if any chunk doesn't have the size its
+    /// pointer expects.
+    pub async fn into_bytes<'a>(
+        self,
+        ctx: &'a CoreContext,
+        blobstore: &'a impl Blobstore,
+        concurrency: usize,
+    ) -> Result<Bytes> {
+        let mut contents = BytesMut::with_capacity(self.size as usize);
+        // `buffered` yields the chunks in order, however their fetches complete.
+        let mut chunks = stream::iter(
+            self.chunks
+                .iter()
+                .map(|pointer| pointer.verify(ctx, blobstore)),
+        )
+        .buffered(concurrency.max(1));
+        while let Some(chunk) = chunks.try_next().await? {
+            contents.extend_from_slice(&chunk.into_bytes());
+        }
+        Ok(contents.freeze())
+    }
 }
 
 impl Arbitrary for ChunkedFileContents {
@@ -258,6 +338,23 @@ impl ContentChunkPointer {
     pub fn size(&self) -> u64 {
         self.size
     }
+
+    /// Load the chunk this points to, checking that it has the size this pointer expects.
+    pub async fn verify<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        blobstore: &'a impl Blobstore,
+    ) -> Result<ContentChunk> {
+        let chunk = self.chunk_id.load(ctx, blobstore).await?;
+        if chunk.size() != self.size {
+            bail!(ErrorKind::ChunkSizeMismatch {
+                chunk_id: self.chunk_id,
+                expected: self.size,
+                actual: chunk.size(),
+            });
+        }
+        Ok(chunk)
+    }
 }
 
 impl Arbitrary for ContentChunkPointer {
@@ -271,6 +368,10 @@ impl Arbitrary for ContentChunkPointer {
 
 #[cfg(test)]
 mod test {
+    use blobstore::Storable;
+    use borrowed::borrowed;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
     use quickcheck::quickcheck;
 
     use super::*;
@@ -296,4 +397,129 @@ mod test {
         let thrift_fc = thrift::FileContents::UnknownField(-1);
         FileContents::from_thrift(thrift_fc).expect_err("unexpected OK - unknown field");
     }
+
+    #[test]
+    fn plan_inline() {
+        let bytes = Bytes::from_static(b"small file");
+        for threshold in [10, 100] {
+            assert_eq!(
+                FileContents::from_bytes_with_policy(bytes.clone(), threshold, 4),
+                FileContentsPlan::Inline(bytes.clone())
+            );
+        }
+        let plan = FileContents::from_bytes_with_policy(Bytes::new(), 0, 4);
+        assert_eq!(plan, FileContentsPlan::Inline(Bytes::new()));
+        assert!(plan.chunks().is_empty());
+    }
+
+    #[test]
+    fn plan_chunked() {
+        let bytes = Bytes::from_static(b"0123456789abcdefghij!");
+        let plan = FileContents::from_bytes_with_policy(bytes.clone(), 10, 5);
+        assert_eq!(
+            plan,
+            FileContentsPlan::Chunked {
+                bytes: bytes.clone(),
+                ranges: vec![0..5, 5..10, 10..15, 15..20, 20..21],
+            }
+        );
+        let chunks = plan.chunks();
+        assert_eq!(chunks, vec!["01234", "56789", "abcde", "fghij", "!"]);
+        assert_eq!(chunks.concat(), bytes);
+
+        // An evenly divisible file has no short tail.
+        let plan = FileContents::from_bytes_with_policy(bytes.slice(..20), 10, 5);
+        let sizes: Vec<_> = plan.chunks().iter().map(Bytes::len).collect();
+        assert_eq!(sizes, vec![5; 4]);
+    }
+
+    #[test]
+    fn chunked_size_and_count() {
+        let content_id = ContentId::from_byte_array([1; 32]);
+        let chunks = vec![
+            ContentChunkPointer::new(ContentChunkId::from_byte_array([2; 32]), 10),
+            ContentChunkPointer::new(ContentChunkId::from_byte_array([3; 32]), 20),
+            ContentChunkPointer::new(ContentChunkId::from_byte_array([4; 32]), 5),
+        ];
+        let chunked = ChunkedFileContents::new(content_id, chunks);
+        assert_eq!(chunked.size(), 35);
+        assert_eq!(chunked.num_chunks(), 3);
+        assert_eq!(FileContents::Chunked(chunked.clone()).size(), 35);
+
+        // The size survives a roundtrip, as it is recomputed from the pointers.
+        let roundtripped = ChunkedFileContents::from_thrift(chunked.into_thrift()).unwrap();
+        assert_eq!(roundtripped.size(), 35);
+        assert_eq!(roundtripped.num_chunks(), 3);
+
+        let empty = ChunkedFileContents::new(content_id, vec![]);
+        assert_eq!(empty.size(), 0);
+        assert_eq!(empty.num_chunks(), 0);
+    }
+
+    #[fbinit::test]
+    async fn chunked_into_bytes(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let blobstore = Memblob::default();
+        borrowed!(ctx, blobstore);
+
+        let parts: [&'static [u8]; 3] = [b"first chunk, ", b"second, ", b"and the third"];
+        let mut pointers = Vec::new();
+        for part in parts {
+            let chunk = ContentChunk::new_bytes(Bytes::from_static(part));
+            let chunk_id = chunk.into_blob().store(ctx, blobstore).await?;
+            pointers.push(ContentChunkPointer::new(chunk_id, part.len() as u64));
+        }
+        let expected = Bytes::from(parts.concat());
+        let content_id = FileContents::content_id_for_bytes(&expected);
+
+        for concurrency in [0, 1, 3] {
+            let chunked = ChunkedFileContents::new(content_id, pointers.clone());
+            assert_eq!(
+                chunked.into_bytes(ctx, blobstore, concurrency).await?,
+                expected
+            );
+        }
+
+        // A pointer with the wrong size makes the whole reconstruction fail.
+        let mut bad_pointers = pointers.clone();
+        bad_pointers[1] = ContentChunkPointer::new(bad_pointers[1].chunk_id(), 100);
+        let chunked = ChunkedFileContents::new(content_id, bad_pointers);
+        let err = chunked.into_bytes(ctx, blobstore, 3).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::ChunkSizeMismatch {
+                expected: 100,
+                actual: 8,
+                ..
+            })
+        ));
+
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn chunk_pointer_verify(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let blobstore = Memblob::default();
+        borrowed!(ctx, blobstore);
+
+        let chunk = ContentChunk::new_bytes(Bytes::from_static(b"chunk data"));
+        let chunk_id = chunk.clone().into_blob().store(ctx, blobstore).await?;
+
+        let pointer = ContentChunkPointer::new(chunk_id, 10);
+        assert_eq!(pointer.verify(ctx, blobstore).await?, chunk);
+
+        let truncated = ContentChunkPointer::new(chunk_id, 11);
+        let err = truncated.verify(ctx, blobstore).await.unwrap_err();
+        match err.downcast_ref::<ErrorKind>() {
+            Some(ErrorKind::ChunkSizeMismatch {
+                chunk_id: id,
+                expected: 11,
+                actual: 10,
+            }) => assert_eq!(*id, chunk_id),
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        Ok(())
+    }
 }
@@ -90,6 +90,25 @@ impl Fsnode {
         &self.summary
     }
 
+    /// Total size of all files in this directory and its sub-directories, as recorded in the
+    /// summary.
+    pub fn total_size(&self) -> u64 {
+        self.summary.descendant_files_total_size
+    }
+
+    /// Number of files in this directory and its sub-directories, as recorded in the summary.
+    pub fn descendant_file_count(&self) -> u64 {
+        self.summary.descendant_files_count
+    }
+
+    /// Content ids of the files directly in this directory.
+    pub fn content_ids(&self) -> impl Iterator<Item = ContentId> + '_ {
+        self.subentries.values().filter_map(|entry| match entry {
+            FsnodeEntry::File(file) => Some(*file.content_id()),
+            FsnodeEntry::Directory(_) => None,
+        })
+    }
+
     pub(crate) fn from_thrift(t: thrift::Fsnode) -> Result<Fsnode> {
         let subentries = t
             .subentries
@@ -332,3 +351,66 @@ impl BlobstoreValue for Fsnode {
         Self::from_bytes(blob.data().as_ref())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use sorted_vector_map::sorted_vector_map;
+
+    use super::*;
+    use crate::hash::Blake2;
+
+    fn summary(
+        child_files: (u64, u64),
+        child_dirs: u64,
+        descendant_files: (u64, u64),
+    ) -> FsnodeSummary {
+        FsnodeSummary {
+            simple_format_sha1: Sha1::from_byte_array([0; 20]),
+            simple_format_sha256: Sha256::from_byte_array([0; 32]),
+            child_files_count: child_files.0,
+            child_files_total_size: child_files.1,
+            child_dirs_count: child_dirs,
+            descendant_files_count: descendant_files.0,
+            descendant_files_total_size: descendant_files.1,
+        }
+    }
+
+    fn file(id: u8, size: u64) -> FsnodeEntry {
+        FsnodeEntry::File(FsnodeFile::new(
+            ContentId::new(Blake2::from_byte_array([id; 32])),
+            FileType::Regular,
+            size,
+            Sha1::from_byte_array([id; 20]),
+            Sha256::from_byte_array([id; 32]),
+        ))
+    }
+
+    #[test]
+    fn test_summary_accessors() {
+        let element = |name: &str| MPathElement::new(name.as_bytes().to_vec()).unwrap();
+
+        // The sub-directory holds three files totalling 300 bytes.
+        let dir = FsnodeEntry::Directory(FsnodeDirectory::new(
+            FsnodeId::from_byte_array([9; 32]),
+            summary((3, 300), 0, (3, 300)),
+        ));
+        let fsnode = Fsnode::new(
+            sorted_vector_map! {
+                element("a") => file(1, 10),
+                element("dir") => dir,
+                element("b") => file(2, 20),
+            },
+            summary((2, 30), 1, (5, 330)),
+        );
+
+        assert_eq!(fsnode.total_size(), 330);
+        assert_eq!(fsnode.descendant_file_count(), 5);
+        assert_eq!(
+            fsnode.content_ids().collect::<Vec<_>>(),
+            vec![
+                ContentId::new(Blake2::from_byte_array([1; 32])),
+                ContentId::new(Blake2::from_byte_array([2; 32])),
+            ]
+        );
+    }
+}
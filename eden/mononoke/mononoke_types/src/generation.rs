@@ -59,3 +59,52 @@ impl Generation {
         Generation::new(self_gen + value)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_difference_from() {
+        let gen5 = Generation::new(5);
+        assert_eq!(gen5.difference_from(FIRST_GENERATION), Some(4));
+        assert_eq!(gen5.difference_from(gen5), Some(0));
+        assert_eq!(gen5.difference_from(Generation::new(6)), None);
+        assert_eq!(FIRST_GENERATION.difference_from(FIRST_GENERATION), Some(0));
+        assert_eq!(FIRST_GENERATION.difference_from(Generation::new(2)), None);
+        assert_eq!(
+            Generation::max_gen().difference_from(FIRST_GENERATION),
+            Some(u64::MAX - 1)
+        );
+    }
+
+    #[test]
+    fn test_checked_sub() {
+        let gen5 = Generation::new(5);
+        assert_eq!(gen5.checked_sub(4), Some(FIRST_GENERATION));
+        // Generation 0 is not a valid generation number.
+        assert_eq!(gen5.checked_sub(5), None);
+        assert_eq!(gen5.checked_sub(6), None);
+        assert_eq!(FIRST_GENERATION.checked_sub(1), None);
+    }
+
+    #[test]
+    fn test_add() {
+        assert_eq!(FIRST_GENERATION.add(0), FIRST_GENERATION);
+        assert_eq!(FIRST_GENERATION.add(4), Generation::new(5));
+        assert_eq!(
+            FIRST_GENERATION.add(4).difference_from(FIRST_GENERATION),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(FIRST_GENERATION < Generation::new(2));
+        assert!(Generation::new(2) < Generation::max_gen());
+        assert_eq!(
+            std::cmp::max(FIRST_GENERATION, Generation::new(3)),
+            Generation::new(3)
+        );
+    }
+}
@@ -5,6 +5,7 @@
  * GNU General Public License version 2.
  */
 
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fmt::Display;
 use std::str;
@@ -12,6 +13,7 @@ use std::str::FromStr;
 
 use abomonation_derive::Abomonation;
 use anyhow::bail;
+use anyhow::Context;
 use anyhow::Error;
 use anyhow::Result;
 use sql::mysql;
@@ -42,6 +44,30 @@ impl Globalrev {
         self.0
     }
 
+    /// The Globalrev immediately following this one.
+    #[inline]
+    pub fn next(&self) -> Self {
+        Self(self.0 + 1)
+    }
+
+    /// The Globalrev immediately preceding this one, or `None` if this is the
+    /// smallest possible Globalrev.
+    #[inline]
+    pub fn prev(&self) -> Option<Self> {
+        self.0.checked_sub(1).map(Self)
+    }
+
+    /// The decimal form of this Globalrev, left-padded with zeros to at least `width` digits.
+    /// Values that already have `width` digits or more are not truncated.
+    pub fn to_padded_string(&self, width: usize) -> String {
+        format!("{:0width$}", self.0, width = width)
+    }
+
+    /// Iterate over the half-open range of Globalrevs `[start, end)`.
+    pub fn range(start: Globalrev, end: Globalrev) -> impl Iterator<Item = Globalrev> {
+        (start.0..end.0).map(Self)
+    }
+
     // ex. svn:uuid/path@1234
     pub fn parse_svnrev(svnrev: &str) -> Result<u64> {
         let at_pos = svnrev
@@ -51,6 +77,27 @@ impl Globalrev {
         Ok(result)
     }
 
+    /// Read the globalrev from Mercurial changeset extras, where it is stored as a decimal
+    /// string under `GLOBALREV_EXTRA`. Returns `None` if there is no globalrev extra.
+    pub fn from_changeset_extra(extras: &BTreeMap<String, Vec<u8>>) -> Result<Option<Self>> {
+        match extras.get(GLOBALREV_EXTRA) {
+            Some(value) => {
+                let globalrev = str::from_utf8(value)
+                    .ok()
+                    .and_then(|value| value.parse::<Globalrev>().ok())
+                    .with_context(|| {
+                        format!(
+                            "Malformed {} extra: {:?}",
+                            GLOBALREV_EXTRA,
+                            String::from_utf8_lossy(value)
+                        )
+                    })?;
+                Ok(Some(globalrev))
+            }
+            None => Ok(None),
+        }
+    }
+
     pub fn from_bcs(bcs: &BonsaiChangeset) -> Result<Self> {
         match (
             bcs.extra().find(|(key, _)| key == &GLOBALREV_EXTRA),
@@ -95,3 +142,64 @@ impl FromStr for Globalrev {
         u64::from_str(s).map(Globalrev::new)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_next_prev() {
+        let rev = Globalrev::new(5);
+        assert_eq!(rev.next(), Globalrev::new(6));
+        assert_eq!(rev.prev(), Some(Globalrev::new(4)));
+        assert_eq!(rev.next().prev(), Some(rev));
+        assert_eq!(Globalrev::new(0).prev(), None);
+    }
+
+    #[test]
+    fn test_to_padded_string() {
+        assert_eq!(Globalrev::new(42).to_padded_string(6), "000042");
+        assert_eq!(Globalrev::new(0).to_padded_string(3), "000");
+        assert_eq!(Globalrev::new(123456).to_padded_string(6), "123456");
+        assert_eq!(Globalrev::new(1234567).to_padded_string(3), "1234567");
+        assert_eq!(Globalrev::new(42).to_padded_string(0), "42");
+    }
+
+    #[test]
+    fn test_range() {
+        let revs: Vec<_> = Globalrev::range(Globalrev::new(3), Globalrev::new(6)).collect();
+        assert_eq!(
+            revs,
+            vec![Globalrev::new(3), Globalrev::new(4), Globalrev::new(5)]
+        );
+
+        assert_eq!(
+            Globalrev::range(Globalrev::new(3), Globalrev::new(3)).count(),
+            0
+        );
+        assert_eq!(
+            Globalrev::range(Globalrev::new(6), Globalrev::new(3)).count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_from_changeset_extra() -> Result<()> {
+        let mut extras = BTreeMap::new();
+        extras.insert("branch".to_owned(), b"default".to_vec());
+        assert_eq!(Globalrev::from_changeset_extra(&extras)?, None);
+
+        extras.insert(GLOBALREV_EXTRA.to_owned(), b"1000147970".to_vec());
+        assert_eq!(
+            Globalrev::from_changeset_extra(&extras)?,
+            Some(Globalrev::new(1000147970))
+        );
+
+        for malformed in [&b"abc"[..], &b""[..], &b"-1"[..], &b"\xff"[..]] {
+            extras.insert(GLOBALREV_EXTRA.to_owned(), malformed.to_vec());
+            assert!(Globalrev::from_changeset_extra(&extras).is_err());
+        }
+
+        Ok(())
+    }
+}
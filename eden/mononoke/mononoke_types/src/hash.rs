@@ -177,6 +177,34 @@ impl Context {
     }
 }
 
+/// Streaming hasher for computing an unkeyed `Blake2` over data that arrives in pieces, e.g.
+/// the chunks of a large file. The result is the same as hashing the concatenated input in one go.
+#[derive(Clone)]
+pub struct Blake2Context(Context);
+
+impl Blake2Context {
+    #[inline]
+    pub fn new() -> Self {
+        Blake2Context(Context::new(b""))
+    }
+
+    #[inline]
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data)
+    }
+
+    #[inline]
+    pub fn finalize(self) -> Blake2 {
+        self.0.finish()
+    }
+}
+
+impl Default for Blake2Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Get a reference to the underlying bytes of a `Blake2`
 impl AsRef<[u8]> for Blake2 {
     fn as_ref(&self) -> &[u8] {
@@ -639,6 +667,32 @@ mod test {
         assert_eq!(nil, NILHASH);
     }
 
+    #[test]
+    fn test_streaming_nil() {
+        assert_eq!(Blake2Context::new().finalize(), NILHASH);
+    }
+
+    quickcheck! {
+        fn streaming_matches_one_shot(chunk_sizes: Vec<u16>) -> bool {
+            let data: Vec<u8> = (0..10 * 1024).map(|i| (i % 251) as u8).collect();
+
+            let mut one_shot = Context::new(b"");
+            one_shot.update(&data);
+            let expected = one_shot.finish();
+
+            let mut streaming = Blake2Context::new();
+            let mut remaining = &data[..];
+            for size in chunk_sizes {
+                let (chunk, rest) = remaining.split_at((size as usize).min(remaining.len()));
+                streaming.update(chunk);
+                remaining = rest;
+            }
+            streaming.update(remaining);
+
+            streaming.finalize() == expected
+        }
+    }
+
     #[test]
     fn parse_ok() {
         assert_eq!(
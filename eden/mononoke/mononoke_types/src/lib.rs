@@ -49,8 +49,12 @@ pub use blob::RawBundle2Blob;
 pub use blobstore::BlobstoreBytes;
 pub use bonsai_changeset::BonsaiChangeset;
 pub use bonsai_changeset::BonsaiChangesetMut;
+pub use bonsai_changeset::FileChangeDiff;
+pub use content_chunk::compute_chunk_id;
 pub use content_chunk::ContentChunk;
+pub use content_metadata::AliasComputer;
 pub use content_metadata::ContentAlias;
+pub use content_metadata::ContentAliasIndex;
 pub use content_metadata::ContentMetadata;
 pub use datetime::DateTime;
 pub use datetime::Timestamp;
@@ -61,22 +65,28 @@ pub use file_change::TrackedFileChange;
 pub use file_contents::ChunkedFileContents;
 pub use file_contents::ContentChunkPointer;
 pub use file_contents::FileContents;
+pub use file_contents::FileContentsPlan;
 pub use generation::Generation;
 pub use generation::FIRST_GENERATION;
 pub use globalrev::Globalrev;
 pub use path::check_case_conflicts;
+pub use path::check_case_conflicts_with_normalization;
+pub use path::check_path_depth;
 pub use path::mpath_element_iter;
 pub use path::path_bytes_from_mpath;
+pub use path::CaseNormalization;
 pub use path::MPath;
 pub use path::MPathElement;
 pub use path::MPathHash;
 pub use path::PrefixTrie;
 pub use path::RepoPath;
 pub use rawbundle2::RawBundle2;
+pub use redaction_key_list::RedactionDiff;
 pub use redaction_key_list::RedactionKeyList;
 pub use repo::RepositoryId;
 pub use repo::REPO_PREFIX_REGEX;
 pub use svnrev::Svnrev;
+pub use svnrev::SvnrevMap;
 pub use thrift_convert::ThriftConvert;
 pub use typed_hash::BasenameSuffixSkeletonManifestId;
 pub use typed_hash::BlobstoreKey;
@@ -90,10 +100,12 @@ pub use typed_hash::DeletedManifestV2Id;
 pub use typed_hash::FastlogBatchId;
 pub use typed_hash::FileUnodeId;
 pub use typed_hash::FsnodeId;
+pub use typed_hash::HashEncoding;
 pub use typed_hash::ManifestUnodeId;
 pub use typed_hash::MononokeId;
 pub use typed_hash::RawBundle2Id;
 pub use typed_hash::SkeletonManifestId;
+pub use typed_hash::TypedHashRef;
 
 mod macros;
 
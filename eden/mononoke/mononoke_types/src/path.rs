@@ -29,9 +29,12 @@ use regex::Regex;
 use serde_derive::Deserialize;
 use serde_derive::Serialize;
 use smallvec::SmallVec;
+use unicode_normalization::UnicodeNormalization;
 
 use crate::bonsai_changeset::BonsaiChangeset;
 use crate::errors::ErrorKind;
+use crate::errors::MPathElementError;
+use crate::errors::TooDeepError;
 use crate::hash::Blake2;
 use crate::hash::Context;
 use crate::thrift;
@@ -233,61 +236,46 @@ impl MPathElement {
         Ok(MPathElement(element.0))
     }
 
+    /// Create a path element, reporting why it is invalid if it is rejected.
+    pub fn new_checked(element: Vec<u8>) -> Result<MPathElement, MPathElementError> {
+        Self::check(&element)?;
+        Ok(MPathElement(SmallVec::from(element)))
+    }
+
     fn verify(p: &[u8]) -> Result<()> {
+        Self::check(p).map_err(|e| {
+            ErrorKind::InvalidPath(String::from_utf8_lossy(p).into_owned(), e.to_string()).into()
+        })
+    }
+
+    fn check(p: &[u8]) -> Result<(), MPathElementError> {
         if p.is_empty() {
-            bail!(ErrorKind::InvalidPath(
-                "".into(),
-                "path elements cannot be empty".into()
-            ));
+            return Err(MPathElementError::Empty);
         }
         if p.contains(&0) {
-            bail!(ErrorKind::InvalidPath(
-                String::from_utf8_lossy(p).into_owned(),
-                "path elements cannot contain '\\0'".into(),
-            ));
+            return Err(MPathElementError::ContainsNull);
         }
         if p.contains(&1) {
             // MPath can not contain '\x01', in particular if mpath ends with '\x01'
             // and it is part of move metadata, because key-value pairs are separated
             // by '\n', you will get '\x01\n' which is also metadata separator.
-            bail!(ErrorKind::InvalidPath(
-                String::from_utf8_lossy(p).into_owned(),
-                "path elements cannot contain '\\1'".into(),
-            ));
+            return Err(MPathElementError::ContainsMetadataSeparator);
         }
         if p.contains(&b'/') {
-            bail!(ErrorKind::InvalidPath(
-                String::from_utf8_lossy(p).into_owned(),
-                "path elements cannot contain '/'".into(),
-            ));
+            return Err(MPathElementError::ContainsSlash);
         }
         if p.contains(&b'\n') {
-            bail!(ErrorKind::InvalidPath(
-                String::from_utf8_lossy(p).into_owned(),
-                "path elements cannot contain '\\n'".into(),
-            ));
+            return Err(MPathElementError::ContainsNewline);
         }
         if p == b"." || p == b".." {
-            bail!(ErrorKind::InvalidPath(
-                String::from_utf8_lossy(p).into_owned(),
-                "path elements cannot be . or .. to avoid traversal attacks".into(),
-            ));
+            return Err(MPathElementError::DotOrDotDot);
         }
-        Self::check_len(p)?;
-        Ok(())
-    }
-
-    fn check_len(p: &[u8]) -> Result<()> {
         if p.len() > MPATH_ELEMENT_MAX_LENGTH {
-            bail!(ErrorKind::InvalidPath(
-                String::from_utf8_lossy(p).into_owned(),
-                format!(
-                    "path elements cannot exceed {} bytes",
-                    MPATH_ELEMENT_MAX_LENGTH
-                )
-            ));
+            return Err(MPathElementError::TooLong {
+                len: p.len(),
+                max: MPATH_ELEMENT_MAX_LENGTH,
+            });
         }
-
         Ok(())
     }
 
@@ -369,6 +357,16 @@ impl MPathElement {
         self.0.ends_with(potential_suffix)
     }
 
+    /// The bytes after the last `.` in this path element, if there are any. Like
+    /// `std::path::Path::extension`, a leading `.` doesn't start an extension, so `.gitignore`
+    /// has none, but `archive.tar.gz` has `gz`.
+    pub fn extension(&self) -> Option<&[u8]> {
+        match self.0.iter().rposition(|b| *b == b'.') {
+            None | Some(0) => None,
+            Some(pos) => Some(&self.0[pos + 1..]),
+        }
+    }
+
     /// Reverse this path element inplace
     pub fn reverse(&mut self) {
         self.0.reverse()
@@ -551,6 +549,16 @@ impl MPath {
         self.elements.len()
     }
 
+    /// The depth of this path, i.e. the number of elements. `foo` has depth 1.
+    pub fn depth(&self) -> usize {
+        self.num_components()
+    }
+
+    /// Whether this path is no deeper than `max`.
+    pub fn is_within_depth(&self, max: usize) -> bool {
+        self.depth() <= max
+    }
+
     /// The number of leading components that are common.
     pub fn common_components<'a, E: IntoIterator<Item = &'a MPathElement>>(
         &self,
@@ -577,6 +585,11 @@ impl MPath {
             .expect("MPaths have at least one component")
     }
 
+    /// The extension of the final component of this path. See `MPathElement::extension`.
+    pub fn extension(&self) -> Option<&[u8]> {
+        self.basename().extension()
+    }
+
     /// Create a new path with the number of leading components specified.
     pub fn take_prefix_components(&self, components: usize) -> Result<Option<MPath>> {
         match components {
@@ -615,6 +628,14 @@ impl MPath {
         }
     }
 
+    /// The rest of this path after `base`, like `std::path::Path::strip_prefix`. Returns `None`
+    /// if this path is not under `base`. Because an `MPath` can't be empty, this also returns
+    /// `None` if this path is equal to `base`; use `is_prefix_of` to tell that case apart.
+    /// `foo/bar/baz` stripped of `foo` is `bar/baz`, but `foo1/bar` is not under `foo`.
+    pub fn strip_prefix(&self, base: &MPath) -> Option<MPath> {
+        self.remove_prefix_component(base)
+    }
+
     pub fn generate<W: Write>(&self, out: &mut W) -> io::Result<()> {
         out.write_all(&self.to_vec())
     }
@@ -624,6 +645,44 @@ impl MPath {
         ret.join(&b'/')
     }
 
+    /// Encode this path as a string that is safe to use in a shell or as a filename. Bytes other
+    /// than ASCII letters, digits, `.`, `_`, `-` and the `/` separators are percent-encoded.
+    /// Reversed by `MPath::from_safe_string`.
+    pub fn to_safe_string(&self) -> String {
+        let mut out = String::with_capacity(self.len());
+        for b in self.to_vec() {
+            if b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'/' | b'-') {
+                out.push(b as char);
+            } else {
+                out.push_str(&format!("%{:02X}", b));
+            }
+        }
+        out
+    }
+
+    /// Decode a path encoded by `MPath::to_safe_string`.
+    pub fn from_safe_string(s: &str) -> Result<MPath> {
+        let invalid = |reason: &str| ErrorKind::InvalidPath(s.to_string(), reason.to_string());
+        let mut bytes = Vec::with_capacity(s.len());
+        let mut input = s.bytes();
+        while let Some(b) = input.next() {
+            if b != b'%' {
+                bytes.push(b);
+                continue;
+            }
+            let hex = [
+                input.next().ok_or_else(|| invalid("truncated escape"))?,
+                input.next().ok_or_else(|| invalid("truncated escape"))?,
+            ];
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return Err(invalid("invalid escape").into());
+            }
+            let hex = std::str::from_utf8(&hex).expect("hex digits are ASCII");
+            bytes.push(u8::from_str_radix(hex, 16).expect("checked for hex digits"));
+        }
+        MPath::new(bytes)
+    }
+
     #[allow(clippy::len_without_is_empty)]
     /// The length of this path, including any slashes in it.
     pub fn len(&self) -> usize {
@@ -660,6 +719,20 @@ impl MPath {
         }
     }
 
+    /// The path with its last element removed, or `None` for a single-element path
+    pub fn parent(&self) -> Option<MPath> {
+        self.split_dirname().0
+    }
+
+    /// Get an iterator over the ancestors of this `MPath`, from the immediate
+    /// parent up to the top-level element. Unlike `into_parent_dir_iter`, it
+    /// does not contain `self`.
+    pub fn ancestors(&self) -> impl Iterator<Item = MPath> {
+        self.parent()
+            .into_iter()
+            .flat_map(MPath::into_parent_dir_iter)
+    }
+
     /// Split an MPath into first path component and the rest
     pub fn split_first(&self) -> (&MPathElement, Option<MPath>) {
         let (first, file_elements) = self
@@ -987,73 +1060,85 @@ impl fmt::Debug for MPath {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum PrefixTrie {
-    Included,
-    Children(HashMap<MPathElement, PrefixTrie>),
+/// A set of path prefixes. Prefixes nested beneath other prefixes are kept, so that the deepest
+/// prefix of a path can be found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrefixTrie {
+    /// Whether the path leading to this node was added as a prefix.
+    included: bool,
+    children: HashMap<MPathElement, PrefixTrie>,
 }
 
 impl PrefixTrie {
     /// Create a new, empty, prefix trie.
     pub fn new() -> PrefixTrie {
-        PrefixTrie::Children(HashMap::new())
+        PrefixTrie::default()
+    }
+
+    /// Create a prefix trie containing the empty prefix, and so all paths.
+    pub fn everything() -> PrefixTrie {
+        PrefixTrie {
+            included: true,
+            children: HashMap::new(),
+        }
     }
 
     /// Add a path prefix to the prefix trie.  Returns true if the prefix
-    /// wasn't already present.
+    /// wasn't already covered by a prefix in the trie.
     pub fn add<'p, P: IntoIterator<Item = &'p MPathElement>>(&mut self, path: P) -> bool {
-        match self {
-            PrefixTrie::Included => false,
-            PrefixTrie::Children(children) => {
-                let mut iter = path.into_iter();
-                match iter.next() {
-                    None => {
-                        *self = PrefixTrie::Included;
-                        true
-                    }
-                    Some(element) => {
-                        if let Some(child) = children.get_mut(element) {
-                            return child.add(iter);
-                        }
-                        children
-                            .entry(element.clone())
-                            .or_insert_with(PrefixTrie::new)
-                            .add(iter)
-                    }
-                }
-            }
+        let mut node = self;
+        let mut covered = false;
+        for element in path {
+            covered |= node.included;
+            node = node
+                .children
+                .entry(element.clone())
+                .or_insert_with(PrefixTrie::new);
         }
+        covered |= node.included;
+        node.included = true;
+        !covered
     }
 
     /// Returns true if any path prefix of the given path has previously been
     /// added to the prefix trie.
     pub fn contains_prefix<'p, P: IntoIterator<Item = &'p MPathElement>>(&self, path: P) -> bool {
-        match self {
-            PrefixTrie::Included => true,
-            PrefixTrie::Children(children) => {
-                let mut iter = path.into_iter();
-                match iter.next() {
-                    None => false,
-                    Some(element) => {
-                        if let Some(child) = children.get(element) {
-                            return child.contains_prefix(iter);
-                        }
-                        false
-                    }
-                }
+        let mut node = self;
+        for element in path {
+            if node.included {
+                return true;
+            }
+            match node.children.get(element) {
+                Some(child) => node = child,
+                None => return false,
             }
         }
+        node.included
     }
 
     /// Returns true if this trie contains all paths.
     pub fn contains_everything(&self) -> bool {
-        self == &PrefixTrie::Included
+        self.included
     }
-}
 
-impl Default for PrefixTrie {
-    fn default() -> PrefixTrie {
-        PrefixTrie::Children(HashMap::new())
+    /// Returns the deepest stored prefix that is an ancestor of (or equal to)
+    /// the given path, if any.
+    ///
+    /// The empty prefix cannot be represented as an `MPath`, so this returns
+    /// `None` if it is the only stored prefix of the path.
+    pub fn longest_prefix(&self, path: &MPath) -> Option<MPath> {
+        let mut node = self;
+        let mut deepest = 0;
+        for (depth, element) in path.into_iter().enumerate() {
+            match node.children.get(element) {
+                Some(child) => node = child,
+                None => break,
+            }
+            if node.included {
+                deepest = depth + 1;
+            }
+        }
+        (deepest > 0).then(|| MPath::from_elements(path.into_iter().take(deepest)))
     }
 }
 
@@ -1064,12 +1149,20 @@ impl Extend<Option<MPath>> for PrefixTrie {
                 self.add(&path);
             } else {
                 // The empty path means all paths are included.
-                *self = PrefixTrie::Included;
+                self.included = true;
             }
         }
     }
 }
 
+impl Extend<MPath> for PrefixTrie {
+    fn extend<T: IntoIterator<Item = MPath>>(&mut self, iter: T) {
+        for path in iter {
+            self.add(&path);
+        }
+    }
+}
+
 impl FromIterator<Option<MPath>> for PrefixTrie {
     fn from_iter<I: IntoIterator<Item = Option<MPath>>>(iter: I) -> Self {
         let mut trie = PrefixTrie::new();
@@ -1078,16 +1171,48 @@ impl FromIterator<Option<MPath>> for PrefixTrie {
     }
 }
 
+/// How path elements are folded before being compared for case conflicts.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum CaseNormalization {
+    /// Lowercase the element as-is. This is what `check_case_conflicts` uses.
+    AsciiFold,
+    /// Normalize the element to Unicode NFC before lowercasing it, so that
+    /// composed and decomposed forms of the same name conflict (as they do on
+    /// APFS).
+    UnicodeCaseFoldNfc,
+}
+
+impl CaseNormalization {
+    /// Returns the folded form of this element, or `None` if it is not valid
+    /// UTF-8 (in which case it cannot conflict with anything).
+    fn fold(self, element: &MPathElement) -> Option<String> {
+        match self {
+            CaseNormalization::AsciiFold => element.to_lowercase_utf8(),
+            CaseNormalization::UnicodeCaseFoldNfc => {
+                let s = std::str::from_utf8(element.as_ref()).ok()?;
+                let s: String = s.nfc().collect::<String>().to_lowercase();
+                Some(s.nfc().collect())
+            }
+        }
+    }
+}
+
 pub struct CaseConflictTrie {
     children: HashMap<MPathElement, CaseConflictTrie>,
     lowercase_to_original: HashMap<String, MPathElement>,
+    normalization: CaseNormalization,
 }
 
 impl CaseConflictTrie {
     fn new() -> CaseConflictTrie {
+        CaseConflictTrie::with_normalization(CaseNormalization::AsciiFold)
+    }
+
+    fn with_normalization(normalization: CaseNormalization) -> CaseConflictTrie {
         CaseConflictTrie {
             children: HashMap::new(),
             lowercase_to_original: HashMap::new(),
+            normalization,
         }
     }
 
@@ -1112,7 +1237,7 @@ impl CaseConflictTrie {
                     });
                 }
 
-                if let Some(lower) = element.to_lowercase_utf8() {
+                if let Some(lower) = self.normalization.fold(element) {
                     if let Some(conflict) = self.lowercase_to_original.get(&lower) {
                         return Err(ReverseMPath {
                             elements: vec![conflict.clone()],
@@ -1122,9 +1247,10 @@ impl CaseConflictTrie {
                     }
                 }
 
+                let normalization = self.normalization;
                 self.children
                     .entry(element.clone())
-                    .or_insert_with(CaseConflictTrie::new)
+                    .or_insert_with(|| CaseConflictTrie::with_normalization(normalization))
                     .add(iter)
             }
         }
@@ -1145,7 +1271,7 @@ impl CaseConflictTrie {
                 if remove {
                     self.children.remove(element);
 
-                    if let Some(lower) = element.to_lowercase_utf8() {
+                    if let Some(lower) = self.normalization.fold(element) {
                         self.lowercase_to_original.remove(&lower);
                     }
                 }
@@ -1211,6 +1337,23 @@ impl<'a> CaseConflictTrieUpdate for &'a BonsaiChangeset {
     }
 }
 
+/// Checks that none of `paths` is deeper than `max`, reporting the first one that is.
+pub fn check_path_depth<'a, I>(paths: I, max: usize) -> Result<(), TooDeepError>
+where
+    I: IntoIterator<Item = &'a MPath>,
+{
+    for path in paths {
+        if !path.is_within_depth(max) {
+            return Err(TooDeepError {
+                path: path.clone(),
+                depth: path.depth(),
+                max,
+            });
+        }
+    }
+    Ok(())
+}
+
 /// Returns first path pair that would introduce a case-conflict, if any. The first element is the
 /// first one that was added into the Trie, and the second is the last.
 pub fn check_case_conflicts<P, I>(iter: I) -> Option<(MPath, MPath)>
@@ -1218,7 +1361,20 @@ where
     P: CaseConflictTrieUpdate,
     I: IntoIterator<Item = P>,
 {
-    let mut trie = CaseConflictTrie::new();
+    check_case_conflicts_with_normalization(iter, CaseNormalization::AsciiFold)
+}
+
+/// Like `check_case_conflicts`, but folds path elements according to `normalization` before
+/// comparing them.
+pub fn check_case_conflicts_with_normalization<P, I>(
+    iter: I,
+    normalization: CaseNormalization,
+) -> Option<(MPath, MPath)>
+where
+    P: CaseConflictTrieUpdate,
+    I: IntoIterator<Item = P>,
+{
+    let mut trie = CaseConflictTrie::with_normalization(normalization);
     for update in iter {
         let conflict = update.apply(&mut trie);
         if conflict.is_some() {
@@ -1276,6 +1432,26 @@ mod test {
         );
     }
 
+    #[test]
+    fn path_safe_string() {
+        let path = MPath::new("dir/100% done.txt").unwrap();
+        assert_eq!(path.to_safe_string(), "dir/100%25%20done.txt");
+        assert_eq!(
+            MPath::from_safe_string("dir/100%25%20done.txt").unwrap(),
+            path
+        );
+
+        let path = MPath::new(b"tab\there/\xff\x02").unwrap();
+        assert_eq!(path.to_safe_string(), "tab%09there/%FF%02");
+        assert_eq!(MPath::from_safe_string("tab%09there/%ff%02").unwrap(), path);
+
+        assert!(MPath::from_safe_string("dir/100%").is_err());
+        assert!(MPath::from_safe_string("dir/100%2").is_err());
+        assert!(MPath::from_safe_string("dir/100%zz").is_err());
+        assert!(MPath::from_safe_string("dir/100%+1").is_err());
+        assert!(MPath::from_safe_string("").is_err());
+    }
+
     quickcheck! {
         /// Verify that instances generated by quickcheck are valid.
         fn path_gen(p: MPath) -> bool {
@@ -1307,6 +1483,12 @@ mod test {
             p.len() == p.to_vec().len()
         }
 
+        fn path_safe_string_roundtrip(p: MPath) -> bool {
+            let encoded = p.to_safe_string();
+            encoded.bytes().all(|b| b.is_ascii_alphanumeric() || b"._/-%".contains(&b))
+                && MPath::from_safe_string(&encoded).unwrap() == p
+        }
+
         fn repo_path_thrift_roundtrip(p: RepoPath) -> bool {
             let thrift_path = p.clone().into_thrift();
             let p2 = RepoPath::from_thrift(thrift_path)
@@ -1379,6 +1561,34 @@ mod test {
         );
     }
 
+    #[test]
+    fn parent_and_ancestors() {
+        fn path(p: &str) -> MPath {
+            MPath::new(p).unwrap()
+        }
+
+        fn ancestors_vec(p: &str) -> Vec<MPath> {
+            path(p).ancestors().collect()
+        }
+
+        assert_eq!(path("a").parent(), None);
+        assert_eq!(path("a/b").parent(), Some(path("a")));
+        assert_eq!(path("a/b/c/d/e").parent(), Some(path("a/b/c/d")));
+
+        assert_eq!(ancestors_vec("a"), Vec::<MPath>::new());
+        assert_eq!(ancestors_vec("a/b"), vec![path("a")]);
+        assert_eq!(
+            ancestors_vec("a/b/c/d/e"),
+            vec![path("a/b/c/d"), path("a/b/c"), path("a/b"), path("a")]
+        );
+
+        let elem = MPathElement::new(b"f".to_vec()).unwrap();
+        let joined = path("a/b/c/d/e").join_element(Some(&elem));
+        assert_eq!(joined, path("a/b/c/d/e/f"));
+        assert_eq!(joined.parent(), Some(path("a/b/c/d/e")));
+        assert_eq!(path("a").join_element(None), path("a"));
+    }
+
     #[test]
     fn components() {
         let foo = MPath::new("foo").unwrap();
@@ -1420,6 +1630,24 @@ mod test {
         assert_eq!(foo_bar12.remove_prefix_component(&foo_bar1), Some(two));
     }
 
+    #[test]
+    fn strip_prefix() {
+        let path = |p| MPath::new(p).unwrap();
+        let file = path("dir/sub/file");
+
+        assert_eq!(file.strip_prefix(&path("dir")), Some(path("sub/file")));
+        assert_eq!(file.strip_prefix(&path("dir/sub")), Some(path("file")));
+
+        // Only whole components match.
+        assert_eq!(file.strip_prefix(&path("di")), None);
+        assert_eq!(file.strip_prefix(&path("other")), None);
+        assert_eq!(file.strip_prefix(&path("dir/sub/file/more")), None);
+
+        // Stripping the whole path would leave nothing, which isn't a valid MPath.
+        assert_eq!(file.strip_prefix(&file), None);
+        assert!(file.is_prefix_of(&file));
+    }
+
     #[test]
     fn bad_path() {
         assert!(MPath::new(b"\0").is_err());
@@ -1450,6 +1678,65 @@ mod test {
         assert!(MPathElement::new(p).is_err());
     }
 
+    #[test]
+    fn path_element_errors() {
+        let cases: Vec<(&[u8], MPathElementError)> = vec![
+            (&b""[..], MPathElementError::Empty),
+            (&b"foo\0bar"[..], MPathElementError::ContainsNull),
+            (
+                &b"foo\x01"[..],
+                MPathElementError::ContainsMetadataSeparator,
+            ),
+            (&b"foo/bar"[..], MPathElementError::ContainsSlash),
+            (&b"foo\nbar"[..], MPathElementError::ContainsNewline),
+            (&b"."[..], MPathElementError::DotOrDotDot),
+            (&b".."[..], MPathElementError::DotOrDotDot),
+            (
+                &[b'a'; 256][..],
+                MPathElementError::TooLong { len: 256, max: 255 },
+            ),
+        ];
+        for (element, expected) in cases {
+            assert_eq!(
+                MPathElement::new_checked(element.to_vec()),
+                Err(expected.clone())
+            );
+            let err = MPathElement::new(element.to_vec()).unwrap_err();
+            assert!(err.to_string().contains(&expected.to_string()));
+        }
+
+        assert_eq!(
+            MPathElement::new_checked(b"...".to_vec()),
+            Ok(MPathElement::new(b"...".to_vec()).unwrap())
+        );
+    }
+
+    #[test]
+    fn path_depth() {
+        let single = MPath::new("foo").unwrap();
+        assert_eq!(single.depth(), 1);
+        assert!(single.is_within_depth(1));
+        assert!(!single.is_within_depth(0));
+
+        let at_limit = MPath::new("a/b/c").unwrap();
+        let too_deep = MPath::new("a/b/c/d").unwrap();
+        assert_eq!(at_limit.depth(), 3);
+        assert!(at_limit.is_within_depth(3));
+        assert!(!too_deep.is_within_depth(3));
+
+        assert_eq!(check_path_depth(vec![&single, &at_limit], 3), Ok(()));
+        assert_eq!(check_path_depth(vec![], 0), Ok(()));
+        let other_too_deep = MPath::new("w/x/y/z/zz").unwrap();
+        assert_eq!(
+            check_path_depth(vec![&single, &too_deep, &other_too_deep], 3),
+            Err(TooDeepError {
+                path: too_deep,
+                depth: 4,
+                max: 3,
+            })
+        );
+    }
+
     #[test]
     fn bad_path_thrift() {
         let bad_thrift = thrift::MPath(vec![thrift::MPathElement(b"abc\0".to_vec().into())]);
@@ -1526,6 +1813,63 @@ mod test {
         );
     }
 
+    #[test]
+    fn case_conflicts_with_normalization() {
+        fn m(mpath: &str) -> MPath {
+            MPath::new(mpath).unwrap()
+        }
+
+        // "café" with a precomposed e-acute, and with e followed by a combining acute accent.
+        let composed = m("dir/caf\u{e9}");
+        let decomposed = m("dir/cafe\u{301}");
+        let upper_decomposed = m("dir/CAFE\u{301}");
+
+        let paths = vec![composed.clone(), decomposed.clone()];
+        assert_eq!(
+            check_case_conflicts_with_normalization(paths.iter(), CaseNormalization::AsciiFold),
+            None,
+        );
+        assert_eq!(
+            check_case_conflicts_with_normalization(
+                paths.iter(),
+                CaseNormalization::UnicodeCaseFoldNfc
+            ),
+            Some((composed.clone(), decomposed)),
+        );
+
+        let paths = vec![composed.clone(), upper_decomposed.clone()];
+        assert_eq!(check_case_conflicts(paths.iter()), None);
+        assert_eq!(
+            check_case_conflicts_with_normalization(
+                paths.iter(),
+                CaseNormalization::UnicodeCaseFoldNfc
+            ),
+            Some((composed, upper_decomposed)),
+        );
+
+        // Plain case conflicts are still detected in both modes.
+        let paths = vec![m("a/b\u{e9}"), m("a/B\u{c9}")];
+        for normalization in [
+            CaseNormalization::AsciiFold,
+            CaseNormalization::UnicodeCaseFoldNfc,
+        ] {
+            assert_eq!(
+                check_case_conflicts_with_normalization(paths.iter(), normalization),
+                Some((m("a/b\u{e9}"), m("a/B\u{c9}"))),
+            );
+        }
+
+        // Distinct names are not conflicts.
+        let paths = vec![m("r\u{e9}sum\u{e9}"), m("resume")];
+        assert_eq!(
+            check_case_conflicts_with_normalization(
+                paths.iter(),
+                CaseNormalization::UnicodeCaseFoldNfc
+            ),
+            None,
+        );
+    }
+
     fn check_pcf_paths<I, T>(paths: I) -> Result<()>
     where
         I: IntoIterator<Item = (T, bool)>,
@@ -1592,6 +1936,69 @@ mod test {
         assert!(prefixes.contains_everything());
     }
 
+    #[test]
+    fn prefix_trie_longest_prefix() {
+        let path = |path| MPath::new(path).unwrap();
+
+        let mut prefixes = PrefixTrie::new();
+        prefixes.extend(vec![path("a/b/c/d"), path("a/b/c"), path("x/y")]);
+
+        // The deepest of several nested prefixes wins.
+        assert_eq!(
+            prefixes.longest_prefix(&path("a/b/c/d/e")),
+            Some(path("a/b/c/d"))
+        );
+        assert_eq!(
+            prefixes.longest_prefix(&path("a/b/c/d")),
+            Some(path("a/b/c/d"))
+        );
+        assert_eq!(
+            prefixes.longest_prefix(&path("a/b/c/e")),
+            Some(path("a/b/c"))
+        );
+        assert_eq!(prefixes.longest_prefix(&path("a/b/c")), Some(path("a/b/c")));
+        assert_eq!(prefixes.longest_prefix(&path("x/y/z")), Some(path("x/y")));
+
+        // No stored ancestor.
+        assert_eq!(prefixes.longest_prefix(&path("a/b")), None);
+        assert_eq!(prefixes.longest_prefix(&path("a/bc/d")), None);
+        assert_eq!(prefixes.longest_prefix(&path("x")), None);
+        assert_eq!(prefixes.longest_prefix(&path("z")), None);
+
+        // Extending with a deeper prefix makes it the match beneath it.
+        prefixes.extend(vec![path("x/y/z/w")]);
+        assert_eq!(
+            prefixes.longest_prefix(&path("x/y/z/w/v")),
+            Some(path("x/y/z/w"))
+        );
+        assert_eq!(prefixes.longest_prefix(&path("x/y/z")), Some(path("x/y")));
+
+        // Everything is contained, but the empty prefix can't be reported.
+        prefixes.extend(vec![None::<MPath>]);
+        assert_eq!(prefixes.longest_prefix(&path("a/b")), None);
+        assert_eq!(
+            prefixes.longest_prefix(&path("a/b/c/e")),
+            Some(path("a/b/c"))
+        );
+    }
+
+    #[test]
+    fn extension() {
+        let path = |path| MPath::new(path).unwrap();
+
+        assert_eq!(path("dir/Makefile").extension(), None);
+        assert_eq!(path("dir/main.rs").extension(), Some(&b"rs"[..]));
+        assert_eq!(path("dir/.gitignore").extension(), None);
+        assert_eq!(path("dir/.config.toml").extension(), Some(&b"toml"[..]));
+        assert_eq!(path("archive.tar.gz").extension(), Some(&b"gz"[..]));
+        assert_eq!(path("trailing.").extension(), Some(&b""[..]));
+        // Only the final component counts.
+        assert_eq!(path("dir.d/file").extension(), None);
+
+        let element = MPathElement::new(b"archive.tar.gz".to_vec()).unwrap();
+        assert_eq!(element.extension(), Some(&b"gz"[..]));
+    }
+
     #[test]
     fn has_suffix_suffix() {
         let path = |path| MPath::new(path).unwrap();
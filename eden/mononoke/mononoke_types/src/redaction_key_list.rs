@@ -5,6 +5,10 @@
  * GNU General Public License version 2.
  */
 
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::hash::Hasher;
+
 use anyhow::Context;
 use anyhow::Result;
 use fbthrift::compact_protocol;
@@ -17,18 +21,96 @@ use crate::thrift;
 use crate::typed_hash::RedactionKeyListId;
 use crate::typed_hash::RedactionKeyListIdContext;
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone)]
 pub struct RedactionKeyList {
-    pub keys: Vec<String>,
+    keys: Vec<String>,
+    /// The same keys as `keys`, for constant time membership checks.
+    key_set: HashSet<String>,
 }
 
 impl RedactionKeyList {
+    pub fn new(keys: Vec<String>) -> Self {
+        let key_set = keys.iter().cloned().collect();
+        Self { keys, key_set }
+    }
+
+    /// The redacted keys, in the order in which they were stored.
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    pub fn into_keys(self) -> Vec<String> {
+        self.keys
+    }
+
+    /// Returns true if `key` is one of the redacted keys.
+    pub fn contains(&self, key: &str) -> bool {
+        self.key_set.contains(key)
+    }
+
+    /// Returns a list of the keys that are present in both `self` and `other`, in the order they
+    /// appear in `self`.
+    pub fn intersect(&self, other: &RedactionKeyList) -> RedactionKeyList {
+        Self::new(
+            self.keys
+                .iter()
+                .filter(|key| other.contains(key))
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// The keys redacted in `self` but not in `previous`, and those no longer redacted. Both
+    /// lists are sorted and have no duplicates.
+    pub fn diff(&self, previous: &RedactionKeyList) -> RedactionDiff {
+        let mut added: Vec<_> = self
+            .key_set
+            .difference(&previous.key_set)
+            .cloned()
+            .collect();
+        let mut removed: Vec<_> = previous
+            .key_set
+            .difference(&self.key_set)
+            .cloned()
+            .collect();
+        added.sort();
+        removed.sort();
+        RedactionDiff { added, removed }
+    }
+
     fn into_thrift(self) -> thrift::RedactionKeyList {
         thrift::RedactionKeyList { keys: self.keys }
     }
 
     fn from_thrift(t: thrift::RedactionKeyList) -> Result<Self> {
-        Ok(Self { keys: t.keys })
+        Ok(Self::new(t.keys))
+    }
+}
+
+/// The changes between two `RedactionKeyList`s, as returned by `RedactionKeyList::diff`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RedactionDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl RedactionDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl PartialEq for RedactionKeyList {
+    fn eq(&self, other: &Self) -> bool {
+        self.keys == other.keys
+    }
+}
+
+impl Eq for RedactionKeyList {}
+
+impl Hash for RedactionKeyList {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.keys.hash(state);
     }
 }
 
@@ -50,3 +132,71 @@ impl BlobstoreValue for RedactionKeyList {
         Self::from_thrift(thrift_tc)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key_list(keys: &[&str]) -> RedactionKeyList {
+        RedactionKeyList::new(keys.iter().map(|key| key.to_string()).collect())
+    }
+
+    #[test]
+    fn test_contains() {
+        let list = key_list(&["content.blake2.aaa", "content.blake2.bbb"]);
+        assert!(list.contains("content.blake2.aaa"));
+        assert!(list.contains("content.blake2.bbb"));
+        assert!(!list.contains("content.blake2.ccc"));
+        assert!(!list.contains("content.blake2"));
+        assert!(!key_list(&[]).contains("content.blake2.aaa"));
+    }
+
+    #[test]
+    fn test_intersect() {
+        let a = key_list(&["k1", "k2", "k3", "k4"]);
+        let b = key_list(&["k5", "k3", "k1"]);
+
+        assert_eq!(a.intersect(&b), key_list(&["k1", "k3"]));
+        assert_eq!(b.intersect(&a), key_list(&["k3", "k1"]));
+        assert!(a.intersect(&b).contains("k3"));
+        assert!(!a.intersect(&b).contains("k2"));
+        assert_eq!(a.intersect(&key_list(&[])), key_list(&[]));
+        assert_eq!(a.intersect(&a), a);
+    }
+
+    #[test]
+    fn test_diff() {
+        let previous = key_list(&["k4", "k1", "k2", "k1"]);
+        let current = key_list(&["k2", "k5", "k3", "k4"]);
+
+        let diff = current.diff(&previous);
+        assert_eq!(
+            diff,
+            RedactionDiff {
+                added: vec!["k3".to_string(), "k5".to_string()],
+                removed: vec!["k1".to_string()],
+            }
+        );
+
+        // Diffing the other way swaps added and removed.
+        let reverse = previous.diff(&current);
+        assert_eq!(reverse.added, diff.removed);
+        assert_eq!(reverse.removed, diff.added);
+
+        // Reordering keys is not a change.
+        assert!(
+            current
+                .diff(&key_list(&["k5", "k4", "k3", "k2"]))
+                .is_empty()
+        );
+        assert!(current.diff(&current).is_empty());
+    }
+
+    #[test]
+    fn test_blob_roundtrip() {
+        let list = key_list(&["k1", "k2"]);
+        let roundtripped = RedactionKeyList::from_blob(list.clone().into_blob()).unwrap();
+        assert_eq!(list, roundtripped);
+        assert!(roundtripped.contains("k2"));
+    }
+}
@@ -67,6 +67,13 @@ impl RepositoryId {
         // Generate repo0001, repo0002, etc.
         format!("{}{:04}{}", REPO_ID_PREFIX, self.0, REPO_ID_SUFFIX)
     }
+
+    /// Extract the repository from a key that starts with a repo prefix, as generated by
+    /// `prefix`. Returns `None` if the key doesn't have a repo prefix.
+    pub fn from_prefixed_key(key: &str) -> Option<Self> {
+        let captures = REPO_PREFIX_REGEX.captures(key)?;
+        captures[1].parse().ok()
+    }
 }
 
 impl fmt::Display for RepositoryId {
@@ -132,4 +139,28 @@ mod test {
         assert!(!REPO_PREFIX_REGEX.is_match("repo00."));
         assert!(!REPO_PREFIX_REGEX.is_match("repo000."));
     }
+
+    #[test]
+    fn from_prefixed_key() {
+        for id in [0, 1, 456, 9999, 12000] {
+            let repo_id = RepositoryId::new(id);
+            let key = format!("{}content.blake2.abc", repo_id.prefix());
+            assert_eq!(RepositoryId::from_prefixed_key(&key), Some(repo_id));
+            assert_eq!(
+                RepositoryId::from_prefixed_key(&repo_id.prefix()),
+                Some(repo_id)
+            );
+        }
+
+        assert_eq!(RepositoryId::from_prefixed_key("content.blake2.abc"), None);
+        assert_eq!(RepositoryId::from_prefixed_key("repo00.content"), None);
+        assert_eq!(
+            RepositoryId::from_prefixed_key("flat/repo0000.content"),
+            None
+        );
+        assert_eq!(
+            RepositoryId::from_prefixed_key("repo99999999999.content"),
+            None
+        );
+    }
 }
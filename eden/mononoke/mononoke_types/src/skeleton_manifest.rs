@@ -106,6 +106,31 @@ impl SkeletonManifest {
         self.summary.child_case_conflicts || self.summary.descendant_case_conflicts
     }
 
+    /// Returns true if `path`, relative to this manifest, exists as either a file or a
+    /// directory. Returns false as soon as a path element is missing.
+    pub async fn contains_path<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        blobstore: &'a impl Blobstore,
+        path: &'a MPath,
+    ) -> Result<bool> {
+        let mut sk_mf = Cow::Borrowed(self);
+        let mut elements = path.into_iter().peekable();
+        while let Some(element) = elements.next() {
+            match sk_mf.subentries.get(element) {
+                None => return Ok(false),
+                Some(SkeletonManifestEntry::File) => return Ok(elements.peek().is_none()),
+                Some(SkeletonManifestEntry::Directory(subdir)) => {
+                    if elements.peek().is_none() {
+                        return Ok(true);
+                    }
+                    sk_mf = Cow::Owned(subdir.id.load(ctx, blobstore).await?);
+                }
+            }
+        }
+        Ok(true)
+    }
+
     pub async fn first_case_conflict<'a>(
         &'a self,
         ctx: &'a CoreContext,
@@ -476,3 +501,56 @@ impl BlobstoreValue for SkeletonManifest {
         Self::from_bytes(blob.data().as_ref())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use blobstore::Storable;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
+    use sorted_vector_map::sorted_vector_map;
+
+    use super::*;
+
+    #[fbinit::test]
+    async fn test_contains_path(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let blobstore = Memblob::default();
+        borrowed!(ctx, blobstore);
+        let element = |name: &str| MPathElement::new(name.as_bytes().to_vec()).unwrap();
+
+        let dir = SkeletonManifest::new(
+            sorted_vector_map! { element("file") => SkeletonManifestEntry::File },
+            Default::default(),
+        );
+        let dir_id = dir.into_blob().store(ctx, blobstore).await?;
+        let root = SkeletonManifest::new(
+            sorted_vector_map! {
+                element("dir") => SkeletonManifestEntry::Directory(
+                    SkeletonManifestDirectory::new(dir_id, Default::default()),
+                ),
+                element("top") => SkeletonManifestEntry::File,
+            },
+            Default::default(),
+        );
+
+        for (path, expected) in [
+            ("dir/file", true),
+            ("dir", true),
+            ("top", true),
+            ("dir/absent", false),
+            ("absent/file", false),
+            ("top/below", false),
+            ("dir/file/below", false),
+        ] {
+            let path = MPath::new(path)?;
+            assert_eq!(
+                root.contains_path(ctx, blobstore, &path).await?,
+                expected,
+                "{}",
+                path
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -5,6 +5,8 @@
  * GNU General Public License version 2.
  */
 
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Display;
 use std::str;
@@ -12,12 +14,19 @@ use std::str::FromStr;
 
 use abomonation_derive::Abomonation;
 use anyhow::bail;
+use anyhow::Context;
 use anyhow::Error;
 use anyhow::Result;
 use sql::mysql;
 
+use crate::errors::ErrorKind;
+use crate::typed_hash::ChangesetId;
 use crate::BonsaiChangeset;
 
+/// The changeset extra recording the SVN revision a changeset was imported from, in the
+/// form `svn:uuid/path@1234`.
+pub const SVNREV_EXTRA: &str = "convert_revision";
+
 // Changeset svnrev. Present only in some repos which were imported from SVN.
 #[derive(Abomonation, Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
 #[derive(mysql::OptTryFromRowField)]
@@ -34,6 +43,30 @@ impl Svnrev {
         self.0
     }
 
+    /// The Svnrev immediately following this one.
+    #[inline]
+    pub fn next(&self) -> Self {
+        Self(self.0 + 1)
+    }
+
+    /// The Svnrev immediately preceding this one, or `None` if this is the
+    /// smallest possible Svnrev.
+    #[inline]
+    pub fn prev(&self) -> Option<Self> {
+        self.0.checked_sub(1).map(Self)
+    }
+
+    /// The decimal form of this Svnrev, left-padded with zeros to at least `width` digits.
+    /// Values that already have `width` digits or more are not truncated.
+    pub fn to_padded_string(&self, width: usize) -> String {
+        format!("{:0width$}", self.0, width = width)
+    }
+
+    /// Iterate over the half-open range of Svnrevs `[start, end)`.
+    pub fn range(start: Svnrev, end: Svnrev) -> impl Iterator<Item = Svnrev> {
+        (start.0..end.0).map(Self)
+    }
+
     // ex. svn:uuid/path@1234
     pub fn parse_svnrev(svnrev: &str) -> Result<u64> {
         let at_pos = svnrev
@@ -43,12 +76,30 @@ impl Svnrev {
         Ok(result)
     }
 
-    pub fn from_bcs(bcs: &BonsaiChangeset) -> Result<Self> {
-        match bcs.extra().find(|(key, _)| key == &"convert_revision") {
-            Some((_, svnrev)) => {
-                let svnrev = Svnrev::parse_svnrev(str::from_utf8(svnrev)?)?;
-                Ok(Self::new(svnrev))
+    /// Read the svnrev from the `SVNREV_EXTRA` extra of `bcs`. Returns `None` if there is no
+    /// such extra, and fails if it doesn't end in a revision number.
+    pub fn from_bonsai_changeset(bcs: &BonsaiChangeset) -> Result<Option<Self>> {
+        match bcs.extra().find(|(key, _)| key == &SVNREV_EXTRA) {
+            Some((_, value)) => {
+                let svnrev = str::from_utf8(value)
+                    .map_err(Error::from)
+                    .and_then(Svnrev::parse_svnrev)
+                    .with_context(|| {
+                        format!(
+                            "Malformed {} extra: {:?}",
+                            SVNREV_EXTRA,
+                            String::from_utf8_lossy(value)
+                        )
+                    })?;
+                Ok(Some(Self::new(svnrev)))
             }
+            None => Ok(None),
+        }
+    }
+
+    pub fn from_bcs(bcs: &BonsaiChangeset) -> Result<Self> {
+        match Self::from_bonsai_changeset(bcs)? {
+            Some(svnrev) => Ok(svnrev),
             None => bail!("Bonsai cs {:?} without svnrev", bcs),
         }
     }
@@ -67,3 +118,169 @@ impl FromStr for Svnrev {
         u64::from_str(s).map(Svnrev::new)
     }
 }
+
+/// An in-memory mapping between svnrevs and changesets, which can be looked up in either
+/// direction. Each svnrev maps to exactly one changeset, and vice versa.
+#[derive(Clone, Debug, Default)]
+pub struct SvnrevMap {
+    by_svnrev: HashMap<Svnrev, ChangesetId>,
+    by_changeset: HashMap<ChangesetId, Svnrev>,
+}
+
+impl SvnrevMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a map from `(svnrev, changeset)` pairs, failing if any svnrev or changeset
+    /// appears more than once.
+    pub fn from_pairs(pairs: impl IntoIterator<Item = (Svnrev, ChangesetId)>) -> Result<Self> {
+        let mut map = Self::new();
+        for (svnrev, cs_id) in pairs {
+            map.insert(svnrev, cs_id)?;
+        }
+        Ok(map)
+    }
+
+    /// Add a mapping. Fails, leaving the map unchanged, if either the svnrev or the changeset
+    /// is already mapped.
+    pub fn insert(&mut self, svnrev: Svnrev, cs_id: ChangesetId) -> Result<()> {
+        if self.by_changeset.contains_key(&cs_id) {
+            bail!(ErrorKind::DuplicateSvnrevChangeset(cs_id));
+        }
+        match self.by_svnrev.entry(svnrev) {
+            Entry::Occupied(_) => bail!(ErrorKind::DuplicateSvnrev(svnrev)),
+            Entry::Vacant(entry) => {
+                entry.insert(cs_id);
+            }
+        }
+        self.by_changeset.insert(cs_id, svnrev);
+        Ok(())
+    }
+
+    pub fn by_svnrev(&self, svnrev: Svnrev) -> Option<ChangesetId> {
+        self.by_svnrev.get(&svnrev).copied()
+    }
+
+    pub fn by_changeset(&self, cs_id: &ChangesetId) -> Option<Svnrev> {
+        self.by_changeset.get(cs_id).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_svnrev.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_svnrev.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sorted_vector_map::SortedVectorMap;
+
+    use super::*;
+    use crate::BonsaiChangesetMut;
+    use crate::DateTime;
+
+    #[test]
+    fn test_next_prev() {
+        let rev = Svnrev::new(5);
+        assert_eq!(rev.next(), Svnrev::new(6));
+        assert_eq!(rev.prev(), Some(Svnrev::new(4)));
+        assert_eq!(rev.next().prev(), Some(rev));
+        assert_eq!(Svnrev::new(0).prev(), None);
+    }
+
+    #[test]
+    fn test_to_padded_string() {
+        assert_eq!(Svnrev::new(42).to_padded_string(6), "000042");
+        assert_eq!(Svnrev::new(0).to_padded_string(3), "000");
+        assert_eq!(Svnrev::new(123456).to_padded_string(6), "123456");
+        assert_eq!(Svnrev::new(1234567).to_padded_string(3), "1234567");
+        assert_eq!(Svnrev::new(42).to_padded_string(0), "42");
+    }
+
+    #[test]
+    fn test_range() {
+        let revs: Vec<_> = Svnrev::range(Svnrev::new(3), Svnrev::new(6)).collect();
+        assert_eq!(revs, vec![Svnrev::new(3), Svnrev::new(4), Svnrev::new(5)]);
+
+        assert_eq!(Svnrev::range(Svnrev::new(3), Svnrev::new(3)).count(), 0);
+        assert_eq!(Svnrev::range(Svnrev::new(6), Svnrev::new(3)).count(), 0);
+    }
+
+    fn changeset_with_extra(extra: Option<&str>) -> BonsaiChangeset {
+        let mut extras = SortedVectorMap::new();
+        extras.insert("branch".to_owned(), b"default".to_vec());
+        if let Some(value) = extra {
+            extras.insert(SVNREV_EXTRA.to_owned(), value.as_bytes().to_vec());
+        }
+        BonsaiChangesetMut {
+            parents: vec![],
+            author: "author".to_owned(),
+            author_date: DateTime::from_timestamp(0, 0).unwrap(),
+            committer: None,
+            committer_date: None,
+            message: "message".to_owned(),
+            extra: extras,
+            file_changes: SortedVectorMap::new(),
+            is_snapshot: false,
+        }
+        .freeze()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_from_bonsai_changeset() -> Result<()> {
+        let cs = changeset_with_extra(Some("svn:uuid/repo/trunk@1234"));
+        assert_eq!(Svnrev::from_bonsai_changeset(&cs)?, Some(Svnrev::new(1234)));
+        assert_eq!(Svnrev::from_bcs(&cs)?, Svnrev::new(1234));
+
+        let cs = changeset_with_extra(None);
+        assert_eq!(Svnrev::from_bonsai_changeset(&cs)?, None);
+        assert!(Svnrev::from_bcs(&cs).is_err());
+
+        for malformed in ["svn:uuid/repo/trunk@abc", "svn:uuid/repo/trunk", ""] {
+            let cs = changeset_with_extra(Some(malformed));
+            assert!(Svnrev::from_bonsai_changeset(&cs).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_svnrev_map() -> Result<()> {
+        let cs1 = ChangesetId::from_byte_array([1; 32]);
+        let cs2 = ChangesetId::from_byte_array([2; 32]);
+        let cs3 = ChangesetId::from_byte_array([3; 32]);
+
+        let map = SvnrevMap::from_pairs(vec![(Svnrev::new(10), cs1), (Svnrev::new(11), cs2)])?;
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.by_svnrev(Svnrev::new(10)), Some(cs1));
+        assert_eq!(map.by_svnrev(Svnrev::new(11)), Some(cs2));
+        assert_eq!(map.by_svnrev(Svnrev::new(12)), None);
+        assert_eq!(map.by_changeset(&cs1), Some(Svnrev::new(10)));
+        assert_eq!(map.by_changeset(&cs2), Some(Svnrev::new(11)));
+        assert_eq!(map.by_changeset(&cs3), None);
+
+        let err = SvnrevMap::from_pairs(vec![(Svnrev::new(10), cs1), (Svnrev::new(10), cs2)])
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::DuplicateSvnrev(rev)) if *rev == Svnrev::new(10)
+        ));
+
+        let mut map = map;
+        let err = map.insert(Svnrev::new(12), cs1).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(ErrorKind::DuplicateSvnrevChangeset(cs)) if *cs == cs1
+        ));
+        assert_eq!(map.by_svnrev(Svnrev::new(12)), None);
+        map.insert(Svnrev::new(12), cs3)?;
+        assert_eq!(map.by_changeset(&cs3), Some(Svnrev::new(12)));
+
+        Ok(())
+    }
+}
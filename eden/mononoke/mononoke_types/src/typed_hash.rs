@@ -5,6 +5,7 @@
  * GNU General Public License version 2.
  */
 
+use std::borrow::Borrow;
 use std::fmt;
 use std::fmt::Debug;
 use std::fmt::Display;
@@ -13,6 +14,7 @@ use std::result;
 use std::str::FromStr;
 
 use abomonation_derive::Abomonation;
+use anyhow::bail;
 use anyhow::Result;
 use async_trait::async_trait;
 use blobstore::Blobstore;
@@ -33,11 +35,14 @@ use crate::content_chunk::ContentChunk;
 use crate::content_metadata::ContentMetadata;
 use crate::content_metadata_v2::ContentMetadataV2;
 use crate::deleted_manifest_v2::DeletedManifestV2;
+use crate::errors::ErrorKind;
+use crate::errors::PrefixResolutionError;
 use crate::fastlog_batch::FastlogBatch;
 use crate::file_contents::FileContents;
 use crate::fsnode::Fsnode;
 use crate::hash::Blake2;
 use crate::hash::Blake2Prefix;
+use crate::hash::BLAKE2_HASH_LENGTH_BYTES;
 use crate::rawbundle2::RawBundle2;
 use crate::redaction_key_list::RedactionKeyList;
 use crate::sharded_map::ShardedMapNode;
@@ -98,6 +103,25 @@ pub enum ChangesetIdsResolvedFromPrefix {
     NoMatch,
 }
 
+/// The most matches `ChangesetIdsResolvedFromPrefix::into_unique` reports for an ambiguous prefix.
+pub const AMBIGUOUS_PREFIX_SAMPLE_SIZE: usize = 10;
+
+impl ChangesetIdsResolvedFromPrefix {
+    /// Returns the changeset the prefix resolved to, or an error if it resolved to none or to
+    /// several of them.
+    pub fn into_unique(self) -> Result<ChangesetId, PrefixResolutionError> {
+        match self {
+            ChangesetIdsResolvedFromPrefix::Single(cs_id) => Ok(cs_id),
+            ChangesetIdsResolvedFromPrefix::Multiple(mut cs_ids)
+            | ChangesetIdsResolvedFromPrefix::TooMany(mut cs_ids) => {
+                cs_ids.truncate(AMBIGUOUS_PREFIX_SAMPLE_SIZE);
+                Err(PrefixResolutionError::Ambiguous(cs_ids))
+            }
+            ChangesetIdsResolvedFromPrefix::NoMatch => Err(PrefixResolutionError::NoMatch),
+        }
+    }
+}
+
 /// An identifier for file contents in Mononoke.
 #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug, Hash)]
 pub struct ContentId(Blake2);
@@ -277,6 +301,15 @@ macro_rules! impl_typed_hash_no_context {
             }
         }
 
+        // Typed hashes hash and compare exactly like their raw bytes, so maps keyed by them can
+        // be queried with a `&[u8]` (for example from a `TypedHashRef`) without building an id.
+        impl std::borrow::Borrow<[u8]> for $typed {
+            #[inline]
+            fn borrow(&self) -> &[u8] {
+                self.0.as_ref()
+            }
+        }
+
         impl TryFrom<$crate::private::Bytes> for $typed {
             type Error = $crate::private::anyhow::Error;
             #[inline]
@@ -505,6 +538,36 @@ impl_typed_hash! {
 
 impl_edenapi_hash_convert!(ContentId, EdenapiContentId);
 
+/// A textual encoding of a hash.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HashEncoding {
+    /// Lowercase hex, as used by `Display` and `FromStr`.
+    Hex,
+    /// Standard base64, without padding.
+    Base64,
+}
+
+impl ContentId {
+    /// Parse a content id from a string in the given encoding.
+    pub fn from_encoded(s: &str, encoding: HashEncoding) -> Result<Self> {
+        match encoding {
+            HashEncoding::Hex => Self::from_str(s),
+            HashEncoding::Base64 => {
+                let bytes = base64::decode_config(s, base64::STANDARD_NO_PAD)?;
+                Self::from_bytes(bytes)
+            }
+        }
+    }
+
+    /// Render this content id as a string in the given encoding.
+    pub fn to_encoded(&self, encoding: HashEncoding) -> String {
+        match encoding {
+            HashEncoding::Hex => self.to_hex().to_string(),
+            HashEncoding::Base64 => base64::encode_config(self.as_ref(), base64::STANDARD_NO_PAD),
+        }
+    }
+}
+
 impl_typed_hash! {
     hash_type => ContentChunkId,
     thrift_hash_type => thrift::ContentChunkId,
@@ -651,6 +714,37 @@ impl MononokeId for ContentMetadataV2Id {
     }
 }
 
+/// A borrowed view of the raw bytes of a typed hash, such as one read out of a larger buffer.
+/// Maps keyed by any typed hash can be queried with `map.get(view.as_bytes())`, which avoids
+/// copying the bytes into an owned id first.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct TypedHashRef<'a>(&'a [u8]);
+
+impl<'a> TypedHashRef<'a> {
+    /// Construct a view of `bytes`, which must be exactly as long as a BLAKE2b hash.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self> {
+        if bytes.len() != BLAKE2_HASH_LENGTH_BYTES {
+            bail!(ErrorKind::InvalidBlake2Input(format!(
+                "need exactly {} bytes",
+                BLAKE2_HASH_LENGTH_BYTES
+            )));
+        }
+        Ok(TypedHashRef(bytes))
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+}
+
+impl<'a> Borrow<[u8]> for TypedHashRef<'a> {
+    #[inline]
+    fn borrow(&self) -> &[u8] {
+        self.0
+    }
+}
+
 impl ChangesetIdPrefix {
     pub const fn new(blake2prefix: Blake2Prefix) -> Self {
         ChangesetIdPrefix(blake2prefix)
@@ -660,6 +754,19 @@ impl ChangesetIdPrefix {
         Blake2Prefix::from_bytes(bytes).map(Self::new)
     }
 
+    /// Construct a prefix from a hex string. Odd-length strings are accepted,
+    /// in which case the last hex digit only constrains the high half of the
+    /// final byte.
+    pub fn from_hex(s: &str) -> Result<Self> {
+        Blake2Prefix::from_str(s).map(Self::new)
+    }
+
+    /// Returns true if `id` starts with this prefix.
+    #[inline]
+    pub fn matches(&self, id: &ChangesetId) -> bool {
+        self.min_bound() <= *id && *id <= self.max_bound()
+    }
+
     #[inline]
     pub fn min_as_ref(&self) -> &[u8] {
         self.0.min_as_ref()
@@ -701,11 +808,40 @@ impl Display for ChangesetIdPrefix {
 
 #[cfg(test)]
 mod test {
+    use std::collections::BTreeMap;
+    use std::collections::HashMap;
+
     use bytes::Bytes;
     use quickcheck::quickcheck;
 
     use super::*;
 
+    #[test]
+    fn changeset_prefix_into_unique() {
+        let ids: Vec<_> = (0..20u8)
+            .map(|i| ChangesetId::from_byte_array([i; 32]))
+            .collect();
+
+        assert_eq!(
+            ChangesetIdsResolvedFromPrefix::Single(ids[0]).into_unique(),
+            Ok(ids[0])
+        );
+        assert_eq!(
+            ChangesetIdsResolvedFromPrefix::NoMatch.into_unique(),
+            Err(PrefixResolutionError::NoMatch)
+        );
+        assert_eq!(
+            ChangesetIdsResolvedFromPrefix::Multiple(ids[..3].to_vec()).into_unique(),
+            Err(PrefixResolutionError::Ambiguous(ids[..3].to_vec()))
+        );
+        match ChangesetIdsResolvedFromPrefix::TooMany(ids.clone()).into_unique() {
+            Err(PrefixResolutionError::Ambiguous(sample)) => {
+                assert_eq!(sample, ids[..AMBIGUOUS_PREFIX_SAMPLE_SIZE].to_vec());
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
     quickcheck! {
         fn changesetid_thrift_roundtrip(h: ChangesetId) -> bool {
             let v = h.into_thrift();
@@ -722,6 +858,30 @@ mod test {
         }
     }
 
+    quickcheck! {
+        fn contentid_encoded_roundtrip(h: ContentId) -> bool {
+            [HashEncoding::Hex, HashEncoding::Base64].iter().all(|encoding| {
+                let s = h.to_encoded(*encoding);
+                ContentId::from_encoded(&s, *encoding).ok() == Some(h)
+            })
+        }
+    }
+
+    #[test]
+    fn contentid_encoded() {
+        let id = ContentId::new(Blake2::from_byte_array([1; 32]));
+        assert_eq!(id.to_encoded(HashEncoding::Hex), id.to_string());
+        assert_eq!(
+            id.to_encoded(HashEncoding::Base64),
+            "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE"
+        );
+
+        // Valid base64, but of 31 bytes rather than 32.
+        let short = base64::encode_config(&[1u8; 31], base64::STANDARD_NO_PAD);
+        assert!(ContentId::from_encoded(&short, HashEncoding::Base64).is_err());
+        assert!(ContentId::from_encoded("not base64!", HashEncoding::Base64).is_err());
+    }
+
     #[test]
     fn thrift_convert_bytes_consistent_for_ids() {
         let id = ShardedMapNodeDMv2Id::from_byte_array([1; 32]);
@@ -808,6 +968,58 @@ mod test {
         );
     }
 
+    #[test]
+    fn typed_hash_ref_lookup() {
+        let present = ContentId::from_byte_array([3; 32]);
+        let absent = ContentId::from_byte_array([4; 32]);
+        let map: HashMap<ContentId, &str> = HashMap::from([(present, "present")]);
+        let sorted: BTreeMap<ContentId, &str> = map.clone().into_iter().collect();
+
+        // A buffer holding hashes back to back, as they might be read from storage.
+        let mut buf = Vec::new();
+        buf.extend_from_slice(present.blake2().as_ref());
+        buf.extend_from_slice(absent.blake2().as_ref());
+
+        let view = TypedHashRef::from_bytes(&buf[..32]).unwrap();
+        assert_eq!(map.get(view.as_bytes()), Some(&"present"));
+        assert_eq!(sorted.get(view.as_bytes()), Some(&"present"));
+
+        let view = TypedHashRef::from_bytes(&buf[32..]).unwrap();
+        assert_eq!(map.get(view.as_bytes()), None);
+        assert_eq!(sorted.get(view.as_bytes()), None);
+
+        assert!(TypedHashRef::from_bytes(&buf[..31]).is_err());
+        assert!(TypedHashRef::from_bytes(&buf).is_err());
+    }
+
+    #[test]
+    fn changeset_id_prefix_matches() {
+        let id = ChangesetId::from_str(
+            "0e5751c026ed9d5b0c4ab4b28d8d4d06bc0f4a1b5f7e8e4d0a7b6a1b1c2d3e4f",
+        )
+        .unwrap();
+
+        // Even length prefixes.
+        assert!(ChangesetIdPrefix::from_hex("").unwrap().matches(&id));
+        assert!(ChangesetIdPrefix::from_hex("0e5751").unwrap().matches(&id));
+        assert!(!ChangesetIdPrefix::from_hex("0e5752").unwrap().matches(&id));
+        assert!(
+            ChangesetIdPrefix::from_hex(&id.to_string())
+                .unwrap()
+                .matches(&id)
+        );
+
+        // Odd length prefixes.
+        assert!(ChangesetIdPrefix::from_hex("0").unwrap().matches(&id));
+        assert!(ChangesetIdPrefix::from_hex("0e5751c").unwrap().matches(&id));
+        assert!(!ChangesetIdPrefix::from_hex("0e5751d").unwrap().matches(&id));
+        assert!(!ChangesetIdPrefix::from_hex("1").unwrap().matches(&id));
+
+        // Prefixes that are too long or not hex are rejected.
+        assert!(ChangesetIdPrefix::from_hex(&format!("{}0", id)).is_err());
+        assert!(ChangesetIdPrefix::from_hex("0g").is_err());
+    }
+
     #[test]
     fn test_serialize_deserialize() {
         let id = ChangesetId::new(Blake2::from_byte_array([1; 32]));
@@ -358,7 +358,7 @@ async fn test_service_access(fb: FacebookInit) -> Result<()> {
                 ServiceWriteRestrictions {
                     permitted_methods: hashset! { String::from("create_changeset") },
                     permitted_bookmarks: hashset! { String::from("main") },
-                    permitted_path_prefixes: PrefixTrie::Included,
+                    permitted_path_prefixes: PrefixTrie::everything(),
                     ..Default::default()
                 }
             };
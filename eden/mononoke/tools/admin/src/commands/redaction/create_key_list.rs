@@ -99,7 +99,7 @@ async fn create_key_list(
     let redaction_blobstore = app.redaction_config_blobstore().await?;
     let darkstorm_blobstore = app.redaction_config_blobstore_for_darkstorm().await?;
 
-    let blob = RedactionKeyList { keys }.into_blob();
+    let blob = RedactionKeyList::new(keys).into_blob();
     let (id1, id2) = try_join(
         blob.clone().store(ctx, &redaction_blobstore),
         blob.store(ctx, &darkstorm_blobstore),